@@ -0,0 +1,305 @@
+// © Marcel Müller 2025, licensed under the EUPL
+
+//! Verifying the files in a tar or zip archive, like a published release tarball, without
+//! unpacking it
+
+use std::collections::HashMap;
+use std::io::BufReader;
+use std::io::Read;
+use std::io::Seek;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+
+use camino::Utf8Path;
+use camino::Utf8PathBuf;
+use flate2::read::GzDecoder;
+use miette::Context;
+use miette::IntoDiagnostic;
+use miette::miette;
+use tracing::debug;
+
+use crate::CheckOptions;
+use crate::Config;
+use crate::FileFilter;
+use crate::Language;
+use crate::parallel;
+use crate::report::Finding;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+/// Zip archives start with a local file header, or the end of the central directory if empty
+const ZIP_MAGICS: [&[u8]; 2] = [b"PK\x03\x04", b"PK\x05\x06"];
+
+/// A regular file in an archive
+#[derive(Debug)]
+struct Entry {
+    path: Utf8PathBuf,
+    content: Vec<u8>,
+}
+
+/// Verifies the files of configured languages in the tar or zip archive at `archive`, returning
+/// whether all of them are valid
+///
+/// Only the entries kept by `filter` are read, and they are verified on `--jobs` threads. With
+/// `ignore_missing_grammar` the entries of languages without a grammar are skipped.
+pub fn check(
+    langs: &HashMap<String, Language>,
+    config: &Config,
+    archive: &Utf8Path,
+    filter: &FileFilter,
+    ignore_missing_grammar: bool,
+    options: &CheckOptions,
+) -> Result<bool, miette::Error> {
+    let file = std::fs::File::open(archive)
+        .into_diagnostic()
+        .with_context(|| miette!("Could not read the archive {archive}"))?;
+    let entries = entries(file, |path, size| {
+        if crate::language_by_file_endings(config, path).is_none() {
+            debug!("Skipping {path}, as it does not belong to a configured language");
+            return false;
+        }
+        if ignore_missing_grammar && crate::lacks_grammar(langs, config, path) {
+            return false;
+        }
+        filter.keeps(path, Some(size))
+    })
+    .with_context(|| miette!("Could not read the archive {archive}"))?;
+
+    // Set once an entry failed with --fail-fast, so that no further entries are reported
+    let stopped = AtomicBool::new(false);
+    let results = parallel::map(&entries, options.jobs, |entry| {
+        let file = entry.path.as_path();
+        if stopped.load(Ordering::Relaxed) {
+            debug!("Not checking {file}, as another file already failed");
+            return Ok(None);
+        }
+        let Ok(content) = std::str::from_utf8(&entry.content) else {
+            debug!("Skipping {file}, as it is not valid UTF-8");
+            return Ok(None);
+        };
+
+        debug!("Checking {file} in {archive}");
+        let status = crate::verify_content(langs, config, file, content)?;
+        if status.is_valid() || !options.fail_on.fails(status.outcome()) {
+            return Ok(None);
+        }
+        if options.fail_fast && stopped.swap(true, Ordering::Relaxed) {
+            return Ok(None);
+        }
+
+        if options.prints_results() {
+            let explanation = options
+                .explain
+                .then(|| crate::explain::explain(config, file, &status));
+            crate::report_invalid_file(file, &status, options.color, explanation.as_deref());
+        }
+        Ok(Some(Finding {
+            path: entry.path.clone(),
+            outcome: status.outcome(),
+            message: status.problem().into_owned(),
+            line: 1,
+        }))
+    })?;
+
+    let findings = results.into_iter().flatten().collect::<Vec<_>>();
+    crate::print_findings(&findings, options)?;
+    Ok(findings.is_empty())
+}
+
+/// The regular files of a zip, tar or gzip compressed tar archive for which `keep` returns true
+///
+/// `keep` is given the path and size of every file, so that only the kept ones are read.
+fn entries(
+    mut archive: impl Read + Seek,
+    keep: impl Fn(&Utf8Path, u64) -> bool,
+) -> Result<Vec<Entry>, miette::Error> {
+    let mut magic = vec![];
+    (&mut archive)
+        .take(4)
+        .read_to_end(&mut magic)
+        .into_diagnostic()?;
+    archive.rewind().into_diagnostic()?;
+
+    if ZIP_MAGICS.iter().any(|zip| magic.starts_with(zip)) {
+        zip_entries(archive, keep)
+    } else if magic.starts_with(GZIP_MAGIC) {
+        tar_entries(GzDecoder::new(BufReader::new(archive)), keep)
+    } else {
+        tar_entries(BufReader::new(archive), keep)
+    }
+}
+
+fn tar_entries(
+    archive: impl Read,
+    keep: impl Fn(&Utf8Path, u64) -> bool,
+) -> Result<Vec<Entry>, miette::Error> {
+    let mut archive = tar::Archive::new(archive);
+    let mut entries = vec![];
+    for entry in archive.entries().into_diagnostic()? {
+        let mut entry = entry.into_diagnostic()?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry.path().into_diagnostic()?;
+        let Some(path) = path.to_str().map(Utf8PathBuf::from) else {
+            debug!(
+                "Skipping {}, as its name is not valid UTF-8",
+                path.display()
+            );
+            continue;
+        };
+        if !keep(&path, entry.size()) {
+            continue;
+        }
+
+        let mut content = vec![];
+        entry
+            .read_to_end(&mut content)
+            .into_diagnostic()
+            .with_context(|| miette!("Could not read {path}"))?;
+        entries.push(Entry { path, content });
+    }
+    Ok(entries)
+}
+
+fn zip_entries(
+    archive: impl Read + Seek,
+    keep: impl Fn(&Utf8Path, u64) -> bool,
+) -> Result<Vec<Entry>, miette::Error> {
+    let mut archive = zip::ZipArchive::new(archive).into_diagnostic()?;
+    let mut entries = vec![];
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).into_diagnostic()?;
+        if !entry.is_file() {
+            continue;
+        }
+        // Names escaping the archive, like `../main.rs`, are not enclosed
+        let Some(path) = entry
+            .enclosed_name()
+            .and_then(|path| Utf8PathBuf::from_path_buf(path).ok())
+        else {
+            debug!(
+                "Skipping {}, as its name is not a valid path",
+                entry.name().unwrap_or_default()
+            );
+            continue;
+        };
+        if !keep(&path, entry.size()) {
+            continue;
+        }
+
+        let mut content = vec![];
+        entry
+            .read_to_end(&mut content)
+            .into_diagnostic()
+            .with_context(|| miette!("Could not read {path}"))?;
+        entries.push(Entry { path, content });
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::io::Write;
+
+    use super::*;
+
+    const FILES: [(&str, &str); 2] = [("src/main.rs", "fn main() {}\n"), ("README", "Hello\n")];
+
+    fn tar_archive() -> Vec<u8> {
+        let mut builder = tar::Builder::new(vec![]);
+        for (path, content) in FILES {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            builder
+                .append_data(&mut header, path, content.as_bytes())
+                .unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    fn zip_archive() -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(vec![]));
+        writer
+            .add_directory("src", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        for (path, content) in FILES {
+            writer
+                .start_file(path, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn read_all(archive: Vec<u8>) -> Vec<(String, String)> {
+        entries(Cursor::new(archive), |_, _| true)
+            .unwrap()
+            .into_iter()
+            .map(|entry| {
+                (
+                    entry.path.into_string(),
+                    String::from_utf8(entry.content).unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    fn expected() -> Vec<(String, String)> {
+        FILES
+            .iter()
+            .map(|(path, content)| (path.to_string(), content.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn tar_archives_are_read() {
+        assert_eq!(read_all(tar_archive()), expected());
+    }
+
+    #[test]
+    fn gzip_compressed_tar_archives_are_read() {
+        assert_eq!(read_all(gzip(&tar_archive())), expected());
+    }
+
+    #[test]
+    fn zip_archives_are_read_without_directories() {
+        assert_eq!(read_all(zip_archive()), expected());
+    }
+
+    #[test]
+    fn only_kept_entries_are_read() {
+        for archive in [tar_archive(), zip_archive()] {
+            let kept = entries(Cursor::new(archive), |path, size| {
+                path.extension() == Some("rs") && size == 13
+            })
+            .unwrap();
+            assert_eq!(kept.len(), 1);
+            assert_eq!(kept[0].path, "src/main.rs");
+        }
+    }
+
+    #[test]
+    fn long_tar_names_are_read() {
+        let path = format!("{}/main.rs", "directory/".repeat(20));
+        let mut builder = tar::Builder::new(vec![]);
+        let mut header = tar::Header::new_gnu();
+        header.set_size(0);
+        builder.append_data(&mut header, &path, &[][..]).unwrap();
+
+        let entries = entries(Cursor::new(builder.into_inner().unwrap()), |_, _| true).unwrap();
+        assert_eq!(entries[0].path, path);
+    }
+
+    #[test]
+    fn other_files_are_errors() {
+        assert!(entries(Cursor::new(b"not an archive".repeat(100)), |_, _| true).is_err());
+    }
+}
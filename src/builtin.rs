@@ -0,0 +1,44 @@
+// © Marcel Müller 2025, licensed under the EUPL
+
+//! Grammars compiled into the binary, selected with the `grammar-*` features
+
+use std::collections::HashMap;
+
+use crate::Language;
+
+/// All grammars this binary was built with, by language name
+pub fn languages() -> HashMap<String, Language> {
+    #[allow(unused_mut)]
+    let mut langs = HashMap::new();
+
+    #[cfg(feature = "grammar-bash")]
+    insert(&mut langs, "bash", tree_sitter_bash::LANGUAGE);
+    #[cfg(feature = "grammar-rust")]
+    insert(&mut langs, "rust", tree_sitter_rust::LANGUAGE);
+    #[cfg(feature = "grammar-toml")]
+    insert(&mut langs, "toml", tree_sitter_toml_ng::LANGUAGE);
+
+    langs
+}
+
+#[cfg(any(
+    feature = "grammar-bash",
+    feature = "grammar-rust",
+    feature = "grammar-toml"
+))]
+fn insert(
+    langs: &mut HashMap<String, Language>,
+    name: &str,
+    language_fn: tree_sitter_language::LanguageFn,
+) {
+    tracing::debug!("Using the built-in grammar {name}");
+    langs.insert(
+        name.to_string(),
+        Language {
+            parsers: std::sync::Mutex::default(),
+            _name: name.to_string(),
+            _library: None,
+            language_fn,
+        },
+    );
+}
@@ -0,0 +1,77 @@
+// © Marcel Müller 2025, licensed under the EUPL
+
+//! Bounded edit distances, to tell headers with a typo from entirely different ones
+
+/// The Levenshtein distance between `a` and `b` in characters, `None` if it exceeds `max`
+///
+/// The computation stops as soon as the distance is known to exceed `max`, so that comparing
+/// entirely different texts stays fast.
+pub fn bounded_levenshtein(a: &str, b: &str, max: usize) -> Option<usize> {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    let mut current = vec![0; b.len() + 1];
+    for (i, a_char) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+
+        // Distances never decrease from one row to the next
+        if current.iter().all(|&distance| distance > max) {
+            return None;
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    Some(previous[b.len()]).filter(|&distance| distance <= max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn equal_texts_have_no_distance() {
+        assert_eq!(
+            bounded_levenshtein("Copyright Foo", "Copyright Foo", 0),
+            Some(0)
+        );
+        assert_eq!(bounded_levenshtein("", "", 0), Some(0));
+    }
+
+    #[test]
+    fn distances_count_edited_characters() {
+        assert_eq!(
+            bounded_levenshtein("Copyright Foo", "Copyrigth Foo", 5),
+            Some(2)
+        );
+        assert_eq!(
+            bounded_levenshtein("Copyright Foo", "Copyright Fo", 5),
+            Some(1)
+        );
+        assert_eq!(bounded_levenshtein("kitten", "sitting", 5), Some(3));
+        assert_eq!(bounded_levenshtein("", "abc", 5), Some(3));
+    }
+
+    #[test]
+    fn distances_are_counted_in_characters() {
+        assert_eq!(bounded_levenshtein("Müller", "Muller", 1), Some(1));
+        assert_eq!(bounded_levenshtein("©", "(c)", 3), Some(3));
+    }
+
+    #[test]
+    fn distances_above_the_bound_are_none() {
+        assert_eq!(bounded_levenshtein("kitten", "sitting", 2), None);
+        assert_eq!(bounded_levenshtein("a", "abcdef", 4), None);
+        assert_eq!(
+            bounded_levenshtein("Copyright Foo", "Licensed under MIT", 3),
+            None
+        );
+    }
+}
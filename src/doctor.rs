@@ -0,0 +1,264 @@
+// © Marcel Müller 2025, licensed under the EUPL
+
+//! Diagnosing the whole setup of lizenz at once
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+use camino::Utf8PathBuf;
+use owo_colors::OwoColorize;
+
+use crate::ColorChoice;
+use crate::Language;
+use crate::LanguageConfig;
+
+/// Prints a checklist of everything lizenz needs to run, returning whether all checks passed
+///
+/// Checks that cannot run because an earlier one failed are reported as skipped.
+pub fn run(
+    config_paths: &[Utf8PathBuf],
+    profile: Option<&str>,
+    grammar_dirs: &[Utf8PathBuf],
+    color: ColorChoice,
+) -> bool {
+    let mut report = Report {
+        color: color.enabled_for(&std::io::stdout()),
+        passed: true,
+    };
+
+    let config = crate::load_configuration(config_paths, profile).map(|mut config| {
+        for (name, lang) in crate::default_languages() {
+            config.languages.entry(name).or_insert(lang);
+        }
+        config
+    });
+    report.check(
+        &format!(
+            "Configuration {} can be loaded",
+            config_paths
+                .iter()
+                .map(|path| path.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        config.as_ref().map(|_| ()).map_err(describe),
+    );
+
+    match &config {
+        Ok(config) => {
+            report.check(
+                "The license text is not empty",
+                if config.license.text.trim().is_empty() {
+                    Err(String::from("[license] text is empty"))
+                } else {
+                    Ok(())
+                },
+            );
+            report.check(
+                "All licenses referenced by languages exist",
+                config.validate_licenses().map_err(|error| describe(&error)),
+            );
+            report.check(
+                "No language configures a comment node kind twice",
+                config.validate_comments().map_err(|error| describe(&error)),
+            );
+            report.check(
+                "All notebook languages exist",
+                config
+                    .validate_notebooks()
+                    .map_err(|error| describe(&error)),
+            );
+        }
+        Err(_) => {
+            report.skip("The license text is not empty");
+            report.skip("All licenses referenced by languages exist");
+            report.skip("No language configures a comment node kind twice");
+            report.skip("All notebook languages exist");
+        }
+    }
+
+    let mut langs = crate::builtin::languages();
+    let grammar_dirs = match crate::find_grammar_dirs(grammar_dirs) {
+        Ok(dirs) => dirs,
+        Err(_) if !langs.is_empty() => {
+            report.skip("A grammar directory exists, only the built-in grammars are used");
+            vec![]
+        }
+        Err(error) => {
+            report.check("A grammar directory exists", Err(error.to_string()));
+            vec![]
+        }
+    };
+
+    let mut loaded_all = true;
+    for dir in grammar_dirs {
+        if !dir.is_dir() {
+            report.check(
+                &format!("The grammar directory {dir} exists"),
+                Err(format!("{dir} is not a directory")),
+            );
+            loaded_all = false;
+            continue;
+        }
+
+        let loaded = crate::load_languages(&dir);
+        report.check(
+            &format!("The grammars in {dir} can be loaded"),
+            loaded.as_ref().map(|_| ()).map_err(describe),
+        );
+        match loaded {
+            Ok(loaded) => {
+                for (name, language) in loaded {
+                    langs.entry(name).or_insert(language);
+                }
+            }
+            Err(_) => loaded_all = false,
+        }
+    }
+    // Missing grammars cannot be told apart from grammars that failed to load
+    let langs = (loaded_all && !langs.is_empty()).then_some(langs);
+
+    let Ok(config) = &config else {
+        report.skip("Every language has a grammar with its node kinds");
+        report.skip("No file matches the endings of multiple languages");
+        return report.passed;
+    };
+
+    // Sorted, so that the report is stable between runs
+    let languages = config.languages.iter().collect::<BTreeMap<_, _>>();
+    match &langs {
+        Some(langs) => {
+            for (name, language_config) in &languages {
+                if language_config.extractor.is_some() {
+                    report.skip(&format!(
+                        "The language {name} has a grammar, it uses an extractor command"
+                    ));
+                    continue;
+                }
+                if let Some(notebook_language) = &language_config.notebook_language {
+                    report.skip(&format!(
+                        "The language {name} has a grammar, its notebooks are checked as {notebook_language}"
+                    ));
+                    continue;
+                }
+                report.check(
+                    &format!("The language {name} has a grammar with its node kinds"),
+                    check_grammar(langs, name, language_config),
+                );
+            }
+        }
+        None => report.skip("Every language has a grammar with its node kinds"),
+    }
+
+    report.check(
+        "No file matches the endings of multiple languages",
+        check_overlapping_endings(&languages),
+    );
+
+    report.passed
+}
+
+struct Report {
+    color: bool,
+    passed: bool,
+}
+
+impl Report {
+    fn check(&mut self, description: &str, result: Result<(), String>) {
+        match result {
+            Ok(()) if self.color => println!("[{}] {description}", "ok".green()),
+            Ok(()) => println!("[ok] {description}"),
+            Err(reason) => {
+                self.passed = false;
+                if self.color {
+                    println!("[{}] {description}", "FAIL".red());
+                } else {
+                    println!("[FAIL] {description}");
+                }
+                for line in reason.lines() {
+                    println!("       {line}");
+                }
+            }
+        }
+    }
+
+    fn skip(&mut self, description: &str) {
+        if self.color {
+            println!("[{}] {description}", "skip".yellow());
+        } else {
+            println!("[skip] {description}");
+        }
+    }
+}
+
+/// The messages of the error and all its causes, one per line
+fn describe(error: &miette::Error) -> String {
+    error
+        .chain()
+        .map(|cause| cause.to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn check_grammar(
+    langs: &HashMap<String, Language>,
+    name: &str,
+    language_config: &LanguageConfig,
+) -> Result<(), String> {
+    let Some(language) = langs.get(name) else {
+        return Err(format!(
+            "No grammar named {name} exists in the grammar directory"
+        ));
+    };
+
+    let grammar = tree_sitter::Language::new(language.language_fn);
+    let unknown = language_config
+        .comments
+        .iter()
+        .map(|conf| conf.tree_sitter_name.as_str())
+        .chain(
+            language_config
+                .declaration
+                .as_ref()
+                .map(|declaration| declaration.node_kind.as_str()),
+        )
+        .chain(language_config.import_node_kind.as_deref())
+        .chain(
+            language_config
+                .comment_containers
+                .iter()
+                .map(String::as_str),
+        )
+        .filter(|kind| grammar.id_for_node_kind(kind, true) == 0)
+        .collect::<Vec<_>>();
+
+    if unknown.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "The grammar has no node kinds named {}",
+            unknown.join(", ")
+        ))
+    }
+}
+
+fn check_overlapping_endings(languages: &BTreeMap<&String, &LanguageConfig>) -> Result<(), String> {
+    let mut owners = BTreeMap::<&str, Vec<&str>>::new();
+    for (name, language_config) in languages {
+        for ending in &language_config.file_endings {
+            owners.entry(ending).or_default().push(name);
+        }
+    }
+
+    let overlaps = owners
+        .iter()
+        .filter(|(_, names)| names.len() > 1)
+        .map(|(ending, names)| format!("{ending} is used by {}", names.join(", ")))
+        .collect::<Vec<_>>();
+
+    if overlaps.is_empty() {
+        Ok(())
+    } else {
+        Err(overlaps.join("\n"))
+    }
+}
@@ -0,0 +1,141 @@
+// © Marcel Müller 2025, licensed under the EUPL
+
+//! Explanations why a file failed verification
+
+use std::fmt::Write;
+
+use camino::Utf8Path;
+
+use crate::CommentKind;
+use crate::Config;
+use crate::FileStatus;
+use crate::MatchMode;
+
+/// Describes how the header of `file` was searched for and where it differs from the license
+pub fn explain(config: &Config, file: &Utf8Path, status: &FileStatus) -> String {
+    let mut explanation = String::new();
+    let Some((name, language_config)) = crate::detect_language(config, file) else {
+        return explanation;
+    };
+
+    let _ = writeln!(explanation, "  language: {name}");
+    for conf in &language_config.comments {
+        let markers = match &conf.comment_kind {
+            CommentKind::Single(prefix) => prefix.clone(),
+            CommentKind::Multi { start, end, .. } => format!("{start} {end}"),
+        };
+        let preferred = if conf.preferred { ", preferred" } else { "" };
+        let _ = writeln!(
+            explanation,
+            "  comment style: {} ({markers}{preferred})",
+            conf.tree_sitter_name
+        );
+    }
+
+    if let Some(timeout) = status.timed_out {
+        let _ = writeln!(
+            explanation,
+            "  header not searched: parsing took longer than {} ms",
+            timeout.as_millis()
+        );
+        return explanation;
+    }
+
+    if !status.has_banner
+        && let Some(banner) = &language_config.required_banner
+    {
+        let _ = writeln!(explanation, "  missing banner: {banner}");
+    }
+
+    if !status.has_footer
+        && let Some(footer) = &language_config.footer
+    {
+        let _ = writeln!(
+            explanation,
+            "  missing footer: the license {footer} at the end"
+        );
+    }
+
+    if let Some(mismatch) = status.year_mismatch {
+        let found = mismatch
+            .found
+            .map_or_else(|| String::from("none"), |year| year.to_string());
+        let _ = writeln!(
+            explanation,
+            "  copyright year: {found}, expected {} from git",
+            mismatch.expected
+        );
+    }
+
+    if let Some(line) = status.first_import {
+        let _ = writeln!(
+            explanation,
+            "  first import: line {line}, only comments before it were searched"
+        );
+    }
+
+    push_lines(&mut explanation, "found", &status.found);
+
+    let license_text = crate::template::with_file(
+        crate::template::verified_part(config.license_text(language_config)),
+        file,
+        config.license.match_mode == MatchMode::Regex,
+    );
+    let license_text = license_text.as_ref();
+    if config.license.match_mode == MatchMode::Regex {
+        push_lines(
+            &mut explanation,
+            "expected a match of",
+            license_text.lines(),
+        );
+        return explanation;
+    }
+
+    let expected = crate::expected_lines(license_text, &config.license, language_config);
+    push_lines(&mut explanation, "expected", &expected);
+
+    if status.has_license {
+        return explanation;
+    }
+
+    let Some(index) = (0..expected.len()).find(|&index| {
+        status
+            .found
+            .get(index)
+            .is_none_or(|found| !crate::line_matches(found, &expected[index]))
+    }) else {
+        return explanation;
+    };
+
+    let found = status.found.get(index).map(String::as_str).unwrap_or("");
+    let column = found
+        .chars()
+        .zip(expected[index].chars())
+        .take_while(|(found, expected)| found == expected)
+        .count();
+    let _ = writeln!(
+        explanation,
+        "  first difference in line {} of the header:",
+        index + 1
+    );
+    let _ = writeln!(
+        explanation,
+        "{}",
+        format!("    found:    {found}").trim_end()
+    );
+    let _ = writeln!(explanation, "    expected: {}", expected[index]);
+    let _ = writeln!(explanation, "              {}^", " ".repeat(column));
+
+    explanation
+}
+
+fn push_lines(
+    explanation: &mut String,
+    title: &str,
+    lines: impl IntoIterator<Item = impl AsRef<str>>,
+) {
+    let _ = writeln!(explanation, "  {title}:");
+    for line in lines {
+        let _ = writeln!(explanation, "    | {}", line.as_ref());
+    }
+}
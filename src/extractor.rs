@@ -0,0 +1,71 @@
+// © Marcel Müller 2025, licensed under the EUPL
+
+//! Extracting the leading comments of files with an external command, for languages without a
+//! tree-sitter grammar
+//!
+//! The command is run with the path of the file appended to its arguments and the content of the
+//! file on stdin, which might differ from the file on disk. It has to exit successfully and print
+//! the text of the leading comments without their markers to stdout, one line per comment line,
+//! e.g. `Copyright 2025 Foo` for a file starting with `;; Copyright 2025 Foo`. An empty output
+//! means the file has no header.
+
+use std::io::Write;
+use std::process::Stdio;
+
+use camino::Utf8Path;
+use miette::Context;
+use miette::IntoDiagnostic;
+use miette::bail;
+use miette::miette;
+
+/// Runs the extractor `command` for `file` with the given content, returning its output
+pub fn leading_comments(
+    command: &[String],
+    file: &Utf8Path,
+    content: &str,
+) -> Result<String, miette::Error> {
+    let Some((program, args)) = command.split_first() else {
+        bail!("The extractor for {file} is an empty command");
+    };
+
+    let mut child = std::process::Command::new(program)
+        .args(args)
+        .arg(file)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .into_diagnostic()
+        .with_context(|| miette!("Could not run the extractor {program} for {file}"))?;
+
+    // Writing on another thread, as the extractor might not read all of its input before writing
+    // output, which would block both sides once the pipe buffers are full
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let output = std::thread::scope(|scope| {
+        scope.spawn(move || {
+            // The extractor does not have to read its input, so a broken pipe is fine
+            let _ = stdin.write_all(content.as_bytes());
+        });
+        child.wait_with_output()
+    })
+    .into_diagnostic()
+    .with_context(|| miette!("Could not run the extractor {program} for {file}"))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        match stderr.trim() {
+            "" => bail!(
+                "The extractor {program} failed for {file} with {}",
+                output.status
+            ),
+            stderr => bail!(
+                "The extractor {program} failed for {file} with {}: {stderr}",
+                output.status
+            ),
+        }
+    }
+
+    String::from_utf8(output.stdout)
+        .into_diagnostic()
+        .with_context(|| miette!("The extractor {program} printed invalid UTF-8 for {file}"))
+}
@@ -0,0 +1,118 @@
+// © Marcel Müller 2025, licensed under the EUPL
+
+//! Finding the header variants that are already used in a directory tree
+
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+
+use camino::Utf8Path;
+use camino::Utf8PathBuf;
+use miette::Context;
+use miette::IntoDiagnostic;
+use miette::miette;
+use serde::Serialize;
+use tracing::debug;
+use tracing::warn;
+
+use crate::Config;
+use crate::Language;
+use crate::OutputFormat;
+
+/// A distinct leading comment found in at least one file
+#[derive(Debug, Serialize)]
+pub struct Variant {
+    header: String,
+    count: usize,
+    languages: BTreeSet<String>,
+    files: Vec<Utf8PathBuf>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct Variants {
+    /// Sorted by descending count
+    variants: Vec<Variant>,
+    without_header: usize,
+}
+
+/// Extracts the leading comments of all files below `directory` that belong to a configured
+/// language, without comparing them to the license
+pub fn collect(
+    langs: &HashMap<String, Language>,
+    config: &Config,
+    directory: &Utf8Path,
+) -> Result<Variants, miette::Error> {
+    let mut files = vec![];
+    crate::walk_directory(directory, &mut files)?;
+
+    let mut variants = Variants::default();
+    let mut by_header = HashMap::<String, Variant>::new();
+    for file in files {
+        if crate::language_by_file_endings(config, &file).is_none() {
+            debug!("Skipping {file}, as it does not belong to a configured language");
+            continue;
+        }
+
+        let content = std::fs::read_to_string(&file)
+            .into_diagnostic()
+            .with_context(|| miette!("While reading the file {file}"))?;
+        let Some(extracted) = crate::extract_comments(langs, config, &file, &content)? else {
+            warn!("Skipping {file}, as parsing it took longer than the timeout");
+            continue;
+        };
+        let header = crate::header_lines(
+            &extracted.comments,
+            &config.license,
+            extracted.language_config,
+        )
+        .join("\n");
+        if header.is_empty() {
+            variants.without_header += 1;
+            continue;
+        }
+
+        let variant = by_header.entry(header.clone()).or_insert_with(|| Variant {
+            header,
+            count: 0,
+            languages: BTreeSet::new(),
+            files: vec![],
+        });
+        variant.count += 1;
+        variant.languages.insert(extracted.name.clone());
+        variant.files.push(file);
+    }
+
+    variants.variants = by_header.into_values().collect();
+    variants
+        .variants
+        .sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.header.cmp(&b.header)));
+
+    Ok(variants)
+}
+
+impl Variants {
+    pub fn print(&self, format: OutputFormat) -> Result<(), miette::Error> {
+        match format {
+            OutputFormat::Human => {
+                for variant in &self.variants {
+                    let languages = variant
+                        .languages
+                        .iter()
+                        .map(String::as_str)
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    println!("{} files ({languages}):", variant.count);
+                    for line in variant.header.lines() {
+                        println!("    | {line}");
+                    }
+                    println!();
+                }
+                println!("{} files without a header", self.without_header);
+            }
+            OutputFormat::Json => {
+                println!("{}", serde_json::to_string_pretty(self).into_diagnostic()?);
+            }
+        }
+
+        Ok(())
+    }
+}
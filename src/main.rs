@@ -1,82 +1,1028 @@
 // © Marcel Müller 2025, licensed under the EUPL
 
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::hash_map::Entry;
+use std::io::IsTerminal;
 use std::io::Write;
+use std::ops::Deref;
+use std::ops::DerefMut;
+use std::process::ExitCode;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::Instant;
 
+use camino::Utf8Component;
 use camino::Utf8Path;
 use camino::Utf8PathBuf;
 use clap::Parser;
 use clap::Subcommand;
+use clap::ValueEnum;
 use glob_match::glob_match;
 use miette::Context;
 use miette::IntoDiagnostic;
+use miette::LabeledSpan;
+use miette::MietteHandlerOpts;
+use miette::NamedSource;
 use miette::bail;
 use miette::miette;
+use owo_colors::OwoColorize;
+use schemars::JsonSchema;
 use serde::Deserialize;
+use serde::Serialize;
 use tracing::debug;
 use tracing::error;
+use tracing::trace;
 use tracing::warn;
 use tracing_subscriber::EnvFilter;
+use tracing_subscriber::filter::LevelFilter;
+use tree_sitter::StreamingIterator;
 use tree_sitter_language::LanguageFn;
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::is_nfc;
+
+use crate::report::Finding;
+use crate::report::ReportFormat;
+
+mod archive;
+mod builtin;
+mod distance;
+mod doctor;
+mod explain;
+mod extractor;
+mod learn;
+mod notebook;
+mod parallel;
+mod patch;
+mod report;
+#[cfg(unix)]
+mod serve;
+mod since;
+mod stats;
+mod template;
+mod year;
+
+/// Exit code used when at least one file failed verification
+const EXIT_VERIFICATION_FAILED: u8 = 1;
+/// Exit code used when lizenz could not run, e.g. due to bad configuration or a missing grammar
+const EXIT_OPERATIONAL_ERROR: u8 = 2;
+
+/// The line starting and ending a front matter block
+const FRONT_MATTER_DELIMITER: &str = "---";
+
+/// The configuration used if none is given on the command line
+const DEFAULT_CONFIG_PATH: &str = "./lizenz.toml";
+
+/// The name content read from stdin is reported as
+const STDIN_FILE: &str = "<stdin>";
+
+/// The capture of a `comment_query` marking the comments searched for the header
+const COMMENT_CAPTURE: &str = "comment";
+
+/// The names the configuration is searched for when a directory is given as configuration
+const CONFIG_FILE_NAMES: [&str; 2] = ["lizenz.toml", ".lizenz.toml"];
+
+const EXIT_CODES_HELP: &str = "\
+Exit codes:
+  0  All files passed verification (or were fixed)
+  1  At least one file is missing its license header
+  2  lizenz could not run, e.g. due to bad configuration or a missing grammar";
 
 #[derive(Debug, Parser)]
+#[clap(after_help = EXIT_CODES_HELP)]
 pub struct Args {
     /// A directory containing tree sitter grammar shared objects
-    #[clap(short, long, env)]
-    pub tree_sitter_grammars: Utf8PathBuf,
+    ///
+    /// Can be given multiple times, or as a `:` separated list, with earlier directories taking
+    /// precedence. Defaults to `$XDG_DATA_HOME/lizenz/grammars`. Grammars built into lizenz with
+    /// the `grammar-*` features take precedence, and make the directory optional.
+    #[clap(short, long, env, value_delimiter = ':')]
+    pub tree_sitter_grammars: Vec<Utf8PathBuf>,
 
+    /// The configuration file, or a directory containing a `lizenz.toml` or `.lizenz.toml`
+    ///
+    /// Can be given multiple times to merge configurations, like an organization wide base and a
+    /// project override. Later files take precedence: their tables like `[license]` are merged
+    /// key by key into the earlier ones, and languages and licenses replace earlier ones with the
+    /// same name entirely, just like configured languages replace the default ones.
     #[clap(short, long)]
-    pub config_path: Option<Utf8PathBuf>,
+    pub config_path: Vec<Utf8PathBuf>,
+
+    /// Merge this profile of the configuration over it, like `ci` for a `[profiles.ci]` table
+    ///
+    /// Profiles are partial configurations, merged just like later `--config-path` files, so
+    /// that e.g. CI can require a different license text.
+    #[clap(long, env = "LIZENZ_PROFILE")]
+    pub profile: Option<String>,
+
+    /// Run as if started in this directory
+    ///
+    /// All relative paths, like the configuration, grammar directories and files to check, are
+    /// resolved against it, and files are reported relative to it.
+    #[clap(long)]
+    pub root: Option<Utf8PathBuf>,
+
+    /// Use this license text instead of the one from the configuration
+    #[clap(long, conflicts_with = "license_text_file")]
+    pub license_text: Option<String>,
+
+    /// Read the license text from this file instead of the configuration
+    #[clap(long)]
+    pub license_text_file: Option<Utf8PathBuf>,
+
+    /// The number of files to check in parallel
+    ///
+    /// With more than one job, files are reported in the order they finish.
+    #[clap(short, long, default_value_t = 1)]
+    pub jobs: usize,
+
+    /// Treat all checked files as this configured language, instead of matching file endings
+    ///
+    /// Files in searched directories are still selected by their file endings.
+    #[clap(long, value_name = "LANGUAGE")]
+    pub assume_language: Option<String>,
+
+    /// Give up parsing a file after this many milliseconds and report it as failing
+    ///
+    /// This keeps grammars that are slow or loop on unusual input from hanging the whole run.
+    /// Files given to an `extractor` are not affected.
+    #[clap(long, value_name = "MILLISECONDS")]
+    pub timeout_ms: Option<u64>,
+
+    /// When to use colors in the output
+    #[clap(long, value_enum, default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
 
     #[clap(subcommand)]
     command: Command,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Use colors if the output is a terminal and `NO_COLOR` is not set
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether output written to the given stream should be colored
+    fn enabled_for(self, stream: &impl IsTerminal) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+                    && stream.is_terminal()
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, Subcommand)]
 pub enum Command {
+    /// Check the licence of the given files
     Verify {
-        /// List of files to check their licence on
-        files: Vec<Utf8PathBuf>,
+        #[clap(flatten)]
+        selection: FileSelection,
+
+        /// Fix files failing verification, just like the fix command
+        #[clap(long)]
+        fix: bool,
+
+        /// Only print the number of files failing verification
+        #[clap(long, conflicts_with = "fix")]
+        count: bool,
+
+        /// Explain why files fail verification, showing the found and expected headers
+        #[clap(long, conflicts_with = "count")]
+        explain: bool,
+
+        /// Which files fail verification, others are neither reported nor counted
+        #[clap(long, value_enum, default_value_t = FailOn::Any)]
+        fail_on: FailOn,
+
+        /// Stop at the first file failing verification, reporting only that one
+        #[clap(long, conflicts_with = "fix")]
+        fail_fast: bool,
+
+        /// Print the files that would be checked with their languages, without checking them
+        #[clap(long, conflicts_with_all = ["fix", "count", "explain", "format"])]
+        list_files: bool,
+
+        /// Verify the files in this tar or zip archive instead, tar archives may be gzip compressed
+        #[clap(
+            long,
+            conflicts_with_all = ["fix", "list_files", "files", "files_from", "since_commit"]
+        )]
+        archive: Option<Utf8PathBuf>,
+
+        /// Print the files that took the longest to verify, for finding slow grammars or huge files
+        ///
+        /// Run with `RUST_LOG=lizenz=trace` to log the time spent reading, parsing and comparing
+        /// every file.
+        #[clap(long, conflicts_with = "archive")]
+        timings: bool,
+
+        /// The format of the report of the files failing verification
+        #[clap(long, value_enum, default_value_t = ReportFormat::Human, conflicts_with = "count")]
+        format: ReportFormat,
+
+        /// Write the report to this file instead of stdout
+        ///
+        /// The human readable results are still printed, so that progress can be followed.
+        #[clap(long, conflicts_with = "count")]
+        output: Option<Utf8PathBuf>,
+
+        /// Report paths relative to this directory, files outside of it with absolute paths
+        #[clap(long, default_value = ".")]
+        relative_to: Utf8PathBuf,
     },
+    /// Check the licences of the given files and try to fix them
     Fix {
-        /// List of files to check their licences and try to fix them
-        files: Vec<Utf8PathBuf>,
+        #[clap(flatten)]
+        selection: FileSelection,
+
+        /// Write the fixes as a patch to this file instead of modifying the files
+        ///
+        /// The patch uses paths relative to the repository root and can be applied with `git apply`.
+        #[clap(long)]
+        diff_output: Option<Utf8PathBuf>,
+
+        /// Extend the years matched by `{{year}}` in valid headers to ranges ending in the current
+        /// year, e.g. `2020` or `2020-2024` to `2020-2025`
+        #[clap(long)]
+        update_years: bool,
+
+        #[clap(flatten)]
+        create: CreateOptions,
+    },
+    /// Print a JSON schema describing the configuration file
+    ConfigSchema,
+    /// Summarize how many files in a directory have a valid license header
+    Stats {
+        /// The directory to search for files, recursively
+        #[clap(default_value = ".")]
+        directory: Utf8PathBuf,
+
+        #[clap(long, value_enum, default_value_t = OutputFormat::Human)]
+        format: OutputFormat,
     },
+    /// List the distinct leading comments of the files in a directory, by how often they occur
+    ///
+    /// This helps choosing the license text when adopting lizenz in an existing code base.
+    Learn {
+        /// The directory to search for files, recursively
+        #[clap(default_value = ".")]
+        directory: Utf8PathBuf,
+
+        #[clap(long, value_enum, default_value_t = OutputFormat::Human)]
+        format: OutputFormat,
+    },
+    /// Verify content read from stdin as a file of the given language, explaining the result
+    ///
+    /// This helps iterating on comment configurations and tree-sitter node kinds without files.
+    Try {
+        /// The configured language of the content
+        #[clap(long)]
+        lang: String,
+    },
+    /// Check the configuration and grammars, printing what is wrong with them
+    Doctor,
+    /// Answer verify and fix requests on a Unix socket, keeping the grammars loaded
+    ///
+    /// Clients send one JSON request per line, like `{"command": "verify", "path": "a.rs"}`, and
+    /// receive one JSON response per line. A `content` in the request is checked instead of the
+    /// file at `path`, and `fix` returns the fixed content instead of writing it.
+    #[cfg(unix)]
+    Serve {
+        /// The path of the socket to create, which must not exist yet
+        socket: Utf8PathBuf,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum FailOn {
+    /// Files without any header
+    Missing,
+    /// Files with a header that is not the expected one, including near matches
+    Mismatch,
+    /// Files without the expected header, except near matches
+    Distant,
+    /// Files without the expected header
+    Any,
+}
+
+impl FailOn {
+    /// Whether files with this outcome fail, files that could not be parsed in time always do
+    fn fails(self, outcome: Outcome) -> bool {
+        if outcome == Outcome::TimedOut {
+            return true;
+        }
+        match self {
+            FailOn::Missing => outcome == Outcome::Missing,
+            FailOn::Mismatch => matches!(outcome, Outcome::Mismatch | Outcome::NearMatch),
+            FailOn::Distant => matches!(outcome, Outcome::Missing | Outcome::Mismatch),
+            FailOn::Any => !matches!(outcome, Outcome::Valid | Outcome::TooSmall),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human readable text
+    Human,
+    Json,
+}
+
+#[derive(Debug, Clone, clap::Args)]
+pub struct FileSelection {
+    /// List of files to check their licence on
+    ///
+    /// When verifying without any files, all files of configured languages in the current
+    /// directory are checked.
+    files: Vec<Utf8PathBuf>,
+
+    /// Also check the files listed in this file, one per line, `-` reads them from stdin
+    #[clap(long)]
+    files_from: Option<Utf8PathBuf>,
+
+    /// The files given with --files-from are separated by NUL instead of newlines
+    ///
+    /// This handles paths containing newlines, as printed by `git ls-files -z` or
+    /// `find -print0`.
+    #[clap(short = '0', long, requires = "files_from")]
+    null: bool,
+
+    /// Also check the files of configured languages changed since this git commit
+    ///
+    /// Deleted files are skipped and renamed files are checked at their new path.
+    #[clap(long, value_name = "COMMIT")]
+    since_commit: Option<String>,
+
+    /// Only check files matching at least one of these globs
+    ///
+    /// Globs containing a `/` are matched against the whole path, others against the file name.
+    #[clap(long = "include")]
+    includes: Vec<String>,
+
+    /// Do not fix files through symbolic links, report them as invalid instead
+    ///
+    /// Otherwise the target of the link is fixed. Verification always checks the target.
+    #[clap(long)]
+    no_follow_symlinks: bool,
+
+    /// Skip the files of languages without a grammar with a warning, instead of failing
+    ///
+    /// This allows sharing a configuration with machines that do not have all grammars.
+    #[clap(long)]
+    ignore_missing_grammar: bool,
+
+    /// Skip files larger than this many bytes
+    #[clap(long, default_value_t = 4 * 1024 * 1024)]
+    max_filesize: u64,
+}
+
+#[derive(Debug, Clone, clap::Args)]
+pub struct CreateOptions {
+    /// Create a new file at this path, containing only the license header
+    #[clap(long)]
+    create: Option<Utf8PathBuf>,
+
+    /// The language of the created file, instead of detecting it from its name
+    #[clap(long, requires = "create")]
+    lang: Option<String>,
+
+    /// Overwrite the created file if it already exists
+    #[clap(long, requires = "create")]
+    force: bool,
+}
+
+impl FileSelection {
+    /// The files that should actually be checked
+    ///
+    /// If no files were given and `default_to_current_dir` is set, these are all files in the
+    /// current directory belonging to a configured language.
+    fn selected_files(
+        self,
+        config: &Config,
+        default_to_current_dir: bool,
+    ) -> Result<impl Iterator<Item = Utf8PathBuf>, miette::Error> {
+        let filter = self.filter();
+        let mut files = self.files;
+        if let Some(path) = &self.files_from {
+            let list = if path == "-" {
+                std::io::read_to_string(std::io::stdin())
+                    .into_diagnostic()
+                    .with_context(|| miette!("Could not read the files to check from stdin"))?
+            } else {
+                std::fs::read_to_string(path)
+                    .into_diagnostic()
+                    .with_context(|| miette!("Could not read the files to check from {path}"))?
+            };
+
+            let separator = if self.null { '\0' } else { '\n' };
+            files.extend(
+                list.split(separator)
+                    .map(|file| {
+                        if self.null {
+                            file
+                        } else {
+                            file.trim_end_matches('\r')
+                        }
+                    })
+                    .filter(|file| !file.is_empty())
+                    .map(Utf8PathBuf::from),
+            );
+        }
+
+        if let Some(commit) = &self.since_commit {
+            files.extend(
+                since::changed_files(commit)?
+                    .into_iter()
+                    .filter(|file| language_by_file_endings(config, file).is_some()),
+            );
+        }
+
+        let listed = self.files_from.is_some() || self.since_commit.is_some();
+        if files.is_empty() && !listed && default_to_current_dir {
+            walk_directory(Utf8Path::new("."), &mut files)?;
+            files.retain(|file| language_by_file_endings(config, file).is_some());
+        }
+
+        Ok(files.into_iter().filter(move |file| {
+            let size = file.metadata().ok().map(|metadata| metadata.len());
+            filter.keeps(file, size)
+        }))
+    }
+
+    /// The filters of the selection, which also apply to the entries of an `--archive`
+    fn filter(&self) -> FileFilter {
+        FileFilter {
+            includes: self.includes.clone(),
+            max_filesize: self.max_filesize,
+        }
+    }
 }
 
-#[derive(Debug, Default, Deserialize)]
+/// The `--include` and `--max-filesize` filters of a selection
+struct FileFilter {
+    includes: Vec<String>,
+    max_filesize: u64,
+}
+
+impl FileFilter {
+    /// Whether the file, of the given size if it is known, should be checked
+    fn keeps(&self, file: &Utf8Path, size: Option<u64>) -> bool {
+        let included = self.includes.is_empty()
+            || self
+                .includes
+                .iter()
+                .any(|glob| glob_matches_file(glob, file));
+        if !included {
+            debug!("Skipping {file}, as it does not match any include");
+            return false;
+        }
+
+        if let Some(size) = size
+            && size > self.max_filesize
+        {
+            warn!(
+                "Skipping {file}, as it is larger than {} bytes ({size} bytes)",
+                self.max_filesize
+            );
+            return false;
+        }
+
+        true
+    }
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
 pub struct LicenseConfig {
+    /// The expected header, a line consisting only of `{{end}}` ends the part that is verified
+    ///
+    /// Anything after it is inserted by `fix`, but may differ between files.
+    ///
+    /// `{{git_author}}` is the `user.name` from the git configuration, `{{year}}` a year or a
+    /// range ending in the current year, and `{{file}}` the path of each file relative to the
+    /// root of its git repository, or to the current directory outside of repositories, with
+    /// forward slashes.
     text: String,
+    /// Expand leading tabs to this many spaces before comparing, `0` removes them
+    #[serde(default)]
+    expand_tabs: Option<usize>,
+    /// Remove all leading whitespace of every line before comparing, e.g. for headers
+    /// indented inside some construct
+    #[serde(default)]
+    ignore_indentation: bool,
+    #[serde(default)]
+    match_mode: MatchMode,
+    /// Compare texts in unicode normalization form C, so that e.g. a precomposed `é` matches an
+    /// `e` followed by a combining accent
+    #[serde(default)]
+    normalize_unicode: bool,
+    /// Require the last year in the header to be the year of this commit of the file
+    ///
+    /// Files without commits are not checked.
+    #[serde(default)]
+    year_from_git: Option<year::GitYear>,
+    /// Wrap the lines of `Single` comment headers inserted by `fix` to this many columns,
+    /// including the comment marker
+    ///
+    /// Headers are then compared word by word, so that differently wrapped headers match.
+    #[serde(default)]
+    wrap: Option<usize>,
+    /// When `fix` inserts a header into a file starting with blank lines, keep exactly one of them
+    /// between the header and the content
+    #[serde(default)]
+    keep_leading_blank: bool,
+    /// Require the header to be the first comment of a file, so that a stray comment of any
+    /// configured kind above it fails verification
+    ///
+    /// Otherwise the header may follow other leading comments.
+    #[serde(default)]
+    header_is_first_comment: bool,
+    /// Only require this many leading lines of the license text to match, e.g. `1` for just the
+    /// copyright notice, `fix` still inserts the whole text
+    ///
+    /// In the `regex` match mode these are lines of the expression.
+    #[serde(default)]
+    verify_lines: Option<usize>,
+    /// Report headers within this many edited characters of the license as near matches, which
+    /// `--fail-on distant` does not fail on
+    ///
+    /// This is not supported in the `regex` match mode.
+    #[serde(default)]
+    max_edit_distance: Option<usize>,
+    /// Phrases the leading comments must not contain, like an old company name
+    ///
+    /// Only the comments before the first code are searched, so that e.g. documentation may still
+    /// mention the phrase. Whitespace is insignificant, so phrases are found even if the header
+    /// wraps them.
+    #[serde(default)]
+    forbidden: Vec<String>,
+    /// A decoration line, like a row of `=`, expected as the first line of every header
+    #[serde(default)]
+    top_rule: Option<String>,
+    /// A decoration line expected as the last line of every header
+    ///
+    /// If the text has an `{{end}}` line, the rule follows the part after it and is therefore only
+    /// inserted, but not verified.
+    #[serde(default)]
+    bottom_rule: Option<String>,
+}
+
+/// How the license text is compared against the leading comments of a file
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchMode {
+    /// The comment lines have to be identical to the license text
+    Exact,
+    /// Like `exact`, but surrounding whitespace and blank lines are ignored and the configured
+    /// normalizations like `expand_tabs` are applied
+    #[default]
+    Normalized,
+    /// The license text is a regular expression, which has to match the normalized comments
+    /// starting at their first line
+    ///
+    /// `fix` cannot insert headers in this mode.
+    Regex,
 }
 
-#[derive(Debug, Default, Deserialize)]
+#[derive(Debug, Default, Deserialize, JsonSchema)]
 pub struct Config {
     license: LicenseConfig,
+    /// Additional license texts, which languages can refer to by name
+    #[serde(default)]
+    licenses: HashMap<String, String>,
     #[serde(default)]
     languages: HashMap<String, LanguageConfig>,
+    /// The language all checked files are treated as, given with `--assume-language`
+    #[serde(skip)]
+    #[schemars(skip)]
+    assumed_language: Option<String>,
+    /// How long parsing a file may take, given with `--timeout-ms`
+    #[serde(skip)]
+    #[schemars(skip)]
+    parse_timeout: Option<Duration>,
+    /// The configuration files as loaded, to point at the location of errors found later on
+    #[serde(skip)]
+    #[schemars(skip)]
+    sources: Vec<(Utf8PathBuf, String)>,
+    /// The compiled expressions of the `regex` match mode, by their pattern
+    #[serde(skip)]
+    #[schemars(skip)]
+    regexes: HashMap<String, regex::Regex>,
+}
+
+impl Config {
+    /// Checks that every license referenced by a language exists
+    fn validate_licenses(&self) -> Result<(), miette::Error> {
+        for (name, language) in &self.languages {
+            if let Some(license) = &language.license
+                && !self.licenses.contains_key(license)
+            {
+                bail!(
+                    "The language {name} uses the license {license}, but no such license is defined in [licenses]"
+                );
+            }
+            if let Some(footer) = &language.footer
+                && !self.licenses.contains_key(footer)
+            {
+                bail!(
+                    "The language {name} uses the footer {footer}, but no such license is defined in [licenses]"
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that every comment node can be attributed to a single comment configuration
+    ///
+    /// A node kind may only be configured more than once for `Single` comments with different
+    /// markers, like `//` and `//!`, which are told apart by the longest marker a comment starts
+    /// with.
+    fn validate_comments(&self) -> Result<(), miette::Error> {
+        for (name, language) in &self.languages {
+            for (index, conf) in language.comments.iter().enumerate() {
+                let ambiguous = language.comments[..index]
+                    .iter()
+                    .filter(|earlier| earlier.tree_sitter_name == conf.tree_sitter_name)
+                    .any(
+                        |earlier| match (&earlier.comment_kind, &conf.comment_kind) {
+                            (CommentKind::Single(earlier), CommentKind::Single(marker)) => {
+                                earlier == marker
+                            }
+                            _ => true,
+                        },
+                    );
+                if ambiguous {
+                    bail!(
+                        "The language {name} configures comments of the node kind {} more than once, which is only possible for Single comments with different markers",
+                        conf.tree_sitter_name
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the `notebook_language` of every language is configured, and not a notebook
+    /// language itself
+    fn validate_notebooks(&self) -> Result<(), miette::Error> {
+        for (name, language) in &self.languages {
+            let Some(notebook_language) = &language.notebook_language else {
+                continue;
+            };
+            match self.languages.get(notebook_language) {
+                None => bail!(
+                    "The language {name} uses the notebook language {notebook_language}, which is not configured"
+                ),
+                Some(code) if code.notebook_language.is_some() => bail!(
+                    "The language {name} uses the notebook language {notebook_language}, which is a notebook language itself"
+                ),
+                Some(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Uses the given text as the license for all languages
+    fn override_license_text(&mut self, text: String) {
+        self.license.text = text;
+        for language in self.languages.values_mut() {
+            language.license = None;
+        }
+    }
+
+    /// Replaces the template variables in all license texts
+    fn expand_templates(&mut self) -> Result<(), miette::Error> {
+        let regex = self.license.match_mode == MatchMode::Regex;
+        self.license.text = template::expand(&self.license.text, regex)?;
+        for text in self.licenses.values_mut() {
+            *text = template::expand(text, regex)?;
+        }
+        Ok(())
+    }
+
+    /// Removes a single trailing newline from all license texts
+    ///
+    /// Texts written as multi-line strings or read from files usually end in a newline, which is
+    /// not part of the header.
+    fn strip_trailing_newlines(&mut self) {
+        for text in std::iter::once(&mut self.license.text).chain(self.licenses.values_mut()) {
+            if let Some(stripped) = text
+                .strip_suffix('\n')
+                .map(|text| text.strip_suffix('\r').unwrap_or(text))
+            {
+                text.truncate(stripped.len());
+            }
+        }
+    }
+
+    /// Surrounds all license texts with the `top_rule` and `bottom_rule` lines
+    fn add_rules(&mut self) {
+        let rule = |rule: &Option<String>| {
+            rule.as_deref().map(|rule| match self.license.match_mode {
+                MatchMode::Regex => regex::escape(rule),
+                MatchMode::Exact | MatchMode::Normalized => rule.to_owned(),
+            })
+        };
+        let (top, bottom) = (
+            rule(&self.license.top_rule),
+            rule(&self.license.bottom_rule),
+        );
+
+        for text in std::iter::once(&mut self.license.text).chain(self.licenses.values_mut()) {
+            if let Some(top) = &top {
+                text.insert_str(0, &format!("{top}\n"));
+            }
+            if let Some(bottom) = &bottom {
+                text.push('\n');
+                text.push_str(bottom);
+            }
+        }
+    }
+
+    /// Compiles the `comment_query` of all languages with a grammar
+    fn compile_queries(&mut self, langs: &HashMap<String, Language>) -> Result<(), miette::Error> {
+        for (name, language) in &mut self.languages {
+            let (Some(path), Some(grammar)) = (&language.comment_query, langs.get(name)) else {
+                continue;
+            };
+
+            let source = std::fs::read_to_string(path)
+                .into_diagnostic()
+                .with_context(|| miette!("Could not read the comment query of {name} at {path}"))?;
+            let query =
+                tree_sitter::Query::new(&tree_sitter::Language::new(grammar.language_fn), &source)
+                    .into_diagnostic()
+                    .with_context(|| miette!("The comment query of {name} at {path} is invalid"))?;
+            if query.capture_index_for_name(COMMENT_CAPTURE).is_none() {
+                bail!("The comment query of {name} at {path} has no @{COMMENT_CAPTURE} capture");
+            }
+            language.query = Some(query);
+        }
+        Ok(())
+    }
+
+    /// Compiles the license texts and footers of the `regex` match mode
+    ///
+    /// Texts containing `{{file}}` are only checked here, as they are compiled for every file.
+    fn compile_regexes(&mut self) -> Result<(), miette::Error> {
+        if self.license.match_mode != MatchMode::Regex {
+            return Ok(());
+        }
+
+        let footers = self
+            .languages
+            .values()
+            .filter_map(|language| language.footer.as_deref())
+            .collect::<HashSet<_>>();
+        let texts = std::iter::once((None, &self.license.text))
+            .chain(self.licenses.iter().map(|(name, text)| (Some(name), text)));
+        let mut regexes = HashMap::new();
+        for (name, text) in texts {
+            let text = template::verified_part(text);
+            let mut patterns = vec![license_regex(text, self.license.verify_lines)];
+            if name.is_some_and(|name| footers.contains(name.as_str())) {
+                patterns.push(footer_regex(text));
+            }
+            for pattern in patterns {
+                // A path is inserted escaped, so any path shows whether the expression is valid
+                let regex =
+                    regex::Regex::new(&template::with_file(&pattern, Utf8Path::new("file"), true))
+                        .map_err(|error| self.regex_error(name.map(String::as_str), &error))?;
+                if !template::mentions_file(&pattern) {
+                    regexes.insert(pattern, regex);
+                }
+            }
+        }
+        self.regexes = regexes;
+        Ok(())
+    }
+
+    /// The expression compiled from the given pattern, if it was not compiled already
+    fn regex(&self, pattern: &str) -> Result<regex::Regex, miette::Error> {
+        match self.regexes.get(pattern) {
+            Some(regex) => Ok(regex.clone()),
+            None => regex::Regex::new(pattern)
+                .into_diagnostic()
+                .with_context(|| miette!("The license text is not a valid regular expression")),
+        }
+    }
+
+    /// An error for the invalid expression of the license with the given name, pointing at the
+    /// configuration defining it
+    fn regex_error(&self, name: Option<&str>, error: &regex::Error) -> miette::Error {
+        let (message, keys) = match name {
+            Some(name) => (
+                format!("The license {name} is not a valid regular expression"),
+                ["licenses", name],
+            ),
+            None => (
+                String::from("The license text is not a valid regular expression"),
+                ["license", "text"],
+            ),
+        };
+        // The configurations are merged in order, so the last one defining the text is used
+        let location = self.sources.iter().rev().find_map(|(path, content)| {
+            value_span(content, &keys).map(|span| (path, content, span))
+        });
+        let message = format!("{message}\n{error}");
+        match location {
+            Some((path, content, span)) => configuration_error(path, content, &message, Some(span)),
+            None => miette!("{}", message.trim_end()),
+        }
+    }
+
+    /// The license text files of the given language have to start with
+    fn license_text(&self, language_config: &LanguageConfig) -> &str {
+        language_config
+            .license
+            .as_ref()
+            .and_then(|license| self.licenses.get(license))
+            .unwrap_or(&self.license.text)
+    }
+
+    /// The license text files of the given language have to end with, if any
+    fn footer_text(&self, language_config: &LanguageConfig) -> Option<&str> {
+        language_config
+            .footer
+            .as_ref()
+            .and_then(|footer| self.licenses.get(footer))
+            .map(String::as_str)
+    }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct CommentConfig {
     tree_sitter_name: String,
     comment_kind: CommentKind,
+    /// Whether `fix` uses this kind of comment, verification accepts headers in any configured kind
     preferred: bool,
+    /// Whether the prefix of `Single` comments matches regardless of case, e.g. `REM` and `rem`
+    #[serde(default)]
+    case_insensitive_marker: bool,
+    /// How `fix` renders the blank lines of the license text as `Single` comments
+    ///
+    /// Verification accepts either rendering.
+    #[serde(default)]
+    blank_line_style: BlankLineStyle,
+}
+
+/// How blank lines of a header are rendered as `Single` comments
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum BlankLineStyle {
+    /// A line with only the marker, like `//`
+    #[default]
+    Marker,
+    /// A line without a comment, which still belongs to the header if comments follow it
+    Empty,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub enum CommentKind {
+    /// Comments starting with this marker on every line, like `//`
+    ///
+    /// Several markers may share a node kind, like `//` and Rust's module doc comments `//!`.
+    /// Comments then belong to the longest marker they start with. A single space following the
+    /// marker is optional, further whitespace is the indentation of the line.
     Single(String),
     Multi {
         start: String,
+        /// An alternative opener like `/**`, which is recognized too and used when inserting headers
+        #[serde(default)]
+        start_alt: Option<String>,
         end: String,
+        /// The marker starting continuation lines, like the `*` in ` * text`
+        ///
+        /// If set it is stripped together with the whitespace before it and a single space after
+        /// it, further whitespace is the indentation of the line. Without it continuation lines
+        /// keep their indentation relative to each other.
         between: Option<String>,
     },
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, JsonSchema)]
 pub struct LanguageConfig {
+    /// Globs matching the files of this language, like `*.rs`
+    ///
+    /// Globs containing a `/`, like `src/**/*.rs`, are matched against the whole path, others
+    /// against the file name.
     file_endings: Vec<String>,
+    /// The kinds of comments of this language
     comments: Vec<CommentConfig>,
+    /// Whether blank comment lines are part of the header and have to match exactly
+    #[serde(default)]
+    preserve_blank_comment_lines: bool,
+    /// Node kinds whose direct children are also searched, if no comment is found at the top level
+    ///
+    /// Only the leading comments of the containers at the start of the file are searched, which
+    /// may follow the head of the container on its first line, like the `[package]` of a TOML
+    /// table.
+    #[serde(default)]
+    comment_containers: Vec<String>,
+    /// The name of an entry in `[licenses]` to use instead of the default license
+    #[serde(default)]
+    license: Option<String>,
+    /// The name of an entry in `[licenses]` the trailing comments of files have to end with, for
+    /// licenses recommending a notice at the end of files
+    #[serde(default)]
+    footer: Option<String>,
+    /// A banner like `Code generated by foo. DO NOT EDIT.` the leading comments have to contain
+    #[serde(default)]
+    required_banner: Option<String>,
+    /// A leading declaration like Java's `package com.foo;`, relative to which the header is placed
+    #[serde(default)]
+    declaration: Option<DeclarationConfig>,
+    /// The tree-sitter node kind of imports, e.g. `use_declaration`, which the header has to
+    /// precede
+    ///
+    /// Headers following the first import are not searched, so that headers accidentally moved
+    /// below the imports fail verification.
+    #[serde(default)]
+    import_node_kind: Option<String>,
+    /// Whether files may start with a front matter block between `---` lines, like Markdown
+    /// files for static site generators, which the header has to follow
+    #[serde(default)]
+    front_matter: bool,
+    /// A tree-sitter query file, relative to the configuration, whose `@comment` captures are the
+    /// only comments searched for the header
+    ///
+    /// The captured nodes still need a comment configuration for their kind. With predicates like
+    /// `#match?` unrelated comments can be ignored, and the highlight queries of grammars can be
+    /// reused.
+    #[serde(default)]
+    #[schemars(with = "Option<String>")]
+    comment_query: Option<Utf8PathBuf>,
+    /// Files with fewer lines need no header, e.g. one line `mod.rs` files re-exporting modules
+    #[serde(default)]
+    min_lines: Option<usize>,
+    /// Files with fewer bytes need no header
+    #[serde(default)]
+    min_bytes: Option<usize>,
+    /// A command printing the leading comments of a file, used instead of a tree-sitter grammar,
+    /// like `["python3", "extract.py"]`
+    ///
+    /// The command gets the path of the file appended and its content on stdin, and has to print
+    /// the text of the leading comments without their markers, one line per comment line. The
+    /// comment configurations are only used to insert headers, and footers need a grammar.
+    #[serde(default)]
+    extractor: Option<Vec<String>>,
+    /// A formatter run on files after fixing them, like `["rustfmt", "--edition", "2024"]`, with
+    /// the path of the file appended
+    ///
+    /// A failing formatter is only warned about, the fix is kept.
+    #[serde(default)]
+    format_command: Option<Vec<String>>,
+    /// The language of the code cells of Jupyter notebooks, like `python` for `*.ipynb` files
+    ///
+    /// The header of a notebook is that of its first code cell, which is verified and fixed like
+    /// a file of this language. Notebooks without code cells need no header.
+    #[serde(default)]
+    notebook_language: Option<String>,
+    /// The compiled `comment_query`
+    #[serde(skip)]
+    #[schemars(skip)]
+    query: Option<tree_sitter::Query>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DeclarationConfig {
+    /// The tree-sitter node kind of the declaration, e.g. `package_declaration`
+    node_kind: String,
+    header: HeaderPosition,
+}
+
+/// Where the header goes relative to a declaration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum HeaderPosition {
+    Before,
+    After,
+}
+
+impl LanguageConfig {
+    /// Whether a file with the given content is below `min_lines` or `min_bytes`
+    fn is_too_small(&self, content: &str) -> bool {
+        self.min_lines
+            .is_some_and(|min| content.lines().count() < min)
+            || self.min_bytes.is_some_and(|min| content.len() < min)
+    }
+
+    /// The comment kind `fix` uses for this language
+    fn preferred_comment(&self) -> Option<&CommentConfig> {
+        self.comments
+            .iter()
+            .find(|conf| conf.preferred)
+            .or_else(|| self.comments.first())
+    }
 }
 
 fn default_languages() -> HashMap<String, LanguageConfig> {
@@ -85,7 +1031,14 @@ fn default_languages() -> HashMap<String, LanguageConfig> {
             String::from("bash"),
             LanguageConfig {
                 file_endings: vec![String::from("*.sh")],
-                comments: vec![],
+                comments: vec![CommentConfig {
+                    tree_sitter_name: String::from("comment"),
+                    comment_kind: CommentKind::Single(String::from("#")),
+                    preferred: true,
+                    case_insensitive_marker: false,
+                    blank_line_style: BlankLineStyle::Marker,
+                }],
+                ..Default::default()
             },
         ),
         (
@@ -97,17 +1050,30 @@ fn default_languages() -> HashMap<String, LanguageConfig> {
                         tree_sitter_name: String::from("block_comment"),
                         comment_kind: CommentKind::Multi {
                             start: String::from("/*"),
+                            start_alt: None,
                             between: Some(String::from("*")),
                             end: String::from("*/"),
                         },
                         preferred: false,
+                        case_insensitive_marker: false,
+                        blank_line_style: BlankLineStyle::Marker,
                     },
                     CommentConfig {
                         tree_sitter_name: String::from("line_comment"),
                         comment_kind: CommentKind::Single(String::from("//")),
                         preferred: true,
+                        case_insensitive_marker: false,
+                        blank_line_style: BlankLineStyle::Marker,
+                    },
+                    CommentConfig {
+                        tree_sitter_name: String::from("line_comment"),
+                        comment_kind: CommentKind::Single(String::from("//!")),
+                        preferred: false,
+                        case_insensitive_marker: false,
+                        blank_line_style: BlankLineStyle::Marker,
                     },
                 ],
+                ..Default::default()
             },
         ),
         (
@@ -118,7 +1084,10 @@ fn default_languages() -> HashMap<String, LanguageConfig> {
                     tree_sitter_name: String::from("comment"),
                     comment_kind: CommentKind::Single(String::from("#")),
                     preferred: true,
+                    case_insensitive_marker: false,
+                    blank_line_style: BlankLineStyle::Marker,
                 }],
+                ..Default::default()
             },
         ),
     ]
@@ -126,33 +1095,99 @@ fn default_languages() -> HashMap<String, LanguageConfig> {
 }
 
 struct Language {
+    /// Idle parsers for this grammar, reused across files, as constructing one per file is costly
+    ///
+    /// These are declared before the library, so that they are dropped before it is unloaded.
+    parsers: Mutex<Vec<tree_sitter::Parser>>,
+    /// The name derived from the grammar's file, which configured languages are matched against
     _name: String,
-    _library: libloading::Library,
+    /// The library the grammar was loaded from, `None` for built-in grammars
+    _library: Option<libloading::Library>,
     language_fn: LanguageFn,
 }
 
-fn main() -> miette::Result<()> {
+fn main() -> ExitCode {
+    let args = Args::parse();
+
     tracing_subscriber::fmt::fmt()
         .pretty()
-        .with_env_filter(EnvFilter::from_default_env())
+        .with_writer(std::io::stderr)
+        .with_ansi(args.color.enabled_for(&std::io::stderr()))
+        .with_env_filter(
+            EnvFilter::builder()
+                .with_default_directive(LevelFilter::WARN.into())
+                .from_env_lossy(),
+        )
         .init();
 
-    let args = Args::parse();
+    let color = args.color;
+    let _ = miette::set_hook(Box::new(move |_| {
+        let enabled = color.enabled_for(&std::io::stderr());
+        Box::new(
+            MietteHandlerOpts::new()
+                .color(enabled)
+                .force_graphical(color == ColorChoice::Always)
+                .build(),
+        )
+    }));
+
+    match run(args) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(EXIT_VERIFICATION_FAILED),
+        Err(error) => {
+            eprintln!("{error:?}");
+            ExitCode::from(EXIT_OPERATIONAL_ERROR)
+        }
+    }
+}
 
-    let langs = load_languages(&args)?;
+/// Runs the given command, returning whether all files passed verification
+fn run(args: Args) -> miette::Result<bool> {
+    if let Some(root) = &args.root {
+        std::env::set_current_dir(root)
+            .into_diagnostic()
+            .with_context(|| miette!("Could not change into the root directory {root}"))?;
+    }
 
-    let mut config: Config = if let Some(config_path) = args
-        .config_path
-        .as_ref()
-        .map(|path| path.as_path())
-        .or_else(|| Some(Utf8Path::new("./lizenz.toml")))
-    {
-        match load_configuration(config_path).with_context(|| {
+    if let Command::ConfigSchema = args.command {
+        let schema = schemars::schema_for!(Config);
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&schema).into_diagnostic()?
+        );
+        return Ok(true);
+    }
+
+    let config_paths = if args.config_path.is_empty() {
+        vec![config_file(Utf8Path::new(DEFAULT_CONFIG_PATH))]
+    } else {
+        args.config_path
+            .iter()
+            .map(|path| config_file(path))
+            .collect()
+    };
+
+    if let Command::Doctor = args.command {
+        return Ok(doctor::run(
+            &config_paths,
+            args.profile.as_deref(),
+            &args.tree_sitter_grammars,
+            args.color,
+        ));
+    }
+
+    let langs = load_all_languages(&args.tree_sitter_grammars)?;
+
+    let mut config: Config =
+        match load_configuration(&config_paths, args.profile.as_deref()).with_context(|| {
             miette!(
                 "While loading config at {}, current working directory is {}",
-                config_path
-                    .canonicalize_utf8()
-                    .unwrap_or(config_path.to_path_buf()),
+                config_paths
+                    .iter()
+                    .map(|path| path.canonicalize_utf8().unwrap_or(path.to_path_buf()))
+                    .map(|path| path.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", "),
                 std::env::current_dir().unwrap_or_default().display()
             )
         }) {
@@ -161,220 +1196,2249 @@ fn main() -> miette::Result<()> {
                 return Err(error
                     .context("Could not load configuration, please verify errors and try again"));
             }
-        }
-    } else {
-        bail!("Could not find configuration, nothing to be done");
-    };
+        };
 
     for (name, lang) in default_languages() {
         config.languages.entry(name).or_insert(lang);
     }
 
-    match args.command {
-        Command::Verify { files } => {
-            for file in files {
-                debug!("Checking {}", file);
-                verify_file(&langs, &config, &file)?;
-            }
-        }
-        Command::Fix { files } => {
-            for file in files {
-                debug!("Checking {}", file);
-                let is_valid = verify_file(&langs, &config, &file)?;
-
-                if !is_valid {
-                    let (language_config, parser) = load_language(&langs, &config, &file)?;
-
-                    let Some(conf) = language_config
-                        .comments
-                        .iter()
-                        .find(|conf| conf.preferred)
-                        .or_else(|| language_config.comments.first())
-                    else {
-                        bail!(
-                            "No comment configuration exists for language {}",
-                            parser.language().unwrap().name().unwrap_or_default(),
-                        );
-                    };
-
-                    let header = match &conf.comment_kind {
-                        CommentKind::Single(prefix) => config
-                            .license
-                            .text
-                            .lines()
-                            .map(|line| {
-                                if line.is_empty() {
-                                    format!("{prefix}\n")
-                                } else {
-                                    format!("{prefix} {line}\n")
-                                }
-                            })
-                            .collect::<String>(),
-                        CommentKind::Multi {
-                            start,
-                            end,
-                            between,
-                        } => {
-                            let line_count = config.license.text.lines().count();
-
-                            match line_count {
-                                0..=1 => {
-                                    format!("{start} {} {end}", config.license.text)
-                                }
-                                2.. => {
-                                    let mut lines = config.license.text.lines();
-                                    let mut header = format!(
-                                        "{start} {}",
-                                        lines.next().expect("We know length is at least 2")
-                                    );
-
-                                    header.extend(lines.by_ref().take(line_count - 2).map(
-                                        |line| {
-                                            format!(
-                                                "{} {}",
-                                                between.as_deref().unwrap_or_default(),
-                                                line
-                                            )
-                                        },
-                                    ));
-
-                                    header.push_str(&format!(
-                                        " {} {end}",
-                                        lines.next().expect("We know length is at least 2")
-                                    ));
-
-                                    header
-                                }
-                            }
-                        }
-                    };
-
-                    let old_content = std::fs::read(&file)
-                        .into_diagnostic()
-                        .with_context(|| miette!("While reading the file {file}"))?;
-
-                    let mut file_handle = std::fs::OpenOptions::new()
-                        .write(true)
-                        .truncate(true)
-                        .open(&file)
-                        .into_diagnostic()
-                        .with_context(|| miette!("Could not open file to write to it at {file}"))?;
-
-                    file_handle
-                        .write_all(header.as_bytes())
-                        .into_diagnostic()
-                        .with_context(|| miette!("Could not write new header at {file}"))?;
-
-                    file_handle
-                        .write_all(&old_content)
-                        .into_diagnostic()
-                        .with_context(|| miette!("Could not write new header at {file}"))?;
-                }
-            }
+    config.validate_licenses()?;
+    config.validate_comments()?;
+    config.validate_notebooks()?;
+
+    if let Some(name) = args.assume_language {
+        if !config.languages.contains_key(&name) {
+            bail!("Cannot assume the language {name}, as it is not configured");
         }
+        config.assumed_language = Some(name);
     }
+    config.parse_timeout = args.timeout_ms.map(Duration::from_millis);
 
-    Ok(())
-}
-
-fn verify_file(
-    langs: &HashMap<String, Language>,
-    config: &Config,
-    file: &Utf8Path,
-) -> Result<bool, miette::Error> {
-    let (language_config, mut parser) = load_language(langs, config, file)?;
-    let text = std::fs::read_to_string(file).into_diagnostic()?;
-    let Some(tree) = parser.parse(&text, None) else {
-        miette::bail!("Could not parse file")
+    let license_override = match (args.license_text, &args.license_text_file) {
+        (Some(text), _) => Some(text),
+        (None, Some(path)) => Some(
+            std::fs::read_to_string(path)
+                .into_diagnostic()
+                .with_context(|| miette!("Could not read the license text from {path}"))?,
+        ),
+        (None, None) => None,
     };
-    let mut cursor = tree.walk();
-    let mut comments = String::new();
-    for child in tree.root_node().named_children(&mut cursor) {
-        if let Some(conf) = language_config
-            .comments
-            .iter()
-            .find(|n| n.tree_sitter_name == child.grammar_name())
-        {
-            let text = child.utf8_text(text.as_bytes()).into_diagnostic()?;
+    if let Some(text) = license_override {
+        config.override_license_text(text);
+    }
 
-            match &conf.comment_kind {
-                CommentKind::Single(prefix) => {
-                    comments.push_str(text.trim_start_matches(prefix).trim());
-                    comments.push('\n');
-                }
-                CommentKind::Multi {
-                    start,
-                    end,
-                    between,
-                } => {
-                    comments.push_str(
-                        &text
-                            .trim_start_matches(start)
-                            .trim_end_matches(end)
+    config.expand_templates()?;
+    config.strip_trailing_newlines();
+    config.add_rules();
+    config.compile_regexes()?;
+    config.compile_queries(&langs)?;
+
+    let (selection, options) = match args.command {
+        Command::Verify {
+            selection,
+            fix,
+            count,
+            explain,
+            fail_on,
+            fail_fast,
+            list_files,
+            archive,
+            timings,
+            format,
+            output,
+            relative_to,
+        } => {
+            if archive.is_some() && config.license.year_from_git.take().is_some() {
+                warn!("Not checking copyright years, as archives have no git history");
+            }
+            if format == ReportFormat::Human && output.is_some() {
+                bail!("--output requires a machine readable --format, like json or sarif");
+            }
+            (
+                selection,
+                CheckOptions {
+                    fix,
+                    diff_output: None,
+                    update_years: false,
+                    default_to_current_dir: true,
+                    count,
+                    explain,
+                    fail_on,
+                    fail_fast,
+                    list_files,
+                    archive,
+                    timings,
+                    format,
+                    output,
+                    relative_to: report::absolute_path(&relative_to)?,
+                    jobs: args.jobs,
+                    color: args.color,
+                },
+            )
+        }
+        Command::Fix {
+            selection,
+            diff_output,
+            update_years,
+            create,
+        } => {
+            if let Some(path) = &create.create {
+                create_file(&config, path, create.lang.as_deref(), create.force)?;
+            }
+            (
+                selection,
+                CheckOptions {
+                    fix: true,
+                    diff_output,
+                    update_years,
+                    default_to_current_dir: false,
+                    count: false,
+                    explain: false,
+                    fail_on: FailOn::Any,
+                    fail_fast: false,
+                    list_files: false,
+                    archive: None,
+                    timings: false,
+                    format: ReportFormat::Human,
+                    output: None,
+                    relative_to: report::absolute_path(Utf8Path::new("."))?,
+                    jobs: args.jobs,
+                    color: args.color,
+                },
+            )
+        }
+        Command::ConfigSchema => unreachable!("The schema is printed before loading anything"),
+        Command::Doctor => unreachable!("The doctor loads everything by itself"),
+        Command::Stats { directory, format } => {
+            let stats = stats::collect(&langs, &config, &directory)?;
+            stats.print(format, args.color)?;
+            return Ok(true);
+        }
+        #[cfg(unix)]
+        Command::Serve { socket } => {
+            serve::serve(&langs, &config, &socket)?;
+            return Ok(true);
+        }
+        Command::Learn { directory, format } => {
+            learn::collect(&langs, &config, &directory)?.print(format)?;
+            return Ok(true);
+        }
+        Command::Try { lang } => {
+            if !config.languages.contains_key(&lang) {
+                bail!("Cannot try the language {lang}, as it is not configured");
+            }
+            config.assumed_language = Some(lang);
+            if config.license.year_from_git.take().is_some() {
+                warn!("Not checking copyright years, as content from stdin has no git history");
+            }
+
+            let content = std::io::read_to_string(std::io::stdin())
+                .into_diagnostic()
+                .with_context(|| miette!("Could not read the content to try from stdin"))?;
+            let file = Utf8Path::new(STDIN_FILE);
+            let status = verify_content(&langs, &config, file, &content)?;
+            if status.is_valid() {
+                println!("{file}: valid license header");
+            } else {
+                report_invalid_file(file, &status, args.color, None);
+            }
+            print!("{}", explain::explain(&config, file, &status));
+            return Ok(status.is_valid());
+        }
+    };
+
+    check_files(&langs, &config, selection, options)
+}
+
+/// How the files of a verify or fix run are checked
+struct CheckOptions {
+    fix: bool,
+    /// Write fixes as a patch to this file, instead of modifying the files
+    diff_output: Option<Utf8PathBuf>,
+    /// Extend the `{{year}}` ranges of valid files to the current year
+    update_years: bool,
+    /// Check the current directory if no files were given
+    default_to_current_dir: bool,
+    /// Only print the number of invalid files
+    count: bool,
+    /// Print why files are invalid
+    explain: bool,
+    /// Which invalid files are not fixed fail the run
+    fail_on: FailOn,
+    /// Stop at the first file failing the run
+    fail_fast: bool,
+    /// Only print the selected files and their languages
+    list_files: bool,
+    /// Verify the files in this archive instead of the selected ones
+    archive: Option<Utf8PathBuf>,
+    /// Print the slowest files after checking
+    timings: bool,
+    /// The format of the report, only the human readable one is printed while checking
+    format: ReportFormat,
+    /// Write the report to this file, still printing the human readable results
+    output: Option<Utf8PathBuf>,
+    /// The absolute directory reported paths are relative to
+    relative_to: Utf8PathBuf,
+    jobs: usize,
+    color: ColorChoice,
+}
+
+impl CheckOptions {
+    /// Whether the human readable results are printed while checking
+    fn prints_results(&self) -> bool {
+        !self.count && (self.format == ReportFormat::Human || self.output.is_some())
+    }
+}
+
+/// Verifies, and possibly fixes, the selected files, returning whether all of them are valid
+fn check_files(
+    langs: &HashMap<String, Language>,
+    config: &Config,
+    selection: FileSelection,
+    options: CheckOptions,
+) -> Result<bool, miette::Error> {
+    if let Some(archive) = &options.archive {
+        return archive::check(
+            langs,
+            config,
+            archive,
+            &selection.filter(),
+            selection.ignore_missing_grammar,
+            &options,
+        );
+    }
+
+    let no_follow_symlinks = selection.no_follow_symlinks;
+    let ignore_missing_grammar = selection.ignore_missing_grammar;
+    let files = selection
+        .selected_files(config, options.default_to_current_dir)?
+        .collect::<Vec<_>>();
+
+    if options.list_files {
+        for file in &files {
+            match detect_language(config, file) {
+                Some((name, _)) => println!("{file}: {name}"),
+                None => println!("{file}: no configured language"),
+            }
+        }
+        return Ok(true);
+    }
+
+    // Human readable results are printed as soon as a file is done, so that they stream in
+    let print_results = options.prints_results();
+    // Set once a file failed with --fail-fast, so that no further files are checked or reported
+    let stopped = AtomicBool::new(false);
+    let report_invalid = |file: &Utf8Path, status: &FileStatus, explanation: Option<&str>| {
+        if options.fail_fast && stopped.swap(true, Ordering::Relaxed) {
+            return CheckResult::Skipped;
+        }
+        let path = report::relative_path(file, &options.relative_to);
+        if print_results {
+            report_invalid_file(&path, status, options.color, explanation);
+        }
+        CheckResult::Invalid(Finding {
+            path,
+            outcome: status.outcome(),
+            message: status.problem().into_owned(),
+            line: 1,
+        })
+    };
+    let timings = Mutex::new(vec![]);
+    let results = parallel::map(&files, options.jobs, |file| {
+        if stopped.load(Ordering::Relaxed) {
+            debug!("Not checking {file}, as another file already failed");
+            return Ok(CheckResult::Skipped);
+        }
+
+        if ignore_missing_grammar && lacks_grammar(langs, config, file) {
+            return Ok(CheckResult::Skipped);
+        }
+
+        debug!("Checking {}", file);
+        let started = Instant::now();
+        let status = verify_file(langs, config, file)?;
+        if options.timings {
+            timings
+                .lock()
+                .expect("A thread panicked while recording timings")
+                .push((file.clone(), started.elapsed()));
+        }
+
+        // Writes a fix, or returns its diff if the files should not be modified
+        let apply_fix = |old_content: &str, new_content: &str| {
+            // Writing the same content would only change the modification time
+            if old_content == new_content {
+                debug!("{file} is unchanged by fixing it, not writing it");
+                return Ok(CheckResult::Fixed { diff: None });
+            }
+            if options.diff_output.is_some() {
+                return Ok(CheckResult::Fixed {
+                    diff: Some(patch::unified_diff(
+                        patch::repo_relative_path(file).as_str(),
+                        old_content,
+                        new_content,
+                    )),
+                });
+            }
+            write_file(file, new_content)?;
+            format_fixed_file(config, file);
+            Ok(CheckResult::Fixed { diff: None })
+        };
+
+        if status.is_valid() {
+            if options.update_years && !(no_follow_symlinks && file.is_symlink()) {
+                let old_content = std::fs::read_to_string(file)
+                    .into_diagnostic()
+                    .with_context(|| miette!("Could not read {file}"))?;
+                let new_content = updated_years(config, file, &status, &old_content);
+                return apply_fix(&old_content, new_content.as_deref().unwrap_or(&old_content));
+            }
+            return Ok(CheckResult::Valid);
+        }
+
+        if options.fix {
+            if status.year_mismatch.is_some() && status.has_license && status.has_banner {
+                warn!("Not fixing {file}, as copyright years cannot be fixed");
+                return Ok(report_invalid(file, &status, None));
+            }
+            if status.forbidden.is_some() {
+                warn!("Not fixing {file}, as forbidden phrases cannot be removed");
+                return Ok(report_invalid(file, &status, None));
+            }
+            if status.timed_out.is_some() {
+                warn!("Not fixing {file}, as parsing it took longer than the timeout");
+                return Ok(report_invalid(file, &status, None));
+            }
+
+            if file.is_symlink() {
+                if no_follow_symlinks {
+                    warn!("Not fixing {file}, as it is a symbolic link");
+                    return Ok(report_invalid(file, &status, None));
+                }
+
+                let target = file
+                    .canonicalize_utf8()
+                    .unwrap_or_else(|_| file.to_path_buf());
+                warn!("{file} is a symbolic link, fixing its target {target}");
+            }
+
+            // Headers that are only invalid because of an outdated range of years are updated
+            // instead of inserting another one
+            if status.has_header {
+                let old_content = std::fs::read_to_string(file)
+                    .into_diagnostic()
+                    .with_context(|| miette!("Could not read {file}"))?;
+                if let Some(new_content) = updated_years(config, file, &status, &old_content)
+                    && verify_content(langs, config, file, &new_content)?.is_valid()
+                {
+                    return apply_fix(&old_content, &new_content);
+                }
+            }
+
+            let (old_content, new_content) = fixed_content(langs, config, file, &status)?;
+            apply_fix(&old_content, &new_content)
+        } else if !options.fail_on.fails(status.outcome()) {
+            debug!("Ignoring {file}, as --fail-on does not include its outcome");
+            Ok(CheckResult::Valid)
+        } else {
+            let explanation =
+                (options.explain && print_results).then(|| explain::explain(config, file, &status));
+            Ok(report_invalid(file, &status, explanation.as_deref()))
+        }
+    })?;
+
+    let mut findings = vec![];
+    let mut diff = String::new();
+    for result in results {
+        match result {
+            CheckResult::Valid | CheckResult::Skipped => {}
+            CheckResult::Invalid(finding) => findings.push(finding),
+            CheckResult::Fixed { diff: file_diff } => diff.extend(file_diff),
+        }
+    }
+
+    print_findings(&findings, &options)?;
+    if options.timings {
+        print_timings(timings.into_inner().unwrap_or_default());
+    }
+
+    if let Some(path) = &options.diff_output {
+        std::fs::write(path, diff)
+            .into_diagnostic()
+            .with_context(|| miette!("Could not write the patch to {path}"))?;
+    }
+
+    Ok(findings.is_empty())
+}
+
+/// How many of the slowest files `--timings` lists
+const SLOWEST_FILES: usize = 10;
+
+/// Prints the files that took the longest to verify to stderr, so that reports stay intact
+fn print_timings(mut timings: Vec<(Utf8PathBuf, Duration)>) {
+    timings.sort_by(|a, b| b.1.cmp(&a.1));
+    let total = timings
+        .iter()
+        .map(|(_, duration)| *duration)
+        .sum::<Duration>();
+
+    eprintln!(
+        "Verifying {} files took {total:.2?}, the slowest were:",
+        timings.len()
+    );
+    for (file, duration) in timings.iter().take(SLOWEST_FILES) {
+        eprintln!("{duration:>12.2?}  {file}");
+    }
+}
+
+/// Prints the number of findings or writes their report, as requested by the options
+fn print_findings(findings: &[Finding], options: &CheckOptions) -> Result<(), miette::Error> {
+    if options.count {
+        println!("{}", findings.len());
+    }
+
+    if let Some(report) = report::render(findings, options.format)? {
+        match &options.output {
+            Some(path) => std::fs::write(path, report + "\n")
+                .into_diagnostic()
+                .with_context(|| miette!("Could not write the report to {path}"))?,
+            // Workflow commands are empty without findings, which needs no blank line
+            None if report.is_empty() => {}
+            None => println!("{report}"),
+        }
+    }
+
+    Ok(())
+}
+
+/// What happened to a single checked file
+enum CheckResult {
+    Valid,
+    Invalid(Finding),
+    /// The file was not checked, as another one already failed with `--fail-fast` or its
+    /// language has no grammar with `--ignore-missing-grammar`
+    Skipped,
+    /// The file was fixed, or the diff fixing it if it should not be modified
+    Fixed {
+        diff: Option<String>,
+    },
+}
+
+/// Computes the old and new content of a file, with whatever the given status reports as
+/// missing inserted at the top
+fn fixed_content(
+    langs: &HashMap<String, Language>,
+    config: &Config,
+    file: &Utf8Path,
+    status: &FileStatus,
+) -> Result<(String, String), miette::Error> {
+    let old_content = std::fs::read_to_string(file)
+        .into_diagnostic()
+        .with_context(|| miette!("While reading the file {file}"))?;
+    let new_content = fix_content(langs, config, file, status, &old_content)?;
+
+    Ok((old_content, new_content))
+}
+
+/// Inserts whatever the given status reports as missing into the content of `file`, which is
+/// the first code cell of notebooks
+fn fix_content(
+    langs: &HashMap<String, Language>,
+    config: &Config,
+    file: &Utf8Path,
+    status: &FileStatus,
+    old_content: &str,
+) -> Result<String, miette::Error> {
+    if !is_notebook(config, file) {
+        return insert_header(langs, config, file, status, old_content);
+    }
+
+    let Some(source) = notebook::first_code_cell(old_content)
+        .with_context(|| miette!("Could not read the notebook {file}"))?
+    else {
+        bail!("Cannot insert a license header into {file}, as the notebook has no code cells");
+    };
+    let source = insert_header(langs, config, file, status, &source)?;
+    notebook::replace_first_code_cell(old_content, &source)
+        .with_context(|| miette!("Could not write the notebook {file}"))
+}
+
+/// Inserts whatever the given status reports as missing into the content of `file`
+fn insert_header(
+    langs: &HashMap<String, Language>,
+    config: &Config,
+    file: &Utf8Path,
+    status: &FileStatus,
+    old_content: &str,
+) -> Result<String, miette::Error> {
+    let Some((name, language_config)) = detect_language(config, file) else {
+        bail!("Could not determine language for {}", file);
+    };
+
+    let Some(conf) = language_config.preferred_comment() else {
+        bail!(
+            "Cannot insert a license header into {file}, as no comment style is configured for the language {name}"
+        );
+    };
+
+    if status.timed_out.is_some() {
+        bail!(
+            "Cannot insert a license header into {file}, as parsing it took longer than the timeout"
+        );
+    }
+
+    if config.license.match_mode == MatchMode::Regex && !(status.has_license && status.has_footer) {
+        bail!("Cannot insert a license header into {file}, as the license is a regular expression");
+    }
+
+    let header = missing_header(config, file, language_config, conf, status);
+
+    // The header goes below front matter or a declaration it has to follow, or below a shebang,
+    // as that has to stay on the first line
+    let (prefix, rest) = if let Some(end) = front_matter_end(language_config, old_content) {
+        old_content.split_at(end)
+    } else if language_config.declaration.is_some()
+        && let Some(end) = declaration_end(
+            &mut load_language(langs, config, file)?.2,
+            language_config,
+            old_content,
+        )
+    {
+        old_content.split_at(end)
+    } else {
+        split_shebang(old_content)
+    };
+    let mut new_content = prefix.to_string();
+    if !prefix.is_empty() && !prefix.ends_with('\n') {
+        new_content.push('\n');
+    }
+    new_content.push_str(&header);
+    match leading_blank_lines(rest) {
+        Some(end) if config.license.keep_leading_blank && !header.is_empty() => {
+            new_content.push('\n');
+            new_content.push_str(&rest[end..]);
+        }
+        _ => new_content.push_str(rest),
+    }
+
+    if let Some(footer) = missing_footer(config, file, language_config, conf, status) {
+        if !new_content.is_empty() && !new_content.ends_with('\n') {
+            new_content.push('\n');
+        }
+        new_content.push_str(&footer);
+    }
+
+    Ok(new_content)
+}
+
+/// The offset of the first line that is not blank, if `content` starts with blank lines
+fn leading_blank_lines(content: &str) -> Option<usize> {
+    let end = content
+        .split_inclusive('\n')
+        .take_while(|line| line.ends_with('\n') && line.trim().is_empty())
+        .map(str::len)
+        .sum::<usize>();
+    (end > 0).then_some(end)
+}
+
+/// The offset of the line following a leading front matter block, if the language allows one
+fn front_matter_end(language_config: &LanguageConfig, content: &str) -> Option<usize> {
+    if !language_config.front_matter {
+        return None;
+    }
+
+    let mut end = 0;
+    for (index, line) in content.split_inclusive('\n').enumerate() {
+        end += line.len();
+        match (index, line.trim_end() == FRONT_MATTER_DELIMITER) {
+            (0, false) => return None,
+            (1.., true) => return Some(end),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The offset of the line following the declaration, if the header belongs after it
+fn declaration_end(
+    parser: &mut tree_sitter::Parser,
+    language_config: &LanguageConfig,
+    content: &str,
+) -> Option<usize> {
+    let declaration = language_config
+        .declaration
+        .as_ref()
+        .filter(|declaration| declaration.header == HeaderPosition::After)?;
+    let tree = parser.parse(content, None)?;
+    let mut cursor = tree.walk();
+    let end = tree
+        .root_node()
+        .named_children(&mut cursor)
+        .find(|child| child.grammar_name() == declaration.node_kind)?
+        .end_byte();
+
+    Some(
+        content[end..]
+            .find('\n')
+            .map_or(content.len(), |newline| end + newline + 1),
+    )
+}
+
+/// The offset of the end of a `#!` line starting the content, excluding its newline
+///
+/// A byte order mark in front of it is ignored, as editors might add one to any file.
+fn shebang_end(content: &str) -> Option<usize> {
+    let start = content.len() - content.trim_start_matches('\u{feff}').len();
+    if !content[start..].starts_with("#!") {
+        return None;
+    }
+
+    Some(content.find('\n').unwrap_or(content.len()))
+}
+
+/// Splits a leading `#!` line, including its newline, from the rest of the content
+fn split_shebang(content: &str) -> (&str, &str) {
+    if shebang_end(content).is_none() {
+        return ("", content);
+    }
+
+    match content.find('\n') {
+        Some(newline) => content.split_at(newline + 1),
+        None => (content, ""),
+    }
+}
+
+/// Renders whatever the given status reports as missing as comments of the given kind
+fn missing_header(
+    config: &Config,
+    file: &Utf8Path,
+    language_config: &LanguageConfig,
+    conf: &CommentConfig,
+    status: &FileStatus,
+) -> String {
+    let mut header = String::new();
+    if !status.has_banner
+        && let Some(banner) = &language_config.required_banner
+    {
+        header.push_str(&render_header(conf, banner, None));
+    }
+    if !status.has_license {
+        header.push_str(&render_header(
+            conf,
+            &template::inserted_text(&template::with_file(
+                config.license_text(language_config),
+                file,
+                false,
+            )),
+            config.license.wrap,
+        ));
+    }
+    header
+}
+
+/// The comment to append to a file if its footer is missing
+fn missing_footer(
+    config: &Config,
+    file: &Utf8Path,
+    language_config: &LanguageConfig,
+    conf: &CommentConfig,
+    status: &FileStatus,
+) -> Option<String> {
+    let footer = config
+        .footer_text(language_config)
+        .filter(|_| !status.has_footer)?;
+    Some(render_header(
+        conf,
+        &template::inserted_text(&template::with_file(footer, file, false)),
+        config.license.wrap,
+    ))
+}
+
+/// Creates a new file containing only the license header
+fn create_file(
+    config: &Config,
+    file: &Utf8Path,
+    language: Option<&str>,
+    force: bool,
+) -> Result<(), miette::Error> {
+    let (name, language_config) = match language {
+        Some(name) => config
+            .languages
+            .get_key_value(name)
+            .ok_or_else(|| miette!("The language {name} is not configured"))?,
+        None => detect_language(config, file).ok_or_else(|| {
+            miette!("Could not determine language for {file}, please pass it with --lang")
+        })?,
+    };
+
+    if language_config.notebook_language.is_some()
+        || language.is_none() && is_notebook(config, file)
+    {
+        bail!("Cannot create the notebook {file}, as notebooks need more than a header");
+    }
+
+    let Some(conf) = language_config.preferred_comment() else {
+        bail!(
+            "Cannot create {file} with a license header, as no comment style is configured for the language {name}"
+        );
+    };
+
+    let status = FileStatus {
+        has_license: false,
+        has_banner: false,
+        has_header: false,
+        has_footer: false,
+        near_match: None,
+        too_small: false,
+        found: vec![],
+        year_mismatch: None,
+        forbidden: None,
+        timed_out: None,
+        first_import: None,
+    };
+    let mut header = missing_header(config, file, language_config, conf, &status);
+    header.extend(missing_footer(config, file, language_config, conf, &status));
+
+    let mut options = std::fs::OpenOptions::new();
+    if force {
+        options.write(true).create(true).truncate(true);
+    } else {
+        options.write(true).create_new(true);
+    }
+
+    let mut file_handle = match options.open(file) {
+        Ok(file_handle) => file_handle,
+        Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => {
+            bail!("{file} already exists, pass --force to overwrite it");
+        }
+        Err(error) => {
+            return Err(error)
+                .into_diagnostic()
+                .with_context(|| miette!("Could not create {file}"));
+        }
+    };
+
+    file_handle
+        .write_all(header.as_bytes())
+        .into_diagnostic()
+        .with_context(|| miette!("Could not write new header at {file}"))?;
+
+    Ok(())
+}
+
+/// The content of a valid file with the `{{year}}` ranges of its header extended to the current
+/// year, `None` if they are up to date
+fn updated_years(
+    config: &Config,
+    file: &Utf8Path,
+    status: &FileStatus,
+    content: &str,
+) -> Option<String> {
+    let (_, language_config) = detect_language(config, file)?;
+    if config.license.match_mode == MatchMode::Regex {
+        return None;
+    }
+
+    let license_text = template::with_file(
+        template::verified_part(config.license_text(language_config)),
+        file,
+        false,
+    );
+    let expected = expected_lines(&license_text, &config.license, language_config);
+    let mut new_content = Cow::Borrowed(content);
+    for (found, expected) in status.found.iter().zip(&expected) {
+        let Some(updated) = template::updated_years(expected, found) else {
+            continue;
+        };
+        if !new_content.contains(found.as_str()) {
+            warn!("Not updating the years of {file}, as its header line `{found}` was normalized");
+            return None;
+        }
+        new_content = Cow::Owned(new_content.replacen(found.as_str(), &updated, 1));
+    }
+
+    match new_content {
+        Cow::Borrowed(_) => None,
+        Cow::Owned(new_content) => Some(new_content),
+    }
+}
+
+/// Replaces the content of an existing file
+///
+/// The file is overwritten in place instead of replacing it with a new one, so that its mode,
+/// ownership and hard links are kept.
+fn write_file(file: &Utf8Path, content: &str) -> Result<(), miette::Error> {
+    let mut file_handle = std::fs::OpenOptions::new()
+        .write(true)
+        .truncate(true)
+        .open(file)
+        .into_diagnostic()
+        .with_context(|| miette!("Could not open file to write to it at {file}"))?;
+
+    file_handle
+        .write_all(content.as_bytes())
+        .into_diagnostic()
+        .with_context(|| miette!("Could not write new header at {file}"))?;
+
+    Ok(())
+}
+
+/// Runs the `format_command` of the file's language on a fixed file
+fn format_fixed_file(config: &Config, file: &Utf8Path) {
+    let Some((program, args)) = detect_language(config, file)
+        .and_then(|(_, language_config)| language_config.format_command.as_deref())
+        .and_then(<[String]>::split_first)
+    else {
+        return;
+    };
+
+    match std::process::Command::new(program)
+        .args(args)
+        .arg(file)
+        .output()
+    {
+        Ok(output) if output.status.success() => debug!("Formatted {file} with {program}"),
+        Ok(output) => warn!(
+            "Formatting {file} with {program} failed with {}, keeping it unformatted: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ),
+        Err(error) => warn!(
+            "Could not run the formatter {program} for {file}, keeping it unformatted: {error}"
+        ),
+    }
+}
+
+/// Renders the license text as a comment of the given configuration, ending in a newline
+///
+/// `Single` comment lines longer than `wrap` columns, including the marker, are wrapped.
+fn render_header(conf: &CommentConfig, license_text: &str, wrap: Option<usize>) -> String {
+    match &conf.comment_kind {
+        CommentKind::Single(prefix) => license_text
+            .lines()
+            .flat_map(|line| match wrap {
+                Some(width) => wrap_line(line, width.saturating_sub(prefix.chars().count() + 1)),
+                None => vec![line.to_string()],
+            })
+            .map(|line| {
+                if line.is_empty() {
+                    match conf.blank_line_style {
+                        BlankLineStyle::Marker => format!("{prefix}\n"),
+                        BlankLineStyle::Empty => String::from("\n"),
+                    }
+                } else {
+                    format!("{prefix} {line}\n")
+                }
+            })
+            .collect::<String>(),
+        CommentKind::Multi {
+            start,
+            start_alt,
+            end,
+            between,
+        } => {
+            let mut lines = license_text.lines();
+            let mut header = format!(
+                "{} {}",
+                start_alt.as_deref().unwrap_or(start),
+                lines.next().unwrap_or_default()
+            );
+
+            for line in lines {
+                header.push('\n');
+                match between {
+                    Some(between) => header.push_str(format!(" {between} {line}").trim_end()),
+                    None => header.push_str(line),
+                }
+            }
+
+            header.push_str(&format!(" {end}\n"));
+            header
+        }
+    }
+}
+
+/// Splits a line at whitespace into lines of at most `width` characters
+///
+/// Words longer than `width` are put on a line of their own.
+fn wrap_line(line: &str, width: usize) -> Vec<String> {
+    if line.chars().count() <= width {
+        return vec![line.to_string()];
+    }
+
+    let mut lines = vec![];
+    let mut current = String::new();
+    for word in line.split_whitespace() {
+        if !current.is_empty() && current.chars().count() + 1 + word.chars().count() > width {
+            lines.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+    lines.push(current);
+    lines
+}
+
+fn report_invalid_file(
+    file: &Utf8Path,
+    status: &FileStatus,
+    color: ColorChoice,
+    explanation: Option<&str>,
+) {
+    let message = status.problem();
+
+    // Lock stdout for the whole report, so that reports of parallel checks do not interleave
+    let mut stdout = std::io::stdout().lock();
+    let _ = if color.enabled_for(&stdout) {
+        writeln!(stdout, "{}: {}", file.bold(), message.red())
+    } else {
+        writeln!(stdout, "{file}: {message}")
+    };
+    if let Some(explanation) = explanation {
+        let _ = write!(stdout, "{explanation}");
+    }
+}
+
+/// The result of verifying a single file
+struct FileStatus {
+    has_license: bool,
+    /// Whether the language's `required_banner` is present, always true if there is none
+    has_banner: bool,
+    /// Whether the file starts with any comment at all
+    has_header: bool,
+    /// Whether the trailing comments end with the language's `footer`, always true if there is none
+    has_footer: bool,
+    /// The edit distance of a header within the license's `max_edit_distance`
+    near_match: Option<usize>,
+    /// Whether the file is too small to need a header, like a notebook without code cells, in
+    /// which case it counts as valid
+    too_small: bool,
+    /// The lines of the leading comments, without the banner
+    found: Vec<String>,
+    /// Set if the license's `year_from_git` is not the year in the header
+    year_mismatch: Option<year::YearMismatch>,
+    /// The first of the license's `forbidden` phrases the leading comments contain
+    forbidden: Option<String>,
+    /// Set to the `--timeout-ms` if parsing the file took longer, in which case it was not
+    /// verified at all
+    timed_out: Option<Duration>,
+    /// The line of the first import, if the license was not found before it
+    first_import: Option<usize>,
+}
+
+impl FileStatus {
+    /// The status of a file that is too small to need a header, or a notebook without code
+    fn needs_no_header() -> Self {
+        FileStatus {
+            has_license: true,
+            has_banner: true,
+            has_header: false,
+            has_footer: true,
+            near_match: None,
+            too_small: true,
+            found: vec![],
+            year_mismatch: None,
+            forbidden: None,
+            timed_out: None,
+            first_import: None,
+        }
+    }
+
+    /// The status of a file whose parsing took longer than `timeout`
+    fn timed_out(timeout: Duration) -> Self {
+        FileStatus {
+            has_license: false,
+            has_banner: false,
+            has_header: false,
+            has_footer: false,
+            near_match: None,
+            too_small: false,
+            found: vec![],
+            year_mismatch: None,
+            forbidden: None,
+            timed_out: Some(timeout),
+            first_import: None,
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        self.has_license
+            && self.has_banner
+            && self.has_footer
+            && self.year_mismatch.is_none()
+            && self.forbidden.is_none()
+            && self.timed_out.is_none()
+    }
+
+    /// Describes why the file is invalid
+    fn problem(&self) -> Cow<'static, str> {
+        if let Some(timeout) = self.timed_out {
+            return Cow::Owned(format!(
+                "parsing took longer than the timeout of {} ms",
+                timeout.as_millis()
+            ));
+        }
+
+        if let Some(phrase) = &self.forbidden {
+            return Cow::Owned(format!(
+                "license header contains the forbidden phrase `{phrase}`"
+            ));
+        }
+
+        if self.outcome() == Outcome::NearMatch
+            && let Some(distance) = self.near_match
+        {
+            return Cow::Owned(format!(
+                "license header nearly matches, {distance} edits from the expected one"
+            ));
+        }
+
+        match self.year_mismatch {
+            Some(mismatch) if self.has_license && self.has_banner => match mismatch.found {
+                Some(found) => Cow::Owned(format!(
+                    "copyright year {found} is not the year {} from git",
+                    mismatch.expected
+                )),
+                None => Cow::Owned(format!(
+                    "copyright year is missing, expected the year {} from git",
+                    mismatch.expected
+                )),
+            },
+            None if self.has_license && self.has_banner && !self.has_footer => {
+                Cow::Borrowed("missing or incorrect license footer")
+            }
+            _ => match self.first_import {
+                Some(line) => Cow::Owned(format!(
+                    "missing or incorrect license header before the first import on line {line}"
+                )),
+                None => Cow::Borrowed("missing or incorrect license header"),
+            },
+        }
+    }
+
+    fn outcome(&self) -> Outcome {
+        if self.timed_out.is_some() {
+            Outcome::TimedOut
+        } else if self.too_small {
+            Outcome::TooSmall
+        } else if self.is_valid() {
+            Outcome::Valid
+        } else if self.near_match.is_some() && self.has_banner && self.has_footer {
+            Outcome::NearMatch
+        } else if self.has_header {
+            Outcome::Mismatch
+        } else {
+            Outcome::Missing
+        }
+    }
+}
+
+/// How a file fared during verification
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Outcome {
+    /// The file has the expected license header
+    Valid,
+    /// The file has no header comment at all
+    Missing,
+    /// The file has a header comment, but it is not the expected one
+    Mismatch,
+    /// The file has a header within the license's `max_edit_distance` of the expected one
+    NearMatch,
+    /// The file is below its language's `min_lines` or `min_bytes`, so it needs no header
+    TooSmall,
+    /// Parsing the file took longer than `--timeout-ms`, so it could not be verified
+    TimedOut,
+}
+
+fn verify_file(
+    langs: &HashMap<String, Language>,
+    config: &Config,
+    file: &Utf8Path,
+) -> Result<FileStatus, miette::Error> {
+    let started = Instant::now();
+    let content = std::fs::read_to_string(file).into_diagnostic()?;
+    trace!("Read {file} in {:?}", started.elapsed());
+    verify_content(langs, config, file, &content)
+}
+
+/// Verifies the given content of `file`, which is only used to determine its language
+fn verify_content(
+    langs: &HashMap<String, Language>,
+    config: &Config,
+    file: &Utf8Path,
+    content: &str,
+) -> Result<FileStatus, miette::Error> {
+    let source;
+    let content = if is_notebook(config, file) {
+        let Some(cell) = notebook::first_code_cell(content)
+            .with_context(|| miette!("Could not read the notebook {file}"))?
+        else {
+            debug!("Skipping {file}, as the notebook has no code cells");
+            return Ok(FileStatus::needs_no_header());
+        };
+        source = cell;
+        source.as_str()
+    } else {
+        content
+    };
+
+    if let Some((name, language_config)) = detect_language(config, file)
+        && language_config.is_too_small(content)
+    {
+        debug!("Skipping {file}, as it is below the minimum size of the language {name}");
+        return Ok(FileStatus::needs_no_header());
+    }
+
+    let ExtractedComments {
+        language_config,
+        comments,
+        trailing_comments,
+        has_syntax_errors,
+        first_import,
+        ..
+    } = {
+        let started = Instant::now();
+        let extracted = extract_comments(langs, config, file, content)?;
+        trace!("Parsed {file} in {:?}", started.elapsed());
+        match (extracted, config.parse_timeout) {
+            (Some(extracted), _) => extracted,
+            (None, Some(timeout)) => {
+                debug!("Gave up parsing {file} after {} ms", timeout.as_millis());
+                return Ok(FileStatus::timed_out(timeout));
+            }
+            (None, None) => bail!("Could not parse {file}"),
+        }
+    };
+
+    let started = Instant::now();
+    let is_regex = config.license.match_mode == MatchMode::Regex;
+    let license_text = template::with_file(
+        template::verified_part(config.license_text(language_config)),
+        file,
+        is_regex,
+    );
+    let license_text = license_text.as_ref();
+    let mut found = header_lines(&comments, &config.license, language_config);
+
+    let has_banner = match &language_config.required_banner {
+        Some(banner) if !banner.trim().is_empty() => {
+            let banner = header_lines(banner, &config.license, language_config);
+            let position = found
+                .windows(banner.len())
+                .position(|window| window == banner.as_slice());
+            if let Some(position) = position {
+                found.drain(position..position + banner.len());
+            } else {
+                debug!("Missing required banner: {}", banner.join("\n"));
+            }
+            position.is_some()
+        }
+        _ => true,
+    };
+
+    let has_header = !found.is_empty();
+    let expected = expected_lines(license_text, &config.license, language_config);
+    let regex = match config.license.match_mode {
+        MatchMode::Regex => {
+            Some(config.regex(&license_regex(license_text, config.license.verify_lines))?)
+        }
+        MatchMode::Exact | MatchMode::Normalized => None,
+    };
+    let starts_with_license = |found: &[Cow<'_, str>]| match &regex {
+        Some(regex) => regex.is_match(&found.join("\n")),
+        None if config.license.wrap.is_some() => {
+            let words = |lines: &[Cow<'_, str>]| {
+                lines
+                    .iter()
+                    .flat_map(|line| line.split_whitespace().map(str::to_string))
+                    .collect::<Vec<_>>()
+            };
+            starts_with_lines(&words(found), &words(&expected))
+        }
+        None => starts_with_lines(found, &expected),
+    };
+    // Stray comments may precede the header, unless it has to be the first comment
+    let last_start = if config.license.header_is_first_comment {
+        0
+    } else {
+        found.len()
+    };
+    let start = (0..=last_start).find(|&start| starts_with_license(&found[start..]));
+    if let Some(start) = start
+        && start > 0
+    {
+        debug!("Ignoring {start} comment lines above the license header of {file}");
+        found.drain(..start);
+    }
+    let has_license = start.is_some();
+    if !has_license {
+        debug!("Expected: {license_text}\nGot: {}", found.join("\n"));
+
+        if has_syntax_errors {
+            warn!(
+                "{file} contains syntax errors, which might prevent detecting its license header"
+            );
+        }
+    }
+
+    let near_match = match config.license.max_edit_distance {
+        Some(max)
+            if !has_license && has_header && config.license.match_mode != MatchMode::Regex =>
+        {
+            let found = found[..expected.len().min(found.len())].join("\n");
+            distance::bounded_levenshtein(&found, &expected.join("\n"), max)
+        }
+        _ => None,
+    };
+
+    let has_footer = match config.footer_text(language_config) {
+        Some(footer) => {
+            let found = header_lines(&trailing_comments, &config.license, language_config);
+            let footer = template::with_file(template::verified_part(footer), file, is_regex);
+            let footer = footer.as_ref();
+            match config.license.match_mode {
+                MatchMode::Exact | MatchMode::Normalized => {
+                    let footer = header_lines(footer, &config.license, language_config);
+                    found.len() >= footer.len()
+                        && starts_with_lines(&found[found.len() - footer.len()..], &footer)
+                }
+                MatchMode::Regex => config
+                    .regex(&footer_regex(footer))?
+                    .is_match(&found.join("\n")),
+            }
+        }
+        None => true,
+    };
+    if !has_footer {
+        debug!("Missing footer, got: {trailing_comments}");
+    }
+
+    let year_mismatch = match config.license.year_from_git {
+        Some(which) if has_license => {
+            year::check(file, which, &found[..expected.len().min(found.len())])?
+        }
+        _ => None,
+    };
+    let forbidden = if config.license.forbidden.is_empty() {
+        None
+    } else {
+        let words = comments.split_whitespace().collect::<Vec<_>>().join(" ");
+        config
+            .license
+            .forbidden
+            .iter()
+            .find(|phrase| {
+                let phrase = phrase.split_whitespace().collect::<Vec<_>>().join(" ");
+                !phrase.is_empty() && words.contains(&phrase)
+            })
+            .cloned()
+    };
+    trace!("Compared the header of {file} in {:?}", started.elapsed());
+
+    Ok(FileStatus {
+        has_license,
+        has_banner,
+        has_header,
+        has_footer,
+        near_match,
+        too_small: false,
+        found: found.into_iter().map(Cow::into_owned).collect(),
+        year_mismatch,
+        forbidden,
+        timed_out: None,
+        first_import: first_import.filter(|_| !has_license),
+    })
+}
+
+/// The comments a file's header is searched in
+struct ExtractedComments<'a> {
+    name: &'a String,
+    language_config: &'a LanguageConfig,
+    /// The text of the comments without their markers, one comment per line
+    comments: String,
+    /// The text of the comments after the last other node, like `comments`
+    trailing_comments: String,
+    has_syntax_errors: bool,
+    /// The line of the first node of the language's `import_node_kind`, starting at 1
+    first_import: Option<usize>,
+}
+
+/// Extracts the leading top level comments of a file with the given content, i.e. those before
+/// any other node
+///
+/// Returns `None` if parsing the file took longer than the `--timeout-ms`.
+fn extract_comments<'a>(
+    langs: &HashMap<String, Language>,
+    config: &'a Config,
+    file: &Utf8Path,
+    content: &str,
+) -> Result<Option<ExtractedComments<'a>>, miette::Error> {
+    if let Some((name, language_config)) = detect_language(config, file)
+        && let Some(command) = &language_config.extractor
+    {
+        let text = &content[front_matter_end(language_config, content).unwrap_or(0)..];
+        return Ok(Some(ExtractedComments {
+            name,
+            language_config,
+            comments: extractor::leading_comments(command, file, text)?,
+            trailing_comments: String::new(),
+            has_syntax_errors: false,
+            first_import: None,
+        }));
+    }
+
+    let (name, language_config, mut parser) = load_language(langs, config, file)?;
+    let offset = front_matter_end(language_config, content).unwrap_or(0);
+    let text = &content[offset..];
+    let Some(tree) = parse(&mut parser, text, config.parse_timeout) else {
+        if config.parse_timeout.is_some() {
+            return Ok(None);
+        }
+        miette::bail!("Could not parse file")
+    };
+    let mut cursor = tree.walk();
+    let mut comments = String::new();
+    let children = tree
+        .root_node()
+        .named_children(&mut cursor)
+        .collect::<Vec<_>>();
+    let is_comment = |node: &tree_sitter::Node| {
+        language_config
+            .comments
+            .iter()
+            .any(|conf| conf.tree_sitter_name == node.grammar_name())
+    };
+    // Some grammars have a node of their own for the shebang, which may precede the header
+    let shebang_end = shebang_end(text);
+    let is_shebang =
+        |node: &tree_sitter::Node| shebang_end.is_some_and(|end| node.start_byte() < end);
+    let captured = language_config
+        .query
+        .as_ref()
+        .map(|query| captured_comments(query, &tree, text));
+    let first_import = language_config.import_node_kind.as_ref().and_then(|kind| {
+        children
+            .iter()
+            .find(|node| node.grammar_name() == kind)
+            .map(tree_sitter::Node::start_byte)
+    });
+    collect_comments(
+        header_nodes(&children, language_config)
+            .iter()
+            .copied()
+            .take_while(|node| node.is_error() || is_comment(node) || is_shebang(node))
+            .take_while(|node| first_import.is_none_or(|start| node.start_byte() < start)),
+        file,
+        language_config,
+        captured.as_ref(),
+        text,
+        &mut comments,
+    );
+
+    let mut trailing_comments = String::new();
+    if language_config.footer.is_some() {
+        let start = match children.iter().rposition(|node| !is_comment(node)) {
+            Some(index) => index + 1,
+            // In files consisting only of comments, like license stubs, every comment is both
+            // leading and trailing, so that the header and footer are found without duplicating
+            // either
+            None => {
+                debug!("{file} consists only of comments, searching all of them for the footer");
+                0
+            }
+        };
+        collect_comments(
+            children[start..].iter().copied(),
+            file,
+            language_config,
+            captured.as_ref(),
+            text,
+            &mut trailing_comments,
+        );
+    }
+
+    if comments.is_empty() && !language_config.comment_containers.is_empty() {
+        for node in &children {
+            if is_shebang(node) {
+                continue;
+            }
+            let is_container = language_config
+                .comment_containers
+                .iter()
+                .any(|name| name == node.grammar_name());
+            // Just like the top level comments, the header must precede the first import
+            let after_import = first_import.is_some_and(|start| node.start_byte() >= start);
+            if !is_container || after_import {
+                break;
+            }
+
+            let mut cursor = node.walk();
+            let nodes = node.named_children(&mut cursor).collect::<Vec<_>>();
+            // The head of a container, like the name of a TOML table, may precede its comments
+            // on its first line
+            let head = nodes
+                .iter()
+                .take_while(|child| {
+                    !is_comment(child) && child.start_position().row == node.start_position().row
+                })
+                .count();
+            let leading = nodes[head..]
+                .iter()
+                .take_while(|child| child.is_error() || is_comment(child) || is_shebang(child))
+                .count();
+            collect_comments(
+                nodes[head..head + leading].iter().copied(),
+                file,
+                language_config,
+                captured.as_ref(),
+                text,
+                &mut comments,
+            );
+            // Code in the container ends the leading comments, just like at the top level
+            if leading == 0 || head + leading < nodes.len() {
+                break;
+            }
+        }
+    }
+
+    Ok(Some(ExtractedComments {
+        name,
+        language_config,
+        comments,
+        trailing_comments,
+        has_syntax_errors: tree.root_node().has_error(),
+        first_import: first_import.map(|start| content[..offset + start].matches('\n').count() + 1),
+    }))
+}
+
+/// Parses `text`, giving up after `timeout`
+///
+/// Returns `None` if parsing took too long or was not possible at all.
+fn parse(
+    parser: &mut tree_sitter::Parser,
+    text: &str,
+    timeout: Option<Duration>,
+) -> Option<tree_sitter::Tree> {
+    let Some(timeout) = timeout else {
+        return parser.parse(text, None);
+    };
+
+    let started = Instant::now();
+    let mut exceeded = |_: &tree_sitter::ParseState| started.elapsed() > timeout;
+    let options = tree_sitter::ParseOptions::new().progress_callback(&mut exceeded);
+    parser.parse_with_options(
+        &mut |offset, _| text.as_bytes().get(offset..).unwrap_or_default(),
+        None,
+        Some(options),
+    )
+}
+
+/// The ids of the nodes captured as `@comment` by a language's comment query
+fn captured_comments(
+    query: &tree_sitter::Query,
+    tree: &tree_sitter::Tree,
+    text: &str,
+) -> HashSet<usize> {
+    let Some(index) = query.capture_index_for_name(COMMENT_CAPTURE) else {
+        return HashSet::new();
+    };
+
+    let mut captured = HashSet::new();
+    let mut cursor = tree_sitter::QueryCursor::new();
+    let mut matches = cursor.matches(query, tree.root_node(), text.as_bytes());
+    while let Some(query_match) = matches.next() {
+        captured.extend(
+            query_match
+                .captures
+                .iter()
+                .filter(|capture| capture.index == index)
+                .map(|capture| capture.node.id()),
+        );
+    }
+    captured
+}
+
+/// The top level nodes which may contain the header, depending on the language's declaration
+fn header_nodes<'n, 'tree>(
+    nodes: &'n [tree_sitter::Node<'tree>],
+    language_config: &LanguageConfig,
+) -> &'n [tree_sitter::Node<'tree>] {
+    let Some(declaration) = &language_config.declaration else {
+        return nodes;
+    };
+
+    match nodes
+        .iter()
+        .position(|node| node.grammar_name() == declaration.node_kind)
+    {
+        Some(index) => match declaration.header {
+            HeaderPosition::Before => &nodes[..index],
+            HeaderPosition::After => &nodes[index + 1..],
+        },
+        None => nodes,
+    }
+}
+
+/// Appends the text of all comments among `nodes` to `comments`, without their comment markers
+///
+/// Comments are appended in the order of `nodes`, which is their order in the source, regardless
+/// of their kind. A header may thus consist of e.g. a block comment followed by line comments,
+/// with blank lines but no code between them.
+///
+/// Error nodes are searched for comments up to their first code, which also ends the comments
+/// among `nodes`. Returns whether such code was found.
+fn collect_comments<'tree>(
+    nodes: impl Iterator<Item = tree_sitter::Node<'tree>>,
+    file: &Utf8Path,
+    language_config: &LanguageConfig,
+    captured: Option<&HashSet<usize>>,
+    text: &str,
+    comments: &mut String,
+) -> bool {
+    // Looked up once, as it searches the whole first line, which is all of a minified file
+    let shebang_end = shebang_end(text);
+    let is_comment = |node: &tree_sitter::Node| {
+        language_config
+            .comments
+            .iter()
+            .any(|conf| conf.tree_sitter_name == node.grammar_name())
+    };
+    for child in nodes {
+        // Syntax errors can cause tree-sitter to wrap the header in an error node, together with
+        // the code following it, whose comments are not leading ones
+        if child.is_error() {
+            let mut cursor = child.walk();
+            let children = child.children(&mut cursor).collect::<Vec<_>>();
+            let leading = children
+                .iter()
+                .take_while(|node| {
+                    node.is_error()
+                        || is_comment(node)
+                        || shebang_end.is_some_and(|end| node.start_byte() < end)
+                })
+                .count();
+            let nested_code = collect_comments(
+                children[..leading].iter().copied(),
+                file,
+                language_config,
+                captured,
+                text,
+                comments,
+            );
+            if nested_code || leading < children.len() {
+                return true;
+            }
+            continue;
+        }
+
+        if !is_comment(&child) {
+            continue;
+        }
+
+        if captured.is_some_and(|captured| !captured.contains(&child.id())) {
+            debug!(
+                "Skipping the {} node at bytes {}..{} in {file}, as the comment query does not capture it",
+                child.grammar_name(),
+                child.start_byte(),
+                child.end_byte()
+            );
+            continue;
+        }
+
+        // A shebang looks like a comment to many grammars, but is not part of the header
+        if shebang_end.is_some_and(|end| child.start_byte() < end) {
+            continue;
+        }
+
+        // Whitespace is not part of the tree, but a blank line between two comments is part of
+        // the header
+        let gap = &text[..child.start_byte()];
+        let gap = &gap[gap.trim_end().len()..];
+        if !comments.is_empty() && gap.matches('\n').count() > 1 {
+            comments.push('\n');
+        }
+
+        let text = match child.utf8_text(text.as_bytes()) {
+            Ok(text) => text,
+            Err(error) => {
+                warn!(
+                    ?error,
+                    "Skipping the {} node at bytes {}..{} in {file}, as it is not valid UTF-8",
+                    child.grammar_name(),
+                    child.start_byte(),
+                    child.end_byte(),
+                );
+                continue;
+            }
+        };
+        let Some(conf) = comment_config(language_config, child.grammar_name(), text) else {
+            continue;
+        };
+
+        match &conf.comment_kind {
+            CommentKind::Single(prefix) => {
+                let line = strip_markers(text, prefix, conf.case_insensitive_marker);
+                comments.push_str(strip_separator(line).trim_end());
+                comments.push('\n');
+            }
+            CommentKind::Multi {
+                start,
+                start_alt,
+                end,
+                between,
+            } => {
+                let body = start_alt
+                    .as_deref()
+                    .and_then(|start_alt| text.strip_prefix(start_alt))
+                    .or_else(|| text.strip_prefix(start.as_str()))
+                    .unwrap_or(text);
+                let body = body.strip_suffix(end.as_str()).unwrap_or(body);
+
+                // A comment on a single line, like `/* MIT */`, is one line of text, which
+                // is trimmed just like the interior lines of longer comments
+                match between {
+                    Some(between) => comments.push_str(
+                        &body
                             .lines()
                             .map(|line| {
-                                line.trim_start_matches(between.as_deref().unwrap_or_default())
-                                    .trim()
+                                strip_separator(line.trim_start().trim_start_matches(between))
+                                    .trim_end()
                             })
                             .collect::<Vec<&str>>()
                             .join("\n"),
-                    );
+                    ),
+                    None => comments.push_str(&dedent(body)),
                 }
+                // The comment might end on a line with code or another comment, which must
+                // not end up on the same line of the header
+                comments.push('\n');
+            }
+        }
+    }
+
+    false
+}
+
+/// The configuration of a comment node with the given kind and text
+///
+/// If several `Single` comments share the node kind, the one with the longest marker the text
+/// starts with is used, so that e.g. `//!` comments are not taken for `//` comments.
+fn comment_config<'a>(
+    language_config: &'a LanguageConfig,
+    kind: &str,
+    text: &str,
+) -> Option<&'a CommentConfig> {
+    let mut candidates = language_config
+        .comments
+        .iter()
+        .filter(|conf| conf.tree_sitter_name == kind);
+
+    let by_marker = candidates
+        .clone()
+        .filter_map(|conf| match &conf.comment_kind {
+            CommentKind::Single(marker)
+                if strip_markers(text, marker, conf.case_insensitive_marker).len() < text.len() =>
+            {
+                Some((marker.len(), conf))
             }
+            _ => None,
+        })
+        .max_by_key(|(length, _)| *length)
+        .map(|(_, conf)| conf);
+
+    by_marker.or_else(|| candidates.next())
+}
+
+/// Removes the indentation shared by the lines of a block comment without `between` markers
+///
+/// The first line follows the comment opener and is only trimmed, all other lines keep their
+/// indentation relative to each other.
+fn dedent(body: &str) -> String {
+    let mut lines = body.lines();
+    let first = lines.next().unwrap_or_default().trim();
+    let rest = lines.collect::<Vec<_>>();
+
+    let indentation = rest
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    std::iter::once(first)
+        .chain(
+            rest.iter()
+                .map(|line| line.get(indentation..).unwrap_or_default().trim_end()),
+        )
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Removes the optional space separating a comment marker from the text following it
+fn strip_separator(text: &str) -> &str {
+    text.strip_prefix(' ').unwrap_or(text)
+}
+
+/// Removes all repetitions of `marker` from the start of `text`
+fn strip_markers<'t>(mut text: &'t str, marker: &str, case_insensitive: bool) -> &'t str {
+    if marker.is_empty() {
+        return text;
+    }
+
+    while let Some(start) = text.get(..marker.len()) {
+        let matches = if case_insensitive {
+            start.eq_ignore_ascii_case(marker)
+        } else {
+            start == marker
+        };
+        if !matches {
+            break;
+        }
+        text = &text[marker.len()..];
+    }
+
+    text
+}
+
+/// Splits a header into its lines, normalizing them unless the license is matched exactly
+fn header_lines<'t>(
+    text: &'t str,
+    license_config: &LicenseConfig,
+    language_config: &LanguageConfig,
+) -> Vec<Cow<'t, str>> {
+    match license_config.match_mode {
+        MatchMode::Exact => lines(text).map(Cow::Borrowed).collect(),
+        MatchMode::Normalized | MatchMode::Regex => {
+            normalize_header(text, license_config, language_config)
         }
     }
+}
+
+/// The lines of a header without their line endings
+///
+/// Unlike [`str::lines`] this also strips a `\r` before a line ending that is part of the line,
+/// like in `\r\r\n` of files converted twice, so that CRLF files compare equal to LF license
+/// texts.
+fn lines(text: &str) -> impl Iterator<Item = &str> {
+    text.lines().map(|line| line.trim_end_matches('\r'))
+}
+
+/// Whether the found lines start with the expected ones, see [`line_matches`]
+fn starts_with_lines(found: &[impl AsRef<str>], expected: &[impl AsRef<str>]) -> bool {
+    found.len() >= expected.len()
+        && found
+            .iter()
+            .zip(expected)
+            .all(|(found, expected)| line_matches(found.as_ref(), expected.as_ref()))
+}
 
-    let comments = comments
-        .lines()
-        .take(config.license.text.lines().count())
-        .collect::<Vec<&str>>()
+/// Whether a found header line is the expected line of the license text, whose `{{year}}`
+/// placeholders match the years of the found line
+fn line_matches(found: &str, expected: &str) -> bool {
+    found == expected || template::years_match(expected, found)
+}
+
+/// The expression a license text in the `regex` match mode is compiled to, matching at the start
+/// of the found header
+fn license_regex(text: &str, verify_lines: Option<usize>) -> String {
+    // The found lines are joined with `\n`, so the license text has to use it as well
+    let text = lines(text)
+        .take(verify_lines.unwrap_or(usize::MAX))
+        .collect::<Vec<_>>()
         .join("\n");
+    format!("^(?:{})", template::year_regex(&text))
+}
+
+/// The expression a footer in the `regex` match mode is compiled to, matching at the end of the
+/// trailing comments
+fn footer_regex(text: &str) -> String {
+    let text = lines(text).collect::<Vec<_>>().join("\n");
+    format!("(?:{})\\z", template::year_regex(&text))
+}
+
+/// The lines of the license text the header of a file has to start with, limited to the
+/// `verify_lines`
+fn expected_lines<'t>(
+    license_text: &'t str,
+    license_config: &LicenseConfig,
+    language_config: &LanguageConfig,
+) -> Vec<Cow<'t, str>> {
+    let mut expected = header_lines(license_text, license_config, language_config);
+    if let Some(count) = license_config.verify_lines {
+        expected.truncate(count);
+    }
+    expected
+}
+
+/// Splits a header into the lines that are relevant when comparing it
+///
+/// Blank lines are dropped, unless the language asks for them to be preserved.
+fn normalize_header<'t>(
+    text: &'t str,
+    license_config: &LicenseConfig,
+    language_config: &LanguageConfig,
+) -> Vec<Cow<'t, str>> {
+    lines(text.trim())
+        .filter(|line| language_config.preserve_blank_comment_lines || !line.trim().is_empty())
+        .map(|line| {
+            if license_config.ignore_indentation {
+                line.trim_start()
+            } else {
+                line
+            }
+        })
+        .map(|line| match license_config.expand_tabs {
+            Some(width) => expand_leading_tabs(line, width),
+            None => Cow::Borrowed(line),
+        })
+        .map(|line| {
+            if license_config.normalize_unicode && !is_nfc(&line) {
+                Cow::Owned(line.nfc().collect())
+            } else {
+                line
+            }
+        })
+        .collect()
+}
 
-    if comments.trim() == config.license.text.trim() {
-        Ok(true)
+fn expand_leading_tabs(line: &str, width: usize) -> Cow<'_, str> {
+    let content = line.trim_start_matches([' ', '\t']);
+    let indentation = &line[..line.len() - content.len()];
+
+    if indentation.contains('\t') {
+        Cow::Owned(format!(
+            "{}{content}",
+            indentation.replace('\t', &" ".repeat(width))
+        ))
     } else {
-        debug!("Expected: {}\nGot: {comments}", config.license.text);
-        Ok(false)
+        Cow::Borrowed(line)
     }
 }
 
-fn load_language<'a>(
-    langs: &HashMap<String, Language>,
+fn load_language<'a, 'l>(
+    langs: &'l HashMap<String, Language>,
     config: &'a Config,
     file: &Utf8Path,
-) -> Result<(&'a LanguageConfig, tree_sitter::Parser), miette::Error> {
-    let language = config.languages.iter().find(|(_name, globs)| {
-        globs
-            .file_endings
-            .iter()
-            .any(|glob| glob_match(glob, file.file_name().unwrap()))
-    });
-    let Some((name, language_config)) = language else {
+) -> Result<(&'a String, &'a LanguageConfig, PooledParser<'l>), miette::Error> {
+    let Some((name, language_config)) = detect_language(config, file) else {
         bail!("Could not determine language for {}", file);
     };
+    if language_config.comments.is_empty() {
+        warn!(
+            "The language {name} has no comment configuration, so no license header can be found in {file}"
+        );
+    }
     let Some(language) = langs.get(name) else {
+        let misnamed = langs.iter().find(|(_, language)| {
+            tree_sitter::Language::new(language.language_fn).name() == Some(name.as_str())
+        });
+        if let Some((file_name, _)) = misnamed {
+            bail!(
+                "Found language {name} but no tree-sitter grammar exists for it, the grammar {file_name} calls itself {name} and might have to be renamed"
+            );
+        }
         bail!(
             "Found language {} but no tree-sitter grammar exists for it",
             name
         );
     };
-    let grammar = tree_sitter::Language::new(language.language_fn);
-    let mut parser = tree_sitter::Parser::new();
-    parser.set_language(&grammar).into_diagnostic()?;
-    Ok((language_config, parser))
+    let idle = language
+        .parsers
+        .lock()
+        .expect("A thread panicked while using a parser")
+        .pop();
+    let parser = match idle {
+        Some(parser) => parser,
+        None => {
+            let grammar = tree_sitter::Language::new(language.language_fn);
+            let mut parser = tree_sitter::Parser::new();
+            parser.set_language(&grammar).into_diagnostic()?;
+            parser
+        }
+    };
+    Ok((
+        name,
+        language_config,
+        PooledParser {
+            language,
+            parser: Some(parser),
+        },
+    ))
+}
+
+/// A parser taken from the idle parsers of a language, which it is returned to when dropped
+///
+/// At most one parser per language and thread is constructed this way, no matter how the files
+/// of different languages are interleaved.
+struct PooledParser<'l> {
+    language: &'l Language,
+    parser: Option<tree_sitter::Parser>,
+}
+
+impl Deref for PooledParser<'_> {
+    type Target = tree_sitter::Parser;
+
+    fn deref(&self) -> &Self::Target {
+        self.parser
+            .as_ref()
+            .expect("The parser is only taken when dropped")
+    }
+}
+
+impl DerefMut for PooledParser<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.parser
+            .as_mut()
+            .expect("The parser is only taken when dropped")
+    }
+}
+
+impl Drop for PooledParser<'_> {
+    fn drop(&mut self) {
+        if let Some(mut parser) = self.parser.take() {
+            // A parse might have been stopped halfway, which the next one must not resume
+            parser.reset();
+            if let Ok(mut parsers) = self.language.parsers.lock() {
+                parsers.push(parser);
+            }
+        }
+    }
+}
+
+/// Collects all files below `dir`, skipping hidden and ignored files
+fn walk_directory(dir: &Utf8Path, files: &mut Vec<Utf8PathBuf>) -> Result<(), miette::Error> {
+    for entry in ignore::WalkBuilder::new(dir)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .build()
+    {
+        let entry = entry
+            .into_diagnostic()
+            .with_context(|| miette!("Could not read the directory {dir}"))?;
+        if !entry
+            .file_type()
+            .is_some_and(|file_type| file_type.is_file())
+        {
+            continue;
+        }
+
+        match Utf8PathBuf::from_path_buf(entry.into_path()) {
+            Ok(path) => files.push(
+                path.strip_prefix(".")
+                    .map(Utf8Path::to_path_buf)
+                    .unwrap_or(path),
+            ),
+            Err(path) => warn!(
+                "Skipping {}, as its path is not valid UTF-8",
+                path.display()
+            ),
+        }
+    }
+
+    Ok(())
+}
+
+/// Finds the language of the given file, which is the `--assume-language` if one was given
+///
+/// Notebooks are of the `notebook_language` of their code cells.
+fn detect_language<'a>(
+    config: &'a Config,
+    file: &Utf8Path,
+) -> Option<(&'a String, &'a LanguageConfig)> {
+    let (name, language_config) = configured_language(config, file)?;
+    match &language_config.notebook_language {
+        Some(language) => config.languages.get_key_value(language),
+        None => Some((name, language_config)),
+    }
+}
+
+/// Whether the language of the given file has neither a grammar nor an extractor, warning that
+/// the file is skipped for `--ignore-missing-grammar` if so
+fn lacks_grammar(langs: &HashMap<String, Language>, config: &Config, file: &Utf8Path) -> bool {
+    let Some((name, language_config)) = detect_language(config, file) else {
+        return false;
+    };
+    if language_config.extractor.is_some() || langs.contains_key(name) {
+        return false;
+    }
+    warn!("Skipping {file}, as no grammar exists for its language {name}");
+    true
+}
+
+/// Whether the given file is a notebook, whose header is in its first code cell
+fn is_notebook(config: &Config, file: &Utf8Path) -> bool {
+    configured_language(config, file)
+        .is_some_and(|(_, language_config)| language_config.notebook_language.is_some())
+}
+
+/// Finds the configured language of the given file, which is the `--assume-language` if one was
+/// given
+fn configured_language<'a>(
+    config: &'a Config,
+    file: &Utf8Path,
+) -> Option<(&'a String, &'a LanguageConfig)> {
+    match &config.assumed_language {
+        Some(name) => config.languages.get_key_value(name),
+        None => language_by_file_endings(config, file),
+    }
+}
+
+/// Finds the language whose file endings match the given file
+///
+/// This selects the files of directories, even with `--assume-language`.
+fn language_by_file_endings<'a>(
+    config: &'a Config,
+    file: &Utf8Path,
+) -> Option<(&'a String, &'a LanguageConfig)> {
+    config.languages.iter().find(|(_name, globs)| {
+        globs
+            .file_endings
+            .iter()
+            .any(|glob| glob_matches_file(glob, file))
+    })
+}
+
+/// Matches a glob against a file
+///
+/// Globs containing a `/` are matched against the whole path, others only against the file name.
+/// Globs always have to match the whole name or path, so `*.rs` matches `main.rs` but neither
+/// `main.rs.bak` nor `main.rsx`. The dialect is the one of `glob_match`:
+///
+/// - `?` matches any single character, `*` any number of characters except `/`, including none,
+///   so `*.rs` also matches a file named `.rs`
+/// - `**` matches any number of path components
+/// - `[ab]` and `[a-z]` match one of the given characters, `[!ab]` any other
+/// - `{a,b}` matches any of the comma separated alternatives
+fn glob_matches_file(glob: &str, file: &Utf8Path) -> bool {
+    if glob.contains('/') {
+        let path = file
+            .components()
+            .filter(|component| *component != Utf8Component::CurDir)
+            .collect::<Utf8PathBuf>();
+        glob_match(glob, path.as_str())
+    } else {
+        file.file_name().is_some_and(|name| glob_match(glob, name))
+    }
+}
+
+/// The configuration file at `path`, which might be a directory containing it
+///
+/// If a directory contains none of the [`CONFIG_FILE_NAMES`], the first one is returned, so that
+/// errors mention where the configuration was expected.
+fn config_file(path: &Utf8Path) -> Utf8PathBuf {
+    if !path.is_dir() {
+        return path.to_path_buf();
+    }
+
+    CONFIG_FILE_NAMES
+        .iter()
+        .map(|name| path.join(name))
+        .find(|file| file.is_file())
+        .unwrap_or_else(|| path.join(CONFIG_FILE_NAMES[0]))
+}
+
+/// Loads the configuration from the given files, merging them in order and the profile over
+/// them, see `--config-path` and `--profile`
+fn load_configuration(
+    config_paths: &[Utf8PathBuf],
+    profile: Option<&str>,
+) -> Result<Config, miette::Error> {
+    let mut merged = toml::Table::new();
+    let mut sources = Vec::new();
+    let mut content = String::new();
+    for config_path in config_paths {
+        content = std::fs::read_to_string(config_path)
+            .into_diagnostic()
+            .with_context(|| miette!("Could not read the configuration {config_path}"))?;
+        // Some editors save files with a byte order mark, which toml does not accept
+        if let Some(stripped) = content.strip_prefix('\u{feff}') {
+            content = stripped.to_string();
+        }
+
+        let mut table = content.parse::<toml::Table>().map_err(|error| {
+            configuration_error(config_path, &content, error.message(), error.span())
+        })?;
+        resolve_comment_queries(&mut table, config_path);
+        if let Some(toml::Value::Table(profiles)) = table.get_mut("profiles") {
+            for (_, profile) in profiles.iter_mut() {
+                if let toml::Value::Table(profile) = profile {
+                    resolve_comment_queries(profile, config_path);
+                }
+            }
+        }
+        merge_configuration(&mut merged, table);
+        sources.push((config_path.clone(), content.clone()));
+    }
+
+    let profiles = merged.remove("profiles");
+    if let Some(name) = profile {
+        let Some(toml::Value::Table(profile)) = profiles
+            .as_ref()
+            .and_then(|profiles| profiles.get(name))
+            .cloned()
+        else {
+            bail!("The profile {name} is not configured, add a [profiles.{name}] table");
+        };
+        merge_configuration(&mut merged, profile);
+    }
+
+    let mut config: Config =
+        toml::Value::Table(merged)
+            .try_into()
+            .map_err(|error: toml::de::Error| {
+                // A single file is deserialized again, so that the error points at its location
+                if let [config_path] = config_paths
+                    && let Err(error) = toml::from_str::<Config>(&content)
+                {
+                    return configuration_error(
+                        config_path,
+                        &content,
+                        error.message(),
+                        error.span(),
+                    );
+                }
+                miette!("{}", error.message().trim_end())
+            })?;
+    config.sources = sources;
+    Ok(config)
+}
+
+/// Merges a configuration over another one
+///
+/// Tables like `[license]` are merged key by key, so that the entries of `[languages]` and
+/// `[licenses]` are replaced entirely.
+fn merge_configuration(merged: &mut toml::Table, table: toml::Table) {
+    for (key, value) in table {
+        match (merged.get_mut(&key), value) {
+            (Some(toml::Value::Table(merged)), toml::Value::Table(table)) => merged.extend(table),
+            (_, value) => {
+                merged.insert(key, value);
+            }
+        }
+    }
+}
+
+/// The location of the value at the given keys in a configuration file
+fn value_span(content: &str, keys: &[&str]) -> Option<std::ops::Range<usize>> {
+    let table = toml::de::DeTable::parse(content).ok()?;
+    let (last, tables) = keys.split_last()?;
+    let mut table = table.get_ref();
+    for key in tables {
+        let toml::de::DeValue::Table(inner) = table.get(*key)?.get_ref() else {
+            return None;
+        };
+        table = inner;
+    }
+    table.get(*last).map(|value| value.span())
+}
+
+fn configuration_error(
+    config_path: &Utf8Path,
+    content: &str,
+    message: &str,
+    span: Option<std::ops::Range<usize>>,
+) -> miette::Error {
+    let labels = span
+        .map(|span| LabeledSpan::at(span, "here"))
+        .into_iter()
+        .collect::<Vec<_>>();
+    miette!(labels = labels, "{}", message.trim_end())
+        .with_source_code(NamedSource::new(config_path, content.to_string()))
+}
+
+/// Resolves the `comment_query` files of languages relative to the configuration defining them
+fn resolve_comment_queries(table: &mut toml::Table, config_path: &Utf8Path) {
+    let directory = config_path.parent().unwrap_or(Utf8Path::new(""));
+    let Some(toml::Value::Table(languages)) = table.get_mut("languages") else {
+        return;
+    };
+    for (_, language) in languages.iter_mut() {
+        if let toml::Value::Table(language) = language
+            && let Some(toml::Value::String(query)) = language.get_mut("comment_query")
+            && Utf8Path::new(query).is_relative()
+        {
+            *query = directory.join(&*query).into_string();
+        }
+    }
+}
+
+/// Finds the directories to load grammars from, in order of precedence
+///
+/// These are the directories given on the command line or in `TREE_SITTER_GRAMMARS`, or
+/// `$XDG_DATA_HOME/lizenz/grammars` if it exists.
+fn find_grammar_dirs(given: &[Utf8PathBuf]) -> Result<Vec<Utf8PathBuf>, miette::Error> {
+    if !given.is_empty() {
+        return Ok(given.to_vec());
+    }
+
+    let data_home = std::env::var("XDG_DATA_HOME")
+        .ok()
+        .filter(|dir| !dir.is_empty())
+        .map(Utf8PathBuf::from)
+        .or_else(|| {
+            std::env::var("HOME")
+                .ok()
+                .map(|home| Utf8PathBuf::from(home).join(".local/share"))
+        });
+
+    let default_dir = data_home.map(|dir| dir.join("lizenz/grammars"));
+    if let Some(dir) = &default_dir
+        && dir.is_dir()
+    {
+        debug!("Using grammars from {dir}");
+        return Ok(vec![dir.clone()]);
+    }
+
+    bail!(
+        "Could not find a grammar directory, searched:\n  - the --tree-sitter-grammars option\n  - the TREE_SITTER_GRAMMARS environment variable\n  - {}",
+        default_dir
+            .as_ref()
+            .map(|dir| dir.as_str())
+            .unwrap_or("$XDG_DATA_HOME/lizenz/grammars (neither XDG_DATA_HOME nor HOME is set)")
+    );
 }
 
-fn load_configuration(config_path: &Utf8Path) -> Result<Config, miette::Error> {
-    toml::from_str(&std::fs::read_to_string(config_path).into_diagnostic()?).into_diagnostic()
+/// Loads the built-in grammars and those in the grammar directories
+///
+/// Built-in grammars take precedence over those in directories, which take precedence over those
+/// in later directories. The grammar directories are optional if there are built-in grammars.
+fn load_all_languages(
+    grammar_dirs: &[Utf8PathBuf],
+) -> Result<HashMap<String, Language>, miette::Error> {
+    let mut langs = builtin::languages();
+
+    match find_grammar_dirs(grammar_dirs) {
+        Ok(grammar_dirs) => {
+            for grammar_dir in grammar_dirs {
+                for (name, language) in load_languages(&grammar_dir)? {
+                    match langs.entry(name) {
+                        Entry::Occupied(entry) => debug!(
+                            "Not using the grammar {} from {grammar_dir}, as it is shadowed by a built-in grammar or one from an earlier directory",
+                            entry.key()
+                        ),
+                        Entry::Vacant(entry) => {
+                            entry.insert(language);
+                        }
+                    }
+                }
+            }
+        }
+        Err(error) if langs.is_empty() => return Err(error),
+        Err(_) => debug!("No grammar directory found, only using the built-in grammars"),
+    }
+
+    Ok(langs)
 }
 
-fn load_languages(args: &Args) -> Result<HashMap<String, Language>, miette::Error> {
+fn load_languages(grammar_dir: &Utf8Path) -> Result<HashMap<String, Language>, miette::Error> {
     let mut langs = HashMap::new();
-    for file in args
-        .tree_sitter_grammars
+    let entries = grammar_dir
         .read_dir_utf8()
-        .into_diagnostic()?
-    {
+        .into_diagnostic()
+        .wrap_err_with(|| format!("Could not read the grammar directory {grammar_dir}"))?;
+    for file in entries {
         let entry = match file {
             Ok(entry) => entry,
             Err(error) => {
@@ -396,18 +3460,28 @@ fn load_languages(args: &Args) -> Result<HashMap<String, Language>, miette::Erro
             }
         }
 
-        let Some(lang_name) = entry.path().file_stem() else {
+        let Some(file_stem) = entry.path().file_stem() else {
             warn!("Found {}, but could not determine its name", entry.path());
             continue;
         };
-        let language = load_ts_lib(entry.path(), lang_name)
+        let lang_name = grammar_name(file_stem);
+        let language = load_ts_lib(entry.path(), &lang_name)
             .with_context(|| format!("While trying to load {}", entry.path()))?;
 
-        langs.insert(lang_name.to_string(), language);
+        langs.insert(lang_name, language);
     }
     Ok(langs)
 }
 
+/// The name of the grammar in a file, like tree-sitter replacing characters that are not valid in
+/// C symbols with underscores, so that `c-sharp.so` is `c_sharp`
+fn grammar_name(file_stem: &str) -> String {
+    file_stem
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
 fn load_ts_lib(entry: &camino::Utf8Path, lang_name: &str) -> Result<Language, miette::Error> {
     let symbol = format!("tree_sitter_{lang_name}");
     let library;
@@ -419,9 +3493,91 @@ fn load_ts_lib(entry: &camino::Utf8Path, lang_name: &str) -> Result<Language, mi
             library.get(symbol.as_bytes()).into_diagnostic()?;
         language_fn = LanguageFn::from_raw(*lang_constructor);
     }
+
+    let grammar = tree_sitter::Language::new(language_fn);
+    let abi_version = grammar.abi_version();
+    debug!("Loaded grammar {lang_name} with ABI version {abi_version}");
+
+    // Languages are only ever looked up by the name derived from the file, but a different
+    // internal name hints at a misnamed file
+    if let Some(internal_name) = grammar.name()
+        && grammar_name(internal_name) != lang_name
+    {
+        warn!(
+            "The grammar in {entry} calls itself {internal_name}, but is used for the language {lang_name} after its file name, rename it to {}.{} if it belongs to {internal_name}",
+            grammar_name(internal_name),
+            entry.extension().unwrap_or("so"),
+        );
+    }
+
+    let supported_versions =
+        tree_sitter::MIN_COMPATIBLE_LANGUAGE_VERSION..=tree_sitter::LANGUAGE_VERSION;
+    if !supported_versions.contains(&abi_version) {
+        bail!(
+            "The grammar {lang_name} uses ABI version {abi_version}, but only versions {} to {} are supported",
+            supported_versions.start(),
+            supported_versions.end(),
+        );
+    }
+
     Ok(Language {
+        parsers: Mutex::default(),
         _name: lang_name.to_string(),
-        _library: library,
+        _library: Some(library),
         language_fn,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(glob: &str, file: &str) -> bool {
+        glob_matches_file(glob, Utf8Path::new(file))
+    }
+
+    #[test]
+    fn extension_globs_match_only_the_extension() {
+        assert!(matches("*.rs", "main.rs"));
+        assert!(matches("*.rs", ".rs"));
+        assert!(!matches("*.rs", "main.rs.bak"));
+        assert!(!matches("*.rs", "main.rsx"));
+        assert!(!matches("*.rs", "main.r"));
+    }
+
+    #[test]
+    fn globs_without_slash_match_the_file_name() {
+        assert!(matches("*.rs", "src/nested/main.rs"));
+        assert!(matches("main.rs", "src/main.rs"));
+        assert!(!matches("src", "src/main.rs"));
+    }
+
+    #[test]
+    fn globs_with_slash_match_the_whole_path() {
+        assert!(matches("src/*.rs", "src/main.rs"));
+        assert!(!matches("src/*.rs", "src/nested/main.rs"));
+        assert!(!matches("src/*.rs", "other/src/main.rs"));
+    }
+
+    #[test]
+    fn double_stars_match_any_number_of_directories() {
+        assert!(matches("src/**/*.rs", "src/main.rs"));
+        assert!(matches("src/**/*.rs", "src/a/b/main.rs"));
+        assert!(matches("**/generated/*.rs", "src/generated/bindings.rs"));
+        assert!(!matches("src/**/*.rs", "tests/main.rs"));
+    }
+
+    #[test]
+    fn leading_current_directories_are_ignored() {
+        assert!(matches("src/*.rs", "./src/main.rs"));
+        assert!(matches("src/**", "./src/a/main.rs"));
+        assert!(matches("*.rs", "./main.rs"));
+    }
+
+    #[test]
+    fn grammar_names_are_valid_c_identifiers() {
+        assert_eq!(grammar_name("rust"), "rust");
+        assert_eq!(grammar_name("c-sharp"), "c_sharp");
+        assert_eq!(grammar_name("tree sitter.toml"), "tree_sitter_toml");
+    }
+}
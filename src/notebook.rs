@@ -0,0 +1,147 @@
+// © Marcel Müller 2025, licensed under the EUPL
+
+//! Headers of Jupyter notebooks, which are JSON files whose code lives in cells
+//!
+//! The header of a notebook is the header of its first code cell, which is verified and fixed
+//! like a file of the notebook's `notebook_language`. Notebooks without code cells, e.g. those
+//! consisting only of markdown, need no header.
+
+use miette::Context;
+use miette::IntoDiagnostic;
+use miette::bail;
+use miette::miette;
+use serde_json::Value;
+
+/// The source of the first code cell of the notebook, `None` if it has no code cells
+pub fn first_code_cell(content: &str) -> Result<Option<String>, miette::Error> {
+    let notebook = parse(content)?;
+    let Some(cell) = code_cells(&notebook).next() else {
+        return Ok(None);
+    };
+
+    match cell.get("source") {
+        Some(Value::String(source)) => Ok(Some(source.clone())),
+        Some(Value::Array(lines)) => lines
+            .iter()
+            .map(|line| {
+                line.as_str()
+                    .ok_or_else(|| miette!("The source of a code cell contains a non-string line"))
+            })
+            .collect::<Result<String, _>>()
+            .map(Some),
+        None => Ok(Some(String::new())),
+        Some(_) => bail!("The source of a code cell is neither a string nor a list of lines"),
+    }
+}
+
+/// The notebook with the source of its first code cell replaced by `source`
+///
+/// The notebook is written like Jupyter does, with sorted keys, an indentation of one space and
+/// the source as a list of lines.
+pub fn replace_first_code_cell(content: &str, source: &str) -> Result<String, miette::Error> {
+    let mut notebook = parse(content)?;
+    let Some(cells) = notebook.get_mut("cells").and_then(Value::as_array_mut) else {
+        bail!("The notebook has no cells");
+    };
+    let Some(cell) = cells.iter_mut().find(|cell| is_code_cell(cell)) else {
+        bail!("Cannot insert a license header into a notebook without code cells");
+    };
+    cell["source"] = Value::Array(
+        source
+            .split_inclusive('\n')
+            .map(|line| Value::String(line.to_string()))
+            .collect(),
+    );
+
+    let mut output = vec![];
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(b" ");
+    let mut serializer = serde_json::Serializer::with_formatter(&mut output, formatter);
+    serde::Serialize::serialize(&notebook, &mut serializer).into_diagnostic()?;
+    let mut output = String::from_utf8(output).into_diagnostic()?;
+    output.push('\n');
+    Ok(output)
+}
+
+fn parse(content: &str) -> Result<Value, miette::Error> {
+    serde_json::from_str(content)
+        .into_diagnostic()
+        .with_context(|| miette!("The notebook is not valid JSON"))
+}
+
+fn code_cells(notebook: &Value) -> impl Iterator<Item = &Value> {
+    notebook
+        .get("cells")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter(|cell| is_code_cell(cell))
+}
+
+fn is_code_cell(cell: &Value) -> bool {
+    cell.get("cell_type").and_then(Value::as_str) == Some("code")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOTEBOOK: &str = r##"{"cells": [
+        {"cell_type": "markdown", "metadata": {}, "source": ["# Title\n"]},
+        {"cell_type": "code", "metadata": {}, "outputs": [], "source": ["# Copyright Foo\n", "echo hi\n"]},
+        {"cell_type": "code", "metadata": {}, "outputs": [], "source": "echo second"}
+    ], "metadata": {}, "nbformat": 4, "nbformat_minor": 5}"##;
+
+    #[test]
+    fn the_first_code_cell_is_joined_from_its_lines() {
+        assert_eq!(
+            first_code_cell(NOTEBOOK).unwrap().as_deref(),
+            Some("# Copyright Foo\necho hi\n")
+        );
+    }
+
+    #[test]
+    fn sources_may_be_a_single_string() {
+        let notebook = r#"{"cells": [{"cell_type": "code", "source": "echo hi\n"}]}"#;
+        assert_eq!(
+            first_code_cell(notebook).unwrap().as_deref(),
+            Some("echo hi\n")
+        );
+    }
+
+    #[test]
+    fn notebooks_without_code_cells_have_no_first_code_cell() {
+        let notebook = r##"{"cells": [{"cell_type": "markdown", "source": ["# Title"]}]}"##;
+        assert_eq!(first_code_cell(notebook).unwrap(), None);
+        assert_eq!(first_code_cell(r#"{"metadata": {}}"#).unwrap(), None);
+    }
+
+    #[test]
+    fn invalid_notebooks_are_errors() {
+        assert!(first_code_cell("not json").is_err());
+        assert!(first_code_cell(r#"{"cells": [{"cell_type": "code", "source": 1}]}"#).is_err());
+        assert!(first_code_cell(r#"{"cells": [{"cell_type": "code", "source": [1]}]}"#).is_err());
+    }
+
+    #[test]
+    fn replacing_the_first_code_cell_keeps_the_other_cells() {
+        let replaced = replace_first_code_cell(NOTEBOOK, "# Copyright Bar\necho hi\n").unwrap();
+        let notebook = parse(&replaced).unwrap();
+        let cells = notebook["cells"].as_array().unwrap();
+
+        assert_eq!(cells.len(), 3);
+        assert_eq!(cells[0]["source"], serde_json::json!(["# Title\n"]));
+        assert_eq!(
+            cells[1]["source"],
+            serde_json::json!(["# Copyright Bar\n", "echo hi\n"])
+        );
+        assert_eq!(cells[2]["source"], "echo second");
+        assert!(replaced.starts_with("{\n \"cells\": [\n"), "{replaced}");
+        assert!(replaced.ends_with("}\n"), "{replaced}");
+    }
+
+    #[test]
+    fn code_cells_cannot_be_replaced_in_notebooks_without_them() {
+        let notebook = r#"{"cells": [{"cell_type": "markdown", "source": []}]}"#;
+        assert!(replace_first_code_cell(notebook, "echo hi\n").is_err());
+    }
+}
@@ -0,0 +1,58 @@
+// © Marcel Müller 2025, licensed under the EUPL
+
+//! Checking files on multiple threads
+
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+/// Runs `f` on all items using up to `jobs` threads, returning the results in the order of the
+/// items
+///
+/// Once `f` failed for an item no further items are started, and the error of the first failing
+/// item is returned.
+pub fn map<T, R, F>(items: &[T], jobs: usize, f: F) -> Result<Vec<R>, miette::Error>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> Result<R, miette::Error> + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+
+    let mut results = std::iter::repeat_with(|| None)
+        .take(items.len())
+        .collect::<Vec<Option<Result<R, miette::Error>>>>();
+
+    std::thread::scope(|scope| {
+        let workers = (0..jobs.clamp(1, items.len().max(1)))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut finished = vec![];
+                    while !failed.load(Ordering::Relaxed) {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(item) = items.get(index) else {
+                            break;
+                        };
+
+                        let result = f(item);
+                        if result.is_err() {
+                            failed.store(true, Ordering::Relaxed);
+                        }
+                        finished.push((index, result));
+                    }
+                    finished
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for worker in workers {
+            for (index, result) in worker.join().expect("A worker thread panicked") {
+                results[index] = Some(result);
+            }
+        }
+    });
+
+    // Items after a failure might not have been started, but the failure is returned first
+    results.into_iter().flatten().collect()
+}
@@ -0,0 +1,163 @@
+// © Marcel Müller 2025, licensed under the EUPL
+
+//! Rendering of fixes as patches that can be applied with `git apply`
+
+use camino::Utf8Path;
+use camino::Utf8PathBuf;
+
+use crate::report;
+
+/// The number of unchanged lines shown around a change
+const CONTEXT_LINES: usize = 3;
+
+/// Renders the change from `old` to `new` of the file at `path` as a git style unified diff
+///
+/// All of lizenz' fixes change a single region of a file, so the diff consists of one hunk
+/// spanning from the first to the last changed line.
+pub fn unified_diff(path: &str, old: &str, new: &str) -> String {
+    let old_lines = old.split_inclusive('\n').collect::<Vec<_>>();
+    let new_lines = new.split_inclusive('\n').collect::<Vec<_>>();
+
+    let prefix = old_lines
+        .iter()
+        .zip(&new_lines)
+        .take_while(|(old, new)| old == new)
+        .count();
+    let suffix = old_lines[prefix..]
+        .iter()
+        .rev()
+        .zip(new_lines[prefix..].iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+
+    let mut diff = format!("diff --git a/{path} b/{path}\n--- a/{path}\n+++ b/{path}\n");
+    if prefix == old_lines.len() && prefix == new_lines.len() {
+        return diff;
+    }
+
+    let start = prefix.saturating_sub(CONTEXT_LINES);
+    let old_end = (old_lines.len() - suffix + CONTEXT_LINES).min(old_lines.len());
+    let new_end = new_lines.len() - (old_lines.len() - old_end);
+
+    let old_count = old_end - start;
+    let new_count = new_end - start;
+    diff.push_str(&format!(
+        "@@ -{},{old_count} +{},{new_count} @@\n",
+        hunk_start(start, old_count),
+        hunk_start(start, new_count),
+    ));
+
+    for line in &old_lines[start..prefix] {
+        push_line(&mut diff, ' ', line);
+    }
+    for line in &old_lines[prefix..old_lines.len() - suffix] {
+        push_line(&mut diff, '-', line);
+    }
+    for line in &new_lines[prefix..new_lines.len() - suffix] {
+        push_line(&mut diff, '+', line);
+    }
+    for line in &old_lines[old_lines.len() - suffix..old_end] {
+        push_line(&mut diff, ' ', line);
+    }
+
+    diff
+}
+
+/// Empty ranges are denoted by the line preceding them, others by their first line
+fn hunk_start(start: usize, count: usize) -> usize {
+    if count == 0 { start } else { start + 1 }
+}
+
+fn push_line(diff: &mut String, marker: char, line: &str) {
+    diff.push(marker);
+    diff.push_str(line);
+    if !line.ends_with('\n') {
+        diff.push_str("\n\\ No newline at end of file\n");
+    }
+}
+
+/// The path of `file` relative to the root of the git repository containing it
+///
+/// Falls back to the path relative to the current directory, which is the `--root` if one was
+/// given, if it is not inside a repository.
+pub fn repo_relative_path(file: &Utf8Path) -> Utf8PathBuf {
+    let repo_relative = file.canonicalize_utf8().ok().and_then(|canonical| {
+        canonical
+            .ancestors()
+            .skip(1)
+            .find(|dir| dir.join(".git").exists())
+            .and_then(|root| canonical.strip_prefix(root).ok())
+            .map(Utf8Path::to_path_buf)
+    });
+
+    repo_relative.unwrap_or_else(|| match report::absolute_path(Utf8Path::new(".")) {
+        Ok(current_dir) => report::relative_path(file, &current_dir),
+        Err(_) => file.to_path_buf(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insertions_at_the_top_keep_the_following_lines_as_context() {
+        assert_eq!(
+            unified_diff(
+                "src/main.rs",
+                "a\nb\nc\nd\ne\n",
+                "// Header\na\nb\nc\nd\ne\n"
+            ),
+            "diff --git a/src/main.rs b/src/main.rs\n--- a/src/main.rs\n+++ b/src/main.rs\n\
+             @@ -1,3 +1,4 @@\n+// Header\n a\n b\n c\n"
+        );
+    }
+
+    #[test]
+    fn changed_lines_are_removed_and_added() {
+        assert_eq!(
+            unified_diff("a.rs", "// Old\nfn a() {}\n", "// New\nfn a() {}\n"),
+            "diff --git a/a.rs b/a.rs\n--- a/a.rs\n+++ b/a.rs\n\
+             @@ -1,2 +1,2 @@\n-// Old\n+// New\n fn a() {}\n"
+        );
+    }
+
+    #[test]
+    fn missing_newlines_at_the_end_are_marked() {
+        assert_eq!(
+            unified_diff("a.rs", "fn a() {}", "// New\nfn a() {}"),
+            "diff --git a/a.rs b/a.rs\n--- a/a.rs\n+++ b/a.rs\n\
+             @@ -1,1 +1,2 @@\n+// New\n fn a() {}\n\\ No newline at end of file\n"
+        );
+    }
+
+    #[test]
+    fn empty_files_start_their_hunks_at_zero() {
+        assert_eq!(
+            unified_diff("a.rs", "", "// New\n"),
+            "diff --git a/a.rs b/a.rs\n--- a/a.rs\n+++ b/a.rs\n@@ -0,0 +1,1 @@\n+// New\n"
+        );
+    }
+
+    #[test]
+    fn unchanged_files_have_no_hunks() {
+        assert_eq!(
+            unified_diff("a.rs", "fn a() {}\n", "fn a() {}\n"),
+            "diff --git a/a.rs b/a.rs\n--- a/a.rs\n+++ b/a.rs\n"
+        );
+    }
+
+    #[test]
+    fn paths_are_relative_to_the_repository() {
+        let repository = tempfile::tempdir().unwrap();
+        let root = Utf8Path::from_path(repository.path()).unwrap();
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/main.rs"), "").unwrap();
+
+        assert_eq!(
+            repo_relative_path(&root.join("src/../src/main.rs")),
+            Utf8Path::new("src").join("main.rs")
+        );
+    }
+}
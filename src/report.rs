@@ -0,0 +1,299 @@
+// © Marcel Müller 2025, licensed under the EUPL
+
+//! Machine readable reports of the files failing verification
+
+use camino::Utf8Component;
+use camino::Utf8Path;
+use camino::Utf8PathBuf;
+use clap::ValueEnum;
+use miette::Context;
+use miette::IntoDiagnostic;
+use miette::miette;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::Outcome;
+
+/// The identifier of the single SARIF rule all findings belong to
+const SARIF_RULE_ID: &str = "license-header";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ReportFormat {
+    /// Human readable text, printed as soon as a file is checked
+    Human,
+    /// A JSON object listing all findings
+    Json,
+    /// A SARIF 2.1.0 log, as understood by code scanning tools
+    Sarif,
+    /// GitHub Actions workflow commands, which annotate the failing files without uploading a
+    /// report
+    Github,
+}
+
+/// A file failing verification
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub path: Utf8PathBuf,
+    pub outcome: Outcome,
+    /// Why the file fails verification, like in the human readable output
+    pub message: String,
+    /// The line the header is expected at
+    pub line: usize,
+}
+
+/// The absolute form of `path` without `.` and `..` components, relative to the current directory
+///
+/// Symbolic links are not resolved, so that reported paths look like the given ones.
+pub fn absolute_path(path: &Utf8Path) -> Result<Utf8PathBuf, miette::Error> {
+    let current_dir = std::env::current_dir()
+        .into_diagnostic()
+        .with_context(|| miette!("Could not determine the current directory"))?;
+    let current_dir = Utf8PathBuf::try_from(current_dir)
+        .into_diagnostic()
+        .with_context(|| miette!("The current directory is not valid UTF-8"))?;
+
+    let mut absolute = Utf8PathBuf::new();
+    for component in current_dir.join(path).components() {
+        match component {
+            Utf8Component::CurDir => {}
+            Utf8Component::ParentDir => {
+                absolute.pop();
+            }
+            component => absolute.push(component),
+        }
+    }
+    Ok(absolute)
+}
+
+/// The path of `file` as reported, relative to the absolute directory `base`
+///
+/// Files outside of `base` are reported with their absolute path.
+pub fn relative_path(file: &Utf8Path, base: &Utf8Path) -> Utf8PathBuf {
+    let Ok(absolute) = absolute_path(file) else {
+        return file.to_path_buf();
+    };
+    match absolute.strip_prefix(base) {
+        Ok(relative) if relative.as_str().is_empty() => Utf8PathBuf::from("."),
+        Ok(relative) => relative.to_path_buf(),
+        Err(_) => absolute,
+    }
+}
+
+/// Renders the findings in the given machine readable format
+///
+/// Returns `None` for the human readable format, as its findings are printed while checking.
+pub fn render(findings: &[Finding], format: ReportFormat) -> Result<Option<String>, miette::Error> {
+    let report = match format {
+        ReportFormat::Human => return Ok(None),
+        ReportFormat::Json => json!({ "findings": findings }),
+        ReportFormat::Sarif => sarif(findings),
+        ReportFormat::Github => return Ok(Some(github(findings))),
+    };
+
+    serde_json::to_string_pretty(&report)
+        .into_diagnostic()
+        .map(Some)
+}
+
+/// One `::error` workflow command per finding, like
+/// `::error file=src/main.rs,line=1::missing or incorrect license header`
+fn github(findings: &[Finding]) -> String {
+    findings
+        .iter()
+        .map(|finding| {
+            format!(
+                "::error file={},line={}::{}",
+                github_property(&finding.path.as_str().replace('\\', "/")),
+                finding.line,
+                github_data(&finding.message)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Escapes the message of a workflow command, which ends at a line break
+fn github_data(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escapes a property of a workflow command, which also ends at a `,` or `:`
+fn github_property(text: &str) -> String {
+    github_data(text).replace(':', "%3A").replace(',', "%2C")
+}
+
+fn sarif(findings: &[Finding]) -> serde_json::Value {
+    let results = findings
+        .iter()
+        .map(|finding| {
+            json!({
+                "ruleId": SARIF_RULE_ID,
+                "level": "error",
+                "message": { "text": finding.message },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": finding.path.as_str().replace('\\', "/") },
+                        "region": { "startLine": finding.line },
+                    },
+                }],
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": [{
+                        "id": SARIF_RULE_ID,
+                        "shortDescription": { "text": "Files must start with the configured license header" },
+                    }],
+                },
+            },
+            "results": results,
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn findings() -> Vec<Finding> {
+        vec![Finding {
+            path: Utf8PathBuf::from("src/main.rs"),
+            outcome: Outcome::Missing,
+            message: String::from("missing or incorrect license header"),
+            line: 1,
+        }]
+    }
+
+    #[test]
+    fn human_reports_are_not_rendered() {
+        assert_eq!(render(&findings(), ReportFormat::Human).unwrap(), None);
+    }
+
+    #[test]
+    fn json_reports_list_the_findings() {
+        let report = render(&findings(), ReportFormat::Json).unwrap().unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&report).unwrap(),
+            json!({
+                "findings": [{
+                    "path": "src/main.rs",
+                    "outcome": "missing",
+                    "message": "missing or incorrect license header",
+                    "line": 1,
+                }],
+            })
+        );
+    }
+
+    #[test]
+    fn sarif_reports_locate_the_findings() {
+        let report = render(&findings(), ReportFormat::Sarif).unwrap().unwrap();
+        let report = serde_json::from_str::<serde_json::Value>(&report).unwrap();
+        assert_eq!(report["version"], "2.1.0");
+
+        let run = &report["runs"][0];
+        assert_eq!(run["tool"]["driver"]["rules"][0]["id"], SARIF_RULE_ID);
+        assert_eq!(
+            run["results"],
+            json!([{
+                "ruleId": SARIF_RULE_ID,
+                "level": "error",
+                "message": { "text": "missing or incorrect license header" },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": "src/main.rs" },
+                        "region": { "startLine": 1 },
+                    },
+                }],
+            }])
+        );
+    }
+
+    #[test]
+    fn sarif_uris_use_forward_slashes() {
+        let mut findings = findings();
+        findings[0].path = Utf8PathBuf::from("src\\main.rs");
+        let report = sarif(&findings);
+        assert_eq!(
+            report["runs"][0]["results"][0]["locations"][0]["physicalLocation"]["artifactLocation"]
+                ["uri"],
+            "src/main.rs"
+        );
+    }
+
+    #[test]
+    fn github_reports_are_error_commands() {
+        assert_eq!(
+            render(&findings(), ReportFormat::Github).unwrap().unwrap(),
+            "::error file=src/main.rs,line=1::missing or incorrect license header"
+        );
+    }
+
+    #[test]
+    fn github_reports_have_one_command_per_line() {
+        let mut findings = findings();
+        findings.push(Finding {
+            path: Utf8PathBuf::from("src\\lib.rs"),
+            outcome: Outcome::NearMatch,
+            message: String::from("license header nearly matches"),
+            line: 3,
+        });
+        assert_eq!(
+            github(&findings),
+            "::error file=src/main.rs,line=1::missing or incorrect license header\n::error file=src/lib.rs,line=3::license header nearly matches"
+        );
+    }
+
+    #[test]
+    fn github_commands_are_escaped() {
+        assert_eq!(
+            github_data("100% done\r\nnext: a, b"),
+            "100%25 done%0D%0Anext: a, b"
+        );
+        assert_eq!(github_property("a:b,c%\n"), "a%3Ab%2Cc%25%0A");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn absolute_paths_are_normalized() {
+        assert_eq!(
+            absolute_path(Utf8Path::new("/project/./src/../main.rs")).unwrap(),
+            "/project/main.rs"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn paths_are_relative_to_the_base() {
+        let base = Utf8Path::new("/project");
+        assert_eq!(
+            relative_path(Utf8Path::new("/project/src/main.rs"), base),
+            "src/main.rs"
+        );
+        assert_eq!(relative_path(Utf8Path::new("/project"), base), ".");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn paths_outside_of_the_base_are_absolute() {
+        let base = Utf8Path::new("/project");
+        assert_eq!(
+            relative_path(Utf8Path::new("/project/../other/main.rs"), base),
+            "/other/main.rs"
+        );
+        assert_eq!(
+            relative_path(Utf8Path::new("/projects/main.rs"), base),
+            "/projects/main.rs"
+        );
+    }
+}
@@ -0,0 +1,165 @@
+// © Marcel Müller 2025, licensed under the EUPL
+
+//! A long running mode answering requests on a Unix socket, so that grammars are only loaded once
+//!
+//! Every line a client sends is a JSON request, which is answered by a single line of JSON:
+//!
+//! ```text
+//! > {"command": "verify", "path": "src/main.rs"}
+//! < {"outcome": "valid"}
+//! > {"command": "fix", "path": "src/new.rs", "content": "fn main() {}\n"}
+//! < {"outcome": "missing", "content": "// License\nfn main() {}\n"}
+//! > {"command": "frobnicate"}
+//! < {"error": "unknown variant `frobnicate`, expected `verify` or `fix` at line 1 column 23"}
+//! ```
+//!
+//! With a `content` the file is not read, its `path` only determines its language. Fixes of such
+//! requests are returned instead of being written.
+
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::os::unix::net::UnixListener;
+use std::os::unix::net::UnixStream;
+
+use camino::Utf8Path;
+use camino::Utf8PathBuf;
+use miette::Context;
+use miette::IntoDiagnostic;
+use miette::miette;
+use serde::Deserialize;
+use serde::Serialize;
+use tracing::debug;
+use tracing::warn;
+
+use crate::Config;
+use crate::Language;
+use crate::Outcome;
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "command", rename_all = "lowercase")]
+enum Request {
+    Verify(Target),
+    Fix(Target),
+}
+
+#[derive(Debug, Deserialize)]
+struct Target {
+    path: Utf8PathBuf,
+    #[serde(default)]
+    content: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct Response {
+    /// The outcome of verifying the file, before fixing it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    outcome: Option<Outcome>,
+    /// The fixed content, if it was given in the request
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Answers requests on a Unix socket at `socket` until the process is stopped
+pub fn serve(
+    langs: &HashMap<String, Language>,
+    config: &Config,
+    socket: &Utf8Path,
+) -> Result<(), miette::Error> {
+    let listener = UnixListener::bind(socket)
+        .into_diagnostic()
+        .with_context(|| miette!("Could not listen on {socket}"))?;
+    debug!("Listening on {socket}");
+
+    std::thread::scope(|scope| {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    scope.spawn(move || {
+                        if let Err(error) = handle_client(langs, config, stream) {
+                            warn!(?error, "Lost connection to a client");
+                        }
+                    });
+                }
+                Err(error) => warn!(?error, "Could not accept a connection on {socket}"),
+            }
+        }
+    });
+
+    Ok(())
+}
+
+fn handle_client(
+    langs: &HashMap<String, Language>,
+    config: &Config,
+    stream: UnixStream,
+) -> std::io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => answer(langs, config, request).unwrap_or_else(|error| Response {
+                error: Some(error.to_string()),
+                ..Default::default()
+            }),
+            Err(error) => Response {
+                error: Some(error.to_string()),
+                ..Default::default()
+            },
+        };
+
+        let mut response = serde_json::to_string(&response)?;
+        response.push('\n');
+        writer.write_all(response.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+fn answer(
+    langs: &HashMap<String, Language>,
+    config: &Config,
+    request: Request,
+) -> Result<Response, miette::Error> {
+    let (target, fix) = match request {
+        Request::Verify(target) => (target, false),
+        Request::Fix(target) => (target, true),
+    };
+
+    let file = target.path.as_path();
+    let status = match &target.content {
+        Some(content) => crate::verify_content(langs, config, file, content)?,
+        None => crate::verify_file(langs, config, file)?,
+    };
+
+    let mut response = Response {
+        outcome: Some(status.outcome()),
+        ..Default::default()
+    };
+    if fix {
+        match &target.content {
+            Some(content) if status.is_valid() => response.content = Some(content.clone()),
+            Some(content) => {
+                response.content = Some(crate::fix_content(langs, config, file, &status, content)?);
+            }
+            None if status.is_valid() => {}
+            None => {
+                let (old_content, new_content) =
+                    crate::fixed_content(langs, config, file, &status)?;
+                if old_content != new_content {
+                    crate::write_file(file, &new_content)?;
+                    crate::format_fixed_file(config, file);
+                }
+            }
+        }
+    }
+
+    Ok(response)
+}
@@ -0,0 +1,112 @@
+// © Marcel Müller 2025, licensed under the EUPL
+
+//! Finding the files changed since a git commit
+
+use camino::Utf8PathBuf;
+use miette::Context;
+use miette::IntoDiagnostic;
+use miette::bail;
+use miette::miette;
+use tracing::debug;
+
+/// The files below the current directory that were added, modified, renamed or copied since
+/// `commit`, relative to the current directory
+///
+/// Deleted files are skipped and renamed files are returned with their new path.
+pub fn changed_files(commit: &str) -> Result<Vec<Utf8PathBuf>, miette::Error> {
+    let output = std::process::Command::new("git")
+        .args([
+            "diff",
+            "--name-status",
+            "-z",
+            "--find-renames",
+            "--relative",
+        ])
+        .arg(commit)
+        .arg("--")
+        .output()
+        .into_diagnostic()
+        .with_context(|| miette!("Could not run git to find the files changed since {commit}"))?;
+    if !output.status.success() {
+        bail!(
+            "Could not find the files changed since {commit}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let output = String::from_utf8(output.stdout)
+        .into_diagnostic()
+        .with_context(|| miette!("git reported changed files with invalid UTF-8 names"))?;
+    existing_files(&output, commit)
+}
+
+/// The paths of the files that still exist according to the NUL separated output of
+/// `git diff --name-status -z`
+fn existing_files(output: &str, commit: &str) -> Result<Vec<Utf8PathBuf>, miette::Error> {
+    let mut fields = output.split('\0').filter(|field| !field.is_empty());
+
+    let mut files = vec![];
+    while let Some(status) = fields.next() {
+        // Renames and copies are followed by the old and the new path, the others by one path
+        let path = match status.chars().next() {
+            Some('R' | 'C') => fields.nth(1),
+            _ => fields.next(),
+        };
+        let Some(path) = path else {
+            bail!("git reported the change {status} without a path");
+        };
+
+        if status.starts_with('D') {
+            debug!("Skipping {path}, as it was deleted since {commit}");
+            continue;
+        }
+        files.push(Utf8PathBuf::from(path));
+    }
+
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn added_and_modified_files_are_returned() {
+        assert_eq!(
+            existing_files("A\0new.rs\0M\0src/main.rs\0", "HEAD").unwrap(),
+            [
+                Utf8PathBuf::from("new.rs"),
+                Utf8PathBuf::from("src/main.rs")
+            ]
+        );
+    }
+
+    #[test]
+    fn renamed_and_copied_files_are_returned_with_their_new_path() {
+        assert_eq!(
+            existing_files("R100\0old.rs\0new.rs\0C075\0a.rs\0b.rs\0", "HEAD").unwrap(),
+            [Utf8PathBuf::from("new.rs"), Utf8PathBuf::from("b.rs")]
+        );
+    }
+
+    #[test]
+    fn deleted_files_are_skipped() {
+        assert_eq!(
+            existing_files("D\0gone.rs\0M\0main.rs\0", "HEAD").unwrap(),
+            [Utf8PathBuf::from("main.rs")]
+        );
+    }
+
+    #[test]
+    fn paths_may_contain_newlines() {
+        assert_eq!(
+            existing_files("A\0new\nline.rs\0", "HEAD").unwrap(),
+            [Utf8PathBuf::from("new\nline.rs")]
+        );
+    }
+
+    #[test]
+    fn changes_without_paths_are_errors() {
+        assert!(existing_files("R100\0old.rs\0", "HEAD").is_err());
+    }
+}
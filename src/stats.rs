@@ -0,0 +1,140 @@
+// © Marcel Müller 2025, licensed under the EUPL
+
+//! License coverage statistics over a directory tree
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+use camino::Utf8Path;
+use miette::IntoDiagnostic;
+use owo_colors::OwoColorize;
+use serde::Serialize;
+use tracing::debug;
+
+use crate::ColorChoice;
+use crate::Config;
+use crate::Language;
+use crate::Outcome;
+use crate::OutputFormat;
+
+#[derive(Debug, Default, Serialize)]
+pub struct Counts {
+    valid: usize,
+    missing: usize,
+    mismatched: usize,
+    /// Files below their language's minimum size, which need no header
+    too_small: usize,
+    /// Files whose parsing took longer than `--timeout-ms`
+    timed_out: usize,
+}
+
+impl Counts {
+    fn record(&mut self, outcome: Outcome) {
+        match outcome {
+            Outcome::Valid => self.valid += 1,
+            Outcome::Missing => self.missing += 1,
+            Outcome::Mismatch | Outcome::NearMatch => self.mismatched += 1,
+            Outcome::TooSmall => self.too_small += 1,
+            Outcome::TimedOut => self.timed_out += 1,
+        }
+    }
+
+    fn total(&self) -> usize {
+        self.valid + self.missing + self.mismatched + self.too_small + self.timed_out
+    }
+
+    /// The files that need no changes
+    fn compliant(&self) -> usize {
+        self.valid + self.too_small
+    }
+
+    /// The percentage of files with a valid header or too small to need one, `100` if there are no
+    /// files
+    fn coverage(&self) -> f64 {
+        if self.total() == 0 {
+            100.0
+        } else {
+            self.compliant() as f64 * 100.0 / self.total() as f64
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct Stats {
+    languages: BTreeMap<String, Counts>,
+    total: Counts,
+    coverage: f64,
+}
+
+/// Verifies all files below `directory` that belong to a configured language
+pub fn collect(
+    langs: &HashMap<String, Language>,
+    config: &Config,
+    directory: &Utf8Path,
+) -> Result<Stats, miette::Error> {
+    let mut files = vec![];
+    crate::walk_directory(directory, &mut files)?;
+
+    let mut stats = Stats::default();
+    for file in files {
+        let Some((name, _)) = crate::language_by_file_endings(config, &file) else {
+            debug!("Skipping {file}, as it does not belong to a configured language");
+            continue;
+        };
+
+        let outcome = crate::verify_file(langs, config, &file)?.outcome();
+        stats
+            .languages
+            .entry(name.clone())
+            .or_default()
+            .record(outcome);
+        stats.total.record(outcome);
+    }
+    stats.coverage = stats.total.coverage();
+
+    Ok(stats)
+}
+
+impl Stats {
+    pub fn print(&self, format: OutputFormat, color: ColorChoice) -> Result<(), miette::Error> {
+        match format {
+            OutputFormat::Human => {
+                println!(
+                    "{:<16} {:>8} {:>8} {:>10} {:>9} {:>9}",
+                    "language", "valid", "missing", "mismatched", "too small", "timed out"
+                );
+                for (name, counts) in &self.languages {
+                    println!(
+                        "{name:<16} {:>8} {:>8} {:>10} {:>9} {:>9}",
+                        counts.valid,
+                        counts.missing,
+                        counts.mismatched,
+                        counts.too_small,
+                        counts.timed_out
+                    );
+                }
+
+                let coverage = format!("{:.1}%", self.coverage);
+                let coverage = if color.enabled_for(&std::io::stdout()) {
+                    if self.total.compliant() == self.total.total() {
+                        coverage.green().to_string()
+                    } else {
+                        coverage.yellow().to_string()
+                    }
+                } else {
+                    coverage
+                };
+                println!(
+                    "\nCoverage: {coverage} ({} of {} files)",
+                    self.total.compliant(),
+                    self.total.total()
+                );
+            }
+            OutputFormat::Json => {
+                println!("{}", serde_json::to_string_pretty(self).into_diagnostic()?);
+            }
+        }
+
+        Ok(())
+    }
+}
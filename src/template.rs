@@ -0,0 +1,303 @@
+// © Marcel Müller 2025, licensed under the EUPL
+
+//! Expansion of `{{variable}}` placeholders in license texts
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::LazyLock;
+use std::sync::Mutex;
+
+use camino::Utf8Path;
+use miette::Context;
+use miette::IntoDiagnostic;
+use miette::bail;
+use miette::miette;
+
+const GIT_AUTHOR: &str = "{{git_author}}";
+const END: &str = "{{end}}";
+const FILE: &str = "{{file}}";
+const YEAR: &str = "{{year}}";
+/// What a `{{year}}` placeholder matches, a year optionally followed by the last year of a range
+const YEAR_PATTERN: &str = r"(\d{4})(?:-(\d{4}))?";
+
+/// Replaces all known template variables in the given license text
+///
+/// - `{{git_author}}`: The `user.name` from the git configuration, escaped for license texts that
+///   are regular expressions
+///
+/// `{{file}}` is kept, as it is replaced per file by [`with_file`]. `{{year}}` is kept, as it
+/// matches a year like `2025` or a range ending in the current year
+/// like `2020-2025` when verifying, and is replaced by the current year when inserting.
+pub fn expand(text: &str, regex: bool) -> Result<String, miette::Error> {
+    let mut text = text.to_string();
+
+    if text.contains(GIT_AUTHOR) {
+        let author = git_author()?;
+        let author = if regex {
+            regex::escape(&author)
+        } else {
+            author
+        };
+        text = text.replace(GIT_AUTHOR, &author);
+    }
+
+    Ok(text)
+}
+
+/// Replaces `{{file}}` with the path of `file` relative to the root of its git repository, or the
+/// current directory outside of repositories, with forward slashes, escaped for license texts
+/// that are regular expressions
+pub fn with_file<'t>(text: &'t str, file: &Utf8Path, regex: bool) -> Cow<'t, str> {
+    if !text.contains(FILE) {
+        return Cow::Borrowed(text);
+    }
+
+    let path = crate::patch::repo_relative_path(file)
+        .as_str()
+        .replace('\\', "/");
+    let path = if regex { regex::escape(&path) } else { path };
+    Cow::Owned(text.replace(FILE, &path))
+}
+
+/// Whether the text contains `{{file}}`, so that it differs between files
+pub fn mentions_file(text: &str) -> bool {
+    text.contains(FILE)
+}
+
+/// The part of a license text that is verified, i.e. everything before an `{{end}}` line
+pub fn verified_part(text: &str) -> &str {
+    match end_line(text) {
+        Some((start, _)) => {
+            let text = &text[..start];
+            let text = text.strip_suffix('\n').unwrap_or(text);
+            text.strip_suffix('\r').unwrap_or(text)
+        }
+        None => text,
+    }
+}
+
+/// The license text as inserted by `fix`, i.e. without an `{{end}}` line and with the current
+/// year for `{{year}}`
+pub fn inserted_text(text: &str) -> Cow<'_, str> {
+    let text = match end_line(text) {
+        Some((start, end)) => Cow::Owned(format!("{}{}", &text[..start], &text[end..])),
+        None => Cow::Borrowed(text),
+    };
+    if text.contains(YEAR) {
+        Cow::Owned(text.replace(YEAR, &crate::year::current_year().to_string()))
+    } else {
+        text
+    }
+}
+
+/// A regular expression license text with `{{year}}` replaced by the pattern of the years it
+/// matches
+pub fn year_regex(text: &str) -> Cow<'_, str> {
+    if text.contains(YEAR) {
+        let current_year = crate::year::current_year();
+        Cow::Owned(text.replace(YEAR, &format!(r"(?:\d{{4}}-{current_year}|\d{{4}})")))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+/// Whether the `found` line is the `expected` one, with the `{{year}}` placeholders matching a
+/// single year or a range ending in the current year
+pub fn years_match(expected: &str, found: &str) -> bool {
+    let current_year = crate::year::current_year();
+    matched_years(expected, found).is_some_and(|years| {
+        years
+            .iter()
+            .all(|(_, first, last)| first == last || *last == current_year)
+    })
+}
+
+/// The `found` line with the `{{year}}` placeholders of `expected` extended to ranges ending in
+/// the current year, `None` if it does not match or is up to date
+///
+/// A single year like `2020` becomes `2020-2025`, an outdated range like `2020-2024` becomes
+/// `2020-2025`.
+pub fn updated_years(expected: &str, found: &str) -> Option<String> {
+    let current_year = crate::year::current_year();
+    let years = matched_years(expected, found)?;
+    if years.iter().all(|(_, _, last)| *last == current_year) {
+        return None;
+    }
+
+    let mut updated = String::new();
+    let mut end = 0;
+    for (range, first, _) in years {
+        updated.push_str(&found[end..range.start]);
+        if first == current_year {
+            updated.push_str(&current_year.to_string());
+        } else {
+            updated.push_str(&format!("{first}-{current_year}"));
+        }
+        end = range.end;
+    }
+    updated.push_str(&found[end..]);
+    Some(updated)
+}
+
+/// The byte range, first and last year matched by each `{{year}}` placeholder, the last year of
+/// a single year being the year itself
+fn matched_years(expected: &str, found: &str) -> Option<Vec<(Range<usize>, u32, u32)>> {
+    if !expected.contains(YEAR) {
+        return None;
+    }
+
+    let captures = line_regex(expected)?.captures(found)?;
+
+    let placeholders = (captures.len() - 1) / 2;
+    (0..placeholders)
+        .map(|index| {
+            let first = captures.get(2 * index + 1)?;
+            let last = captures.get(2 * index + 2).unwrap_or(first);
+            Some((
+                first.start()..last.end(),
+                first.as_str().parse().ok()?,
+                last.as_str().parse().ok()?,
+            ))
+        })
+        .collect()
+}
+
+/// The expression matching the `expected` line, with each `{{year}}` placeholder capturing the
+/// first and last year
+///
+/// The expressions are compiled once per line, as every line of every file is compared.
+fn line_regex(expected: &str) -> Option<regex::Regex> {
+    static LINE_REGEXES: LazyLock<Mutex<HashMap<String, Option<regex::Regex>>>> =
+        LazyLock::new(Mutex::default);
+
+    let mut regexes = LINE_REGEXES
+        .lock()
+        .expect("A thread panicked while compiling the expression of a line");
+    if let Some(regex) = regexes.get(expected) {
+        return regex.clone();
+    }
+
+    let pattern = expected
+        .split(YEAR)
+        .map(regex::escape)
+        .collect::<Vec<_>>()
+        .join(YEAR_PATTERN);
+    let regex = regex::Regex::new(&format!("^{pattern}$")).ok();
+    regexes.insert(expected.to_string(), regex.clone());
+    regex
+}
+
+/// The byte range of the first line consisting only of `{{end}}`, including its newline
+fn end_line(text: &str) -> Option<(usize, usize)> {
+    let mut start = 0;
+    for line in text.split_inclusive('\n') {
+        if line.trim() == END {
+            return Some((start, start + line.len()));
+        }
+        start += line.len();
+    }
+    None
+}
+
+fn git_author() -> Result<String, miette::Error> {
+    let output = std::process::Command::new("git")
+        .args(["config", "user.name"])
+        .output()
+        .into_diagnostic()
+        .with_context(|| miette!("Could not run git to resolve {GIT_AUTHOR}"))?;
+
+    let name = String::from_utf8(output.stdout).into_diagnostic()?;
+    let name = name.trim();
+    if !output.status.success() || name.is_empty() {
+        bail!("Could not resolve {GIT_AUTHOR}, as git has no user.name configured");
+    }
+
+    Ok(name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_verified_part_ends_before_the_end_line() {
+        assert_eq!(
+            verified_part("Copyright Foo\n{{end}}\nMore"),
+            "Copyright Foo"
+        );
+        assert_eq!(
+            verified_part("Copyright Foo\r\n{{end}}\r\n"),
+            "Copyright Foo"
+        );
+        assert_eq!(
+            verified_part("Copyright Foo {{end}}"),
+            "Copyright Foo {{end}}"
+        );
+        assert_eq!(verified_part("Copyright Foo"), "Copyright Foo");
+    }
+
+    #[test]
+    fn inserted_texts_drop_the_end_line_and_fill_in_the_year() {
+        let year = crate::year::current_year();
+        assert_eq!(
+            inserted_text("Copyright Foo\n{{end}}\nMore"),
+            "Copyright Foo\nMore"
+        );
+        assert_eq!(
+            inserted_text("Copyright {{year}} Foo"),
+            format!("Copyright {year} Foo")
+        );
+    }
+
+    #[test]
+    fn file_placeholders_are_found() {
+        assert!(mentions_file("See {{file}}"));
+        assert!(!mentions_file("Copyright {{year}} Foo"));
+    }
+
+    #[test]
+    fn years_match_single_years() {
+        assert!(years_match("Copyright {{year}} Foo", "Copyright 2020 Foo"));
+        assert!(!years_match("Copyright {{year}} Foo", "Copyright 20 Foo"));
+        assert!(!years_match("Copyright {{year}} Foo", "Copyright 2020 Bar"));
+    }
+
+    #[test]
+    fn years_match_ranges_ending_in_the_current_year() {
+        let year = crate::year::current_year();
+        let expected = "Copyright {{year}} Foo";
+        assert!(years_match(expected, &format!("Copyright 2020-{year} Foo")));
+        assert!(!years_match(
+            expected,
+            &format!("Copyright 2020-{} Foo", year - 1)
+        ));
+    }
+
+    #[test]
+    fn outdated_years_are_extended_to_ranges() {
+        let year = crate::year::current_year();
+        let expected = "Copyright {{year}} Foo, {{year}} Bar";
+        assert_eq!(
+            updated_years(
+                expected,
+                &format!("Copyright 2020 Foo, 2019-{} Bar", year - 1)
+            ),
+            Some(format!("Copyright 2020-{year} Foo, 2019-{year} Bar"))
+        );
+        assert_eq!(
+            updated_years(expected, &format!("Copyright {year} Foo, 2019-{year} Bar")),
+            None
+        );
+        assert_eq!(updated_years(expected, "Copyright Foo"), None);
+    }
+
+    #[test]
+    fn year_regexes_match_years_and_ranges() {
+        let year = crate::year::current_year();
+        let regex = regex::Regex::new(&format!("^{}$", year_regex("Copyright {{year}}"))).unwrap();
+        assert!(regex.is_match("Copyright 2020"));
+        assert!(regex.is_match(&format!("Copyright 2020-{year}")));
+        assert!(!regex.is_match("Copyright 2020-1999"));
+    }
+}
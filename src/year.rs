@@ -0,0 +1,194 @@
+// © Marcel Müller 2025, licensed under the EUPL
+
+//! Checking the copyright years of headers against the git history
+
+use std::time::SystemTime;
+
+use camino::Utf8Path;
+use miette::Context;
+use miette::IntoDiagnostic;
+use miette::bail;
+use miette::miette;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use tracing::debug;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Which commit of a file determines the year its header has to contain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GitYear {
+    /// The last commit changing the file
+    LastModified,
+    /// The commit adding the file, following renames
+    Created,
+}
+
+/// A header whose copyright year differs from the year git reports for the file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct YearMismatch {
+    /// The last year in the header, `None` if it contains no year
+    pub found: Option<u32>,
+    pub expected: u32,
+}
+
+/// Compares the last year in the header lines with the year of the given commit of `file`
+///
+/// Files without commits are not checked, as they have no year yet.
+pub fn check(
+    file: &Utf8Path,
+    which: GitYear,
+    header: &[impl AsRef<str>],
+) -> Result<Option<YearMismatch>, miette::Error> {
+    let Some(expected) = git_year(file, which)? else {
+        debug!("Not checking the copyright year of {file}, as it has no commits");
+        return Ok(None);
+    };
+
+    let found = header_year(header);
+    if found == Some(expected) {
+        Ok(None)
+    } else {
+        Ok(Some(YearMismatch { found, expected }))
+    }
+}
+
+/// The current year in UTC, or the year of `SOURCE_DATE_EPOCH` if it is set for reproducible
+/// output
+pub fn current_year() -> u32 {
+    let seconds = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.trim().parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_secs())
+        });
+    year_of_day(seconds / SECONDS_PER_DAY)
+}
+
+/// The year of the given day since the Unix epoch, in the proleptic Gregorian calendar
+///
+/// This is the `civil_from_days` algorithm by Howard Hinnant.
+fn year_of_day(days: u64) -> u32 {
+    // Shift the epoch to 0000-03-01, so that leap days are at the end of years
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let year = year_of_era + era * 400 + u64::from(month >= 10);
+    u32::try_from(year).unwrap_or(u32::MAX)
+}
+
+/// The last year mentioned in the header, e.g. the end of a range like `2020-2025`
+fn header_year(header: &[impl AsRef<str>]) -> Option<u32> {
+    header
+        .iter()
+        .flat_map(|line| {
+            line.as_ref()
+                .split(|c: char| !c.is_ascii_digit())
+                .filter(|word| word.len() == 4)
+                .filter_map(|word| word.parse::<u32>().ok())
+                .collect::<Vec<_>>()
+        })
+        .filter(|year| (1970..3000).contains(year))
+        .last()
+}
+
+/// The year of the given commit of `file` according to git, `None` if it has no commits
+fn git_year(file: &Utf8Path, which: GitYear) -> Result<Option<u32>, miette::Error> {
+    let directory = file
+        .parent()
+        .filter(|parent| !parent.as_str().is_empty())
+        .unwrap_or(Utf8Path::new("."));
+    let Some(name) = file.file_name() else {
+        bail!("Could not determine the git history of {file}, as it has no file name");
+    };
+
+    let output = std::process::Command::new("git")
+        .arg("-C")
+        .arg(directory)
+        .args(["log", "--follow", "--format=%ad", "--date=format:%Y", "--"])
+        .arg(name)
+        .output()
+        .into_diagnostic()
+        .with_context(|| miette!("Could not run git to determine the year of {file}"))?;
+    if !output.status.success() {
+        if !has_commits(directory)? {
+            return Ok(None);
+        }
+        bail!(
+            "Could not determine the git history of {file}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let log = String::from_utf8(output.stdout).into_diagnostic()?;
+    let year = match which {
+        GitYear::LastModified => log.lines().next(),
+        GitYear::Created => log.lines().last(),
+    };
+
+    year.map(|year| {
+        year.trim()
+            .parse()
+            .into_diagnostic()
+            .with_context(|| miette!("git reported an invalid year {year} for {file}"))
+    })
+    .transpose()
+}
+
+/// Whether the repository containing `directory` has any commits, which `git log` requires
+fn has_commits(directory: &Utf8Path) -> Result<bool, miette::Error> {
+    let status = std::process::Command::new("git")
+        .arg("-C")
+        .arg(directory)
+        .args(["rev-parse", "--quiet", "--verify", "HEAD"])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .into_diagnostic()
+        .with_context(|| miette!("Could not run git in {directory}"))?;
+    Ok(status.success())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn years_of_days_since_the_epoch() {
+        assert_eq!(year_of_day(0), 1970);
+        assert_eq!(year_of_day(364), 1970);
+        assert_eq!(year_of_day(365), 1971);
+        // 2000-02-29, 2000-12-31 and 2001-01-01
+        assert_eq!(year_of_day(11_016), 2000);
+        assert_eq!(year_of_day(11_322), 2000);
+        assert_eq!(year_of_day(11_323), 2001);
+        assert_eq!(year_of_day(1_750_000_000 / SECONDS_PER_DAY), 2025);
+    }
+
+    #[test]
+    fn header_years_are_the_last_year_mentioned() {
+        assert_eq!(header_year(&["Copyright 2020 Foo"]), Some(2020));
+        assert_eq!(header_year(&["Copyright 2020-2024 Foo"]), Some(2024));
+        assert_eq!(
+            header_year(&["Copyright 2019 Foo", "Copyright 2021 Bar"]),
+            Some(2021)
+        );
+    }
+
+    #[test]
+    fn header_years_ignore_other_numbers() {
+        assert_eq!(header_year(&["Copyright Foo"]), None);
+        assert_eq!(header_year(&["Version 12345 of 1234"]), None);
+        assert_eq!(
+            header_year(&["Copyright 2020 Foo", "See RFC 9999"]),
+            Some(2020)
+        );
+    }
+}
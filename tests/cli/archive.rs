@@ -0,0 +1,165 @@
+// © Marcel Müller 2025, licensed under the EUPL
+
+use std::io::Cursor;
+use std::io::Write;
+
+use crate::CONFIG;
+use crate::Project;
+
+const ENTRIES: [(&str, &str); 3] = [
+    ("src/valid.rs", "// Copyright Foo\nfn main() {}\n"),
+    ("src/invalid.rs", "fn main() {}\n"),
+    ("README.md", "Not checked\n"),
+];
+
+fn tar_archive(entries: &[(&str, &str)]) -> Vec<u8> {
+    let mut builder = tar::Builder::new(vec![]);
+    for (path, content) in entries {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        builder
+            .append_data(&mut header, path, content.as_bytes())
+            .expect("Could not add a file to the archive");
+    }
+    builder.into_inner().expect("Could not write the archive")
+}
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+    encoder
+        .write_all(data)
+        .expect("Could not compress the archive");
+    encoder.finish().expect("Could not compress the archive")
+}
+
+fn zip_archive(entries: &[(&str, &str)]) -> Vec<u8> {
+    let mut writer = zip::ZipWriter::new(Cursor::new(vec![]));
+    for (path, content) in entries {
+        writer
+            .start_file(*path, zip::write::SimpleFileOptions::default())
+            .expect("Could not add a file to the archive");
+        writer
+            .write_all(content.as_bytes())
+            .expect("Could not add a file to the archive");
+    }
+    writer
+        .finish()
+        .expect("Could not write the archive")
+        .into_inner()
+}
+
+/// A project containing the archive at `path`
+fn project(path: &str, archive: &[u8]) -> Project {
+    let project = Project::new(CONFIG);
+    std::fs::write(project.path().join(path), archive).expect("Could not write the archive");
+    project
+}
+
+#[test]
+fn files_in_archives_are_verified() {
+    for (path, archive) in [
+        ("release.tar", tar_archive(&ENTRIES)),
+        ("release.tar.gz", gzip(&tar_archive(&ENTRIES))),
+        ("release.zip", zip_archive(&ENTRIES)),
+    ] {
+        project(path, &archive)
+            .lizenz(&["verify", "--archive", path])
+            .assert_code(1)
+            .assert_output("src/invalid.rs: missing or incorrect license header")
+            .assert_no_output("src/valid.rs")
+            .assert_no_output("README.md");
+    }
+}
+
+#[test]
+fn archives_with_only_valid_files_pass() {
+    project("release.tar", &tar_archive(&ENTRIES[..1]))
+        .lizenz(&["verify", "--archive", "release.tar"])
+        .assert_code(0);
+}
+
+#[test]
+fn archive_entries_are_filtered_by_includes() {
+    project("release.tar", &tar_archive(&ENTRIES))
+        .lizenz(&[
+            "verify",
+            "--archive",
+            "release.tar",
+            "--include",
+            "valid.rs",
+        ])
+        .assert_code(0);
+}
+
+#[test]
+fn large_archive_entries_are_skipped() {
+    project("release.zip", &zip_archive(&ENTRIES))
+        .lizenz(&["verify", "--archive", "release.zip", "--max-filesize", "10"])
+        .assert_code(0)
+        .assert_output("Skipping src/valid.rs, as it is larger than 10 bytes");
+}
+
+#[test]
+fn archive_entries_are_verified_in_parallel() {
+    let names = (0..20).map(|i| format!("src/{i}.rs")).collect::<Vec<_>>();
+    let entries = names
+        .iter()
+        .map(|name| (name.as_str(), "fn main() {}\n"))
+        .collect::<Vec<_>>();
+
+    let run = project("release.tar", &tar_archive(&entries)).lizenz(&[
+        "--jobs",
+        "4",
+        "verify",
+        "--archive",
+        "release.tar",
+    ]);
+    run.assert_code(1);
+    for name in &names {
+        run.assert_output(&format!("{name}: missing"));
+    }
+}
+
+#[test]
+fn archives_cannot_be_fixed() {
+    project("release.tar", &tar_archive(&ENTRIES))
+        .lizenz(&["verify", "--fix", "--archive", "release.tar"])
+        .assert_code(2);
+}
+
+#[test]
+fn invalid_archives_are_errors() {
+    project("release.tar", b"not an archive")
+        .lizenz(&["verify", "--archive", "release.tar"])
+        .assert_code(2)
+        .assert_output("Could not read the archive release.tar");
+}
+
+#[test]
+fn ignore_missing_grammar_skips_archived_files_without_a_grammar() {
+    let project = Project::new(&format!(
+        "{CONFIG}\n[languages.lisp]\nfile_endings = [\"*.lisp\"]\ncomments = [{{ tree_sitter_name = \"comment\", comment_kind = {{ Single = \";\" }}, preferred = true }}]\n"
+    ));
+    let archive = tar_archive(&[
+        ("src/main.lisp", "(main)\n"),
+        ("src/invalid.rs", "fn main() {}\n"),
+    ]);
+    std::fs::write(project.path().join("release.tar"), archive)
+        .expect("Could not write the archive");
+
+    project
+        .lizenz(&["verify", "--archive", "release.tar"])
+        .assert_code(2)
+        .assert_output("Found language lisp but no tree-sitter grammar exists for it");
+    project
+        .lizenz(&[
+            "verify",
+            "--ignore-missing-grammar",
+            "--archive",
+            "release.tar",
+        ])
+        .assert_code(1)
+        .assert_output("Skipping src/main.lisp, as no grammar exists for its language lisp")
+        .assert_output("src/invalid.rs: missing or incorrect license header");
+}
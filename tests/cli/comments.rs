@@ -0,0 +1,761 @@
+// © Marcel Müller 2025, licensed under the EUPL
+
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::CONFIG;
+use crate::Project;
+use crate::config;
+
+const LICENSE: &str = "[license]\ntext = \"Copyright Foo\\nAll rights reserved\"\n";
+
+/// A configuration whose Rust language only has the given comments
+fn rust_comments(comments: &str) -> String {
+    format!("{LICENSE}\n[languages.rust]\nfile_endings = [\"*.rs\"]\ncomments = [{comments}]\n")
+}
+
+const DOC_BLOCK_COMMENTS: &str = r#"{ tree_sitter_name = "block_comment", comment_kind = { Multi = { start = "/*", start_alt = "/**", between = "*", end = "*/" } }, preferred = true }"#;
+
+#[test]
+fn alternative_openers_verify() {
+    Project::new(&rust_comments(DOC_BLOCK_COMMENTS))
+        .file(
+            "main.rs",
+            "/** Copyright Foo\n * All rights reserved\n */\nfn main() {}\n",
+        )
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(0);
+}
+
+#[test]
+fn regular_openers_still_verify_with_an_alternative() {
+    Project::new(&rust_comments(DOC_BLOCK_COMMENTS))
+        .file(
+            "main.rs",
+            "/* Copyright Foo\n * All rights reserved\n */\nfn main() {}\n",
+        )
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(0);
+}
+
+#[test]
+fn fix_inserts_the_alternative_opener() {
+    let project = Project::new(&rust_comments(DOC_BLOCK_COMMENTS));
+    project
+        .file("main.rs", "fn main() {}\n")
+        .lizenz(&["fix", "main.rs"])
+        .assert_code(0);
+
+    assert_eq!(
+        project.read("main.rs"),
+        "/** Copyright Foo\n * All rights reserved */\nfn main() {}\n"
+    );
+    project.lizenz(&["verify", "main.rs"]).assert_code(0);
+}
+
+#[test]
+fn headers_in_non_preferred_styles_verify() {
+    Project::new(&crate::config(
+        r#"text = "Copyright Foo\nAll rights reserved""#,
+        "",
+    ))
+    .file(
+        "main.rs",
+        "/* Copyright Foo\n * All rights reserved\n */\nfn main() {}\n",
+    )
+    .lizenz(&["verify", "main.rs"])
+    .assert_code(0);
+}
+
+#[test]
+fn fix_inserts_missing_headers_in_the_preferred_style() {
+    let project = Project::new(&crate::config(
+        r#"text = "Copyright Foo\nAll rights reserved""#,
+        "",
+    ));
+    project
+        .file("missing.rs", "fn main() {}\n")
+        .file(
+            "block.rs",
+            "/* Copyright Foo\n * All rights reserved\n */\nfn main() {}\n",
+        )
+        .lizenz(&["fix", "missing.rs", "block.rs"])
+        .assert_code(0);
+
+    assert_eq!(
+        project.read("missing.rs"),
+        "// Copyright Foo\n// All rights reserved\nfn main() {}\n"
+    );
+    assert_eq!(
+        project.read("block.rs"),
+        "/* Copyright Foo\n * All rights reserved\n */\nfn main() {}\n"
+    );
+}
+
+#[test]
+fn headers_of_files_with_syntax_errors_verify() {
+    let project = Project::new(crate::CONFIG);
+    project
+        .file("broken.rs", "// Copyright Foo\n}}} fn main( {\nlet = ;\n")
+        .file("missing.rs", "}}} fn main( {\nlet = ;\n");
+
+    project.lizenz(&["verify", "broken.rs"]).assert_code(0);
+    project.lizenz(&["verify", "missing.rs"]).assert_code(1);
+}
+
+/// Shell scripts whose comments start with `#REM`, matched regardless of case if `insensitive`
+fn rem_comments(insensitive: bool) -> String {
+    format!(
+        "{LICENSE}\n[languages.bash]\nfile_endings = [\"*.sh\"]\ncomments = [{{ tree_sitter_name = \"comment\", comment_kind = {{ Single = \"#REM\" }}, preferred = true, case_insensitive_marker = {insensitive} }}]\n"
+    )
+}
+
+#[test]
+fn case_insensitive_markers_match_mixed_case() {
+    let script = "#Rem Copyright Foo\n#rem All rights reserved\necho hello\n";
+
+    Project::new(&rem_comments(true))
+        .file("run.sh", script)
+        .lizenz(&["verify", "run.sh"])
+        .assert_code(0);
+    Project::new(&rem_comments(false))
+        .file("run.sh", script)
+        .lizenz(&["verify", "run.sh"])
+        .assert_code(1);
+}
+
+#[test]
+fn case_insensitive_markers_keep_their_case_when_fixing() {
+    let project = Project::new(&rem_comments(true));
+    project
+        .file("run.sh", "echo hello\n")
+        .lizenz(&["fix", "run.sh"])
+        .assert_code(0);
+
+    assert_eq!(
+        project.read("run.sh"),
+        "#REM Copyright Foo\n#REM All rights reserved\necho hello\n"
+    );
+}
+
+const PLAIN_BLOCK_COMMENTS: &str = r#"{ tree_sitter_name = "block_comment", comment_kind = { Multi = { start = "/*", end = "*/" } }, preferred = true }"#;
+
+const INDENTED_LICENSE: &str = "[license]\ntext = \"Copyright Foo\\nTerms:\\n  - keep this\"\n";
+
+/// A configuration with the indented license whose Rust language only has the given comments
+fn indented_license(comments: &str) -> String {
+    format!(
+        "{INDENTED_LICENSE}\n[languages.rust]\nfile_endings = [\"*.rs\"]\ncomments = [{comments}]\n"
+    )
+}
+
+#[test]
+fn blocks_without_between_keep_relative_indentation() {
+    let project = Project::new(&indented_license(PLAIN_BLOCK_COMMENTS));
+    project
+        .file(
+            "aligned.rs",
+            "/* Copyright Foo\n   Terms:\n     - keep this\n*/\nfn main() {}\n",
+        )
+        .file(
+            "flattened.rs",
+            "/* Copyright Foo\n   Terms:\n   - keep this\n*/\nfn main() {}\n",
+        );
+
+    project.lizenz(&["verify", "aligned.rs"]).assert_code(0);
+    project.lizenz(&["verify", "flattened.rs"]).assert_code(1);
+}
+
+#[test]
+fn blocks_with_between_strip_the_marker_and_indentation() {
+    Project::new(&indented_license(
+        r#"{ tree_sitter_name = "block_comment", comment_kind = { Multi = { start = "/*", between = "*", end = "*/" } }, preferred = true }"#,
+    ))
+    .file(
+        "main.rs",
+        "/* Copyright Foo\n * Terms:\n *   - keep this\n */\nfn main() {}\n",
+    )
+    .lizenz(&["verify", "main.rs"])
+    .assert_code(0);
+}
+
+#[test]
+fn fix_round_trips_blocks_without_between() {
+    let project = Project::new(&indented_license(PLAIN_BLOCK_COMMENTS));
+    project
+        .file("main.rs", "fn main() {}\n")
+        .lizenz(&["fix", "main.rs"])
+        .assert_code(0);
+
+    assert_eq!(
+        project.read("main.rs"),
+        "/* Copyright Foo\nTerms:\n  - keep this */\nfn main() {}\n"
+    );
+    project.lizenz(&["verify", "main.rs"]).assert_code(0);
+}
+
+#[test]
+fn comments_with_multibyte_characters_verify() {
+    // Nodes always end on character boundaries of valid UTF-8, so their text can be read
+    Project::new("[license]\ntext = \"© Föö 😀\\nAll rights reserved\"\n")
+        .file(
+            "main.rs",
+            "// © Föö 😀\n// All rights reserved\n/* ü */ fn main() {}\n",
+        )
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(0)
+        .assert_no_output("not valid UTF-8");
+}
+
+#[test]
+fn block_comments_followed_by_code_on_the_same_line_verify() {
+    Project::new(&rust_comments(PLAIN_BLOCK_COMMENTS))
+        .file(
+            "main.rs",
+            "/* Copyright Foo\n   All rights reserved */ fn main() {}\n",
+        )
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(0);
+}
+
+#[test]
+fn comments_after_code_on_the_same_line_are_not_part_of_the_header() {
+    Project::new(&rust_comments(PLAIN_BLOCK_COMMENTS))
+        .file(
+            "main.rs",
+            "/* Copyright Foo */ fn main() {} /* All rights reserved */\n",
+        )
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(1);
+}
+
+#[test]
+fn headers_split_by_code_fail() {
+    Project::new(LICENSE)
+        .file(
+            "main.rs",
+            "// Copyright Foo\nfn main() {}\n// All rights reserved\n",
+        )
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(1);
+}
+
+#[test]
+fn headers_at_the_end_of_files_fail() {
+    Project::new(LICENSE)
+        .file(
+            "main.rs",
+            "fn main() {}\n// Copyright Foo\n// All rights reserved\n",
+        )
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(1);
+}
+
+#[test]
+fn headers_after_a_shebang_verify() {
+    Project::new(LICENSE)
+        .file(
+            "main.rs",
+            "#!/usr/bin/env run-cargo-script\n// Copyright Foo\n// All rights reserved\nfn main() {}\n",
+        )
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(0);
+}
+
+#[test]
+fn fix_inserts_above_block_comments_followed_by_code() {
+    let project = Project::new(&rust_comments(PLAIN_BLOCK_COMMENTS));
+    project
+        .file("main.rs", "/* helper */ fn main() {}\n")
+        .lizenz(&["fix", "main.rs"])
+        .assert_code(0);
+    assert_eq!(
+        project.read("main.rs"),
+        "/* Copyright Foo\nAll rights reserved */\n/* helper */ fn main() {}\n"
+    );
+    project.lizenz(&["verify", "main.rs"]).assert_code(0);
+}
+
+#[test]
+fn comment_kinds_configured_twice_are_configuration_errors() {
+    Project::new(&rust_comments(&format!(
+        "{PLAIN_BLOCK_COMMENTS}, {DOC_BLOCK_COMMENTS}"
+    )))
+    .file("main.rs", "fn main() {}\n")
+    .lizenz(&["verify", "main.rs"])
+    .assert_code(2)
+    .assert_output(
+        "The language rust configures comments of the node kind block_comment more than once",
+    );
+}
+
+#[test]
+fn single_comments_with_the_same_marker_are_configuration_errors() {
+    let line = r#"{ tree_sitter_name = "line_comment", comment_kind = { Single = "//" }, preferred = true }"#;
+    Project::new(&rust_comments(&format!("{line}, {line}")))
+        .file("main.rs", "fn main() {}\n")
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(2)
+        .assert_output("node kind line_comment more than once");
+}
+
+#[test]
+fn single_comments_with_different_markers_share_a_node_kind() {
+    let line = r#"{ tree_sitter_name = "line_comment", comment_kind = { Single = "//" }, preferred = true }"#;
+    let doc = r#"{ tree_sitter_name = "line_comment", comment_kind = { Single = "//!" }, preferred = false }"#;
+    Project::new(&rust_comments(&format!("{line}, {doc}")))
+        .file(
+            "main.rs",
+            "//! Copyright Foo\n//! All rights reserved\nfn main() {}\n",
+        )
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(0);
+}
+
+const LINE_COMMENTS: &str =
+    r#"{ tree_sitter_name = "line_comment", comment_kind = { Single = "//" }, preferred = false }"#;
+
+const MODULE_DOC_COMMENTS: &str =
+    r#"{ tree_sitter_name = "line_comment", comment_kind = { Single = "//!" }, preferred = true }"#;
+
+#[test]
+fn fix_inserts_preferred_module_doc_comments() {
+    let project = Project::new(&rust_comments(&format!(
+        "{LINE_COMMENTS}, {MODULE_DOC_COMMENTS}"
+    )));
+    project
+        .file("main.rs", "fn main() {}\n")
+        .lizenz(&["fix", "main.rs"])
+        .assert_code(0);
+
+    assert_eq!(
+        project.read("main.rs"),
+        "//! Copyright Foo\n//! All rights reserved\nfn main() {}\n"
+    );
+    project.lizenz(&["verify", "main.rs"]).assert_code(0);
+}
+
+#[test]
+fn module_doc_comments_are_not_line_comments() {
+    Project::new(&rust_comments(LINE_COMMENTS))
+        .file(
+            "main.rs",
+            "//! Copyright Foo\n//! All rights reserved\nfn main() {}\n",
+        )
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(1);
+}
+
+#[test]
+fn default_rust_comments_include_module_doc_comments() {
+    let project = Project::new("[license]\ntext = \"Copyright Foo\"\n");
+    project
+        .file("main.rs", "//! Copyright Foo\nfn main() {}\n")
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(0);
+    project
+        .file("other.rs", "fn main() {}\n")
+        .lizenz(&["fix", "other.rs"])
+        .assert_code(0);
+    assert_eq!(project.read("other.rs"), "// Copyright Foo\nfn main() {}\n");
+}
+
+#[test]
+fn shebang_comments_are_not_part_of_the_header() {
+    let project = Project::new("[license]\ntext = \"Copyright Foo\"\n");
+    project
+        .file(
+            "run.sh",
+            "#!/usr/bin/env bash\n# Copyright Foo\necho hello\n",
+        )
+        .lizenz(&["verify", "run.sh"])
+        .assert_code(0);
+    project
+        .file("run.sh", "#!/usr/bin/env bash\necho hello\n")
+        .lizenz(&["verify", "run.sh"])
+        .assert_code(1);
+}
+
+#[test]
+fn shebang_comments_behind_a_byte_order_mark_are_not_part_of_the_header() {
+    Project::new("[license]\ntext = \"Copyright Foo\"\n")
+        .file(
+            "run.sh",
+            "\u{feff}#!/usr/bin/env bash\n# Copyright Foo\necho hello\n",
+        )
+        .lizenz(&["verify", "run.sh"])
+        .assert_code(0);
+}
+
+/// A license fitting into a one-line block comment
+fn one_line_license(comments: &str) -> String {
+    format!(
+        "[license]\ntext = \"MIT\"\n\n[languages.rust]\nfile_endings = [\"*.rs\"]\ncomments = [{comments}]\n"
+    )
+}
+
+const BETWEEN_BLOCK_COMMENTS: &str = r#"{ tree_sitter_name = "block_comment", comment_kind = { Multi = { start = "/*", between = "*", end = "*/" } }, preferred = true }"#;
+
+#[test]
+fn one_line_block_comments_verify() {
+    for comments in [PLAIN_BLOCK_COMMENTS, BETWEEN_BLOCK_COMMENTS] {
+        let project = Project::new(&one_line_license(comments));
+        for header in ["/* MIT */", "/*MIT*/", "/*  MIT  */"] {
+            project
+                .file("main.rs", &format!("{header}\nfn main() {{}}\n"))
+                .lizenz(&["verify", "main.rs"])
+                .assert_code(0);
+        }
+        project
+            .file("main.rs", "/* MITE */\nfn main() {}\n")
+            .lizenz(&["verify", "main.rs"])
+            .assert_code(1);
+    }
+}
+
+#[test]
+fn fix_inserts_one_line_block_comments() {
+    let project = Project::new(&one_line_license(BETWEEN_BLOCK_COMMENTS));
+    project
+        .file("main.rs", "fn main() {}\n")
+        .lizenz(&["fix", "main.rs"])
+        .assert_code(0);
+    assert_eq!(project.read("main.rs"), "/* MIT */\nfn main() {}\n");
+}
+
+/// A license whose lines are split between a block comment and a line comment
+const MIXED_LICENSE: &str = r#"[license]
+text = """
+Copyright Foo
+SPDX-License-Identifier: MIT"""
+"#;
+
+#[test]
+fn adjacent_comments_of_different_kinds_form_the_header() {
+    let project = Project::new(MIXED_LICENSE);
+    project
+        .file(
+            "line.rs",
+            "/* Copyright Foo */\n// SPDX-License-Identifier: MIT\nfn main() {}\n",
+        )
+        .file(
+            "block.rs",
+            "/*\n * Copyright Foo\n */\n// SPDX-License-Identifier: MIT\nfn main() {}\n",
+        )
+        .lizenz(&["verify", "line.rs", "block.rs"])
+        .assert_code(0);
+}
+
+#[test]
+fn blank_lines_between_comments_of_different_kinds_are_allowed() {
+    Project::new(MIXED_LICENSE)
+        .file(
+            "main.rs",
+            "/* Copyright Foo */\n\n\n// SPDX-License-Identifier: MIT\nfn main() {}\n",
+        )
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(0);
+}
+
+#[test]
+fn comments_of_different_kinds_keep_their_source_order() {
+    Project::new(MIXED_LICENSE)
+        .file(
+            "main.rs",
+            "// SPDX-License-Identifier: MIT\n/* Copyright Foo */\nfn main() {}\n",
+        )
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(1);
+}
+
+#[test]
+fn code_between_comments_of_different_kinds_ends_the_header() {
+    Project::new(MIXED_LICENSE)
+        .file(
+            "main.rs",
+            "/* Copyright Foo */\nuse std::io;\n// SPDX-License-Identifier: MIT\nfn main() {}\n",
+        )
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(1);
+}
+
+/// Files with stray comments of both kinds above and below the header
+fn project_with_stray_comments(config: &str) -> Project {
+    let project = Project::new(config);
+    project
+        .file("line.rs", "// TODO\n// Copyright Foo\nfn main() {}\n")
+        .file("block.rs", "/* TODO */\n// Copyright Foo\nfn main() {}\n")
+        .file("below.rs", "// Copyright Foo\n// TODO\nfn main() {}\n");
+    project
+}
+
+#[test]
+fn stray_comments_may_precede_the_header() {
+    project_with_stray_comments(CONFIG)
+        .lizenz(&["verify", "line.rs", "block.rs", "below.rs"])
+        .assert_code(0);
+}
+
+#[test]
+fn stray_comments_above_the_first_comment_header_fail_verification() {
+    project_with_stray_comments(&config(
+        "text = \"Copyright Foo\"\nheader_is_first_comment = true",
+        "",
+    ))
+    .lizenz(&["verify", "line.rs", "block.rs", "below.rs"])
+    .assert_code(1)
+    .assert_output("line.rs: missing or incorrect license header")
+    .assert_output("block.rs: missing or incorrect license header")
+    .assert_no_output("below.rs:");
+}
+
+#[test]
+fn fix_inserts_the_header_above_stray_comments() {
+    let project = Project::new(CONFIG);
+    project
+        .file("main.rs", "// TODO\nfn main() {}\n")
+        .lizenz(&["fix", "main.rs"])
+        .assert_code(0);
+    assert_eq!(
+        project.read("main.rs"),
+        "// Copyright Foo\n// TODO\nfn main() {}\n"
+    );
+}
+
+#[test]
+fn files_of_only_the_header_verify_and_are_not_fixed() {
+    let project = Project::new(CONFIG);
+    for content in [
+        "// Copyright Foo\n",
+        "// Copyright Foo",
+        "/* Copyright Foo */\n",
+    ] {
+        project.file("stub.rs", content);
+        project.lizenz(&["verify", "stub.rs"]).assert_code(0);
+        project.lizenz(&["fix", "stub.rs"]).assert_code(0);
+        assert_eq!(project.read("stub.rs"), content);
+    }
+}
+
+#[test]
+fn fix_of_files_of_only_other_comments_is_idempotent() {
+    let project = Project::new(CONFIG);
+    project
+        .file("stub.rs", "// Copyright Bar\n")
+        .lizenz(&["fix", "stub.rs"])
+        .assert_code(0);
+    assert_eq!(
+        project.read("stub.rs"),
+        "// Copyright Foo\n// Copyright Bar\n"
+    );
+
+    project.lizenz(&["fix", "stub.rs"]).assert_code(0);
+    assert_eq!(
+        project.read("stub.rs"),
+        "// Copyright Foo\n// Copyright Bar\n"
+    );
+}
+
+#[test]
+fn files_of_only_comments_hold_both_the_header_and_the_footer() {
+    let project = Project::new(&format!(
+        "{CONFIG}\n[licenses]\nfooter = \"End of Foo\"\n\n[languages.rust]\nfile_endings = [\"*.rs\"]\ncomments = [{{ tree_sitter_name = \"line_comment\", comment_kind = {{ Single = \"//\" }}, preferred = true }}]\nfooter = \"footer\"\n"
+    ));
+    project
+        .file("valid.rs", "// Copyright Foo\n// End of Foo\n")
+        .lizenz_with_log(&["verify", "valid.rs"], "lizenz=debug")
+        .assert_code(0)
+        .assert_log("valid.rs consists only of comments");
+
+    project
+        .file("stub.rs", "// Copyright Foo\n")
+        .lizenz(&["fix", "stub.rs"])
+        .assert_code(0);
+    assert_eq!(project.read("stub.rs"), "// Copyright Foo\n// End of Foo\n");
+    project.lizenz(&["fix", "stub.rs"]).assert_code(0);
+    assert_eq!(project.read("stub.rs"), "// Copyright Foo\n// End of Foo\n");
+}
+
+#[test]
+fn the_whitespace_after_single_line_markers_is_insignificant() {
+    let project = Project::new(CONFIG);
+    for header in [
+        "//Copyright Foo",
+        "// Copyright Foo",
+        "//  Copyright Foo",
+        "//\tCopyright Foo",
+    ] {
+        project
+            .file("main.rs", &format!("{header}\nfn main() {{}}\n"))
+            .lizenz(&["verify", "main.rs"])
+            .assert_code(0);
+    }
+}
+
+#[test]
+fn fix_round_trips_indented_license_lines() {
+    let project = Project::new("[license]\ntext = \"Copyright Foo\\n  All rights reserved\"\n");
+    project
+        .file("main.rs", "fn main() {}\n")
+        .lizenz(&["fix", "main.rs"])
+        .assert_code(0);
+    assert_eq!(
+        project.read("main.rs"),
+        "// Copyright Foo\n//   All rights reserved\nfn main() {}\n"
+    );
+    project.lizenz(&["verify", "main.rs"]).assert_code(0);
+    // The whitespace beyond the separating space is the indentation of the line
+    project
+        .file(
+            "other.rs",
+            "//Copyright Foo\n//All rights reserved\nfn main() {}\n",
+        )
+        .lizenz(&["verify", "other.rs"])
+        .assert_code(1);
+}
+
+#[test]
+fn long_single_line_files_are_verified_in_linear_time() {
+    // Like minified sources, about 2MB without a single newline
+    let code = "const A: u8 = 1; ".repeat(120_000);
+    let project = Project::new(CONFIG);
+    project
+        .file("valid.rs", &format!("/* Copyright Foo */ {code}"))
+        .file("invalid.rs", &code)
+        .file(
+            "shebang.rs",
+            &format!("#!/usr/bin/env run-cargo-script {code}"),
+        );
+
+    let start = Instant::now();
+    project.lizenz(&["verify", "valid.rs"]).assert_code(0);
+    project.lizenz(&["verify", "invalid.rs"]).assert_code(1);
+    project.lizenz(&["verify", "shebang.rs"]).assert_code(1);
+    project.lizenz(&["fix", "invalid.rs"]).assert_code(0);
+    project.lizenz(&["verify", "invalid.rs"]).assert_code(0);
+    // Quadratic work on a line this long takes hours, linear work seconds even in debug builds
+    assert!(
+        start.elapsed() < Duration::from_secs(120),
+        "{:?}",
+        start.elapsed()
+    );
+}
+
+#[test]
+fn comments_after_code_in_error_nodes_are_not_headers() {
+    Project::new(CONFIG)
+        .file("main.rs", "fn main(\n// Copyright Foo\n")
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(1);
+}
+
+#[test]
+fn headers_verify_in_every_default_rust_comment_style() {
+    let project = Project::new(LICENSE);
+    let styles = [
+        ("line.rs", "// Copyright Foo\n// All rights reserved\n"),
+        (
+            "module_doc.rs",
+            "//! Copyright Foo\n//! All rights reserved\n",
+        ),
+        (
+            "block.rs",
+            "/* Copyright Foo\n * All rights reserved\n */\n",
+        ),
+        (
+            "block_opened_alone.rs",
+            "/*\n * Copyright Foo\n * All rights reserved\n */\n",
+        ),
+        (
+            "block_without_stars.rs",
+            "/* Copyright Foo\n   All rights reserved */\n",
+        ),
+        (
+            "doc_block.rs",
+            "/**\n * Copyright Foo\n * All rights reserved\n */\n",
+        ),
+    ];
+    for (file, header) in styles {
+        project.file(file, &format!("{header}fn main() {{}}\n"));
+        project.lizenz(&["verify", file]).assert_code(0);
+    }
+
+    let mut args = vec!["verify"];
+    args.extend(styles.iter().map(|(file, _)| *file));
+    project.lizenz(&args).assert_code(0);
+}
+
+#[test]
+fn headers_differing_in_any_default_rust_comment_style_fail() {
+    let project = Project::new(LICENSE);
+    for (file, header) in [
+        ("line.rs", "// Copyright Foo\n// All rights reservd\n"),
+        (
+            "module_doc.rs",
+            "//! Copyright Foo\n//! All rights reservd\n",
+        ),
+        ("block.rs", "/* Copyright Foo\n * All rights reservd\n */\n"),
+    ] {
+        project
+            .file(file, &format!("{header}fn main() {{}}\n"))
+            .lizenz(&["verify", file])
+            .assert_code(1);
+    }
+}
+
+/// A configuration of a license with an interior blank line, written in `//` comments of the
+/// given `blank_line_style`, whose blank lines have to match
+fn blank_line_config(style: &str) -> String {
+    format!(
+        "[license]\ntext = \"Copyright Foo\\n\\nAll rights reserved\"\n\n[languages.rust]\nfile_endings = [\"*.rs\"]\npreserve_blank_comment_lines = true\ncomments = [{{ tree_sitter_name = \"line_comment\", comment_kind = {{ Single = \"//\" }}, preferred = true, blank_line_style = \"{style}\" }}]\n"
+    )
+}
+
+#[test]
+fn fix_renders_blank_lines_in_the_blank_line_style() {
+    for (style, header) in [
+        ("marker", "// Copyright Foo\n//\n// All rights reserved\n"),
+        ("empty", "// Copyright Foo\n\n// All rights reserved\n"),
+    ] {
+        let project = Project::new(&blank_line_config(style));
+        project
+            .file("main.rs", "fn main() {}\n")
+            .lizenz(&["fix", "main.rs"])
+            .assert_code(0);
+        assert_eq!(project.read("main.rs"), format!("{header}fn main() {{}}\n"));
+        project.lizenz(&["verify", "main.rs"]).assert_code(0);
+    }
+}
+
+#[test]
+fn blank_lines_verify_in_either_style() {
+    for style in ["marker", "empty"] {
+        let project = Project::new(&blank_line_config(style));
+        project
+            .file(
+                "marker.rs",
+                "// Copyright Foo\n//\n// All rights reserved\nfn main() {}\n",
+            )
+            .file(
+                "empty.rs",
+                "// Copyright Foo\n\n// All rights reserved\nfn main() {}\n",
+            )
+            .file(
+                "missing.rs",
+                "// Copyright Foo\n// All rights reserved\nfn main() {}\n",
+            );
+
+        project.lizenz(&["verify", "marker.rs"]).assert_code(0);
+        project.lizenz(&["verify", "empty.rs"]).assert_code(0);
+        project.lizenz(&["verify", "missing.rs"]).assert_code(1);
+    }
+}
+
+#[test]
+fn unknown_blank_line_styles_are_errors() {
+    Project::new(&blank_line_config("none"))
+        .file("main.rs", "fn main() {}\n")
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(2)
+        .assert_output("unknown variant `none`");
+}
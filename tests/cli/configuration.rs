@@ -0,0 +1,300 @@
+// © Marcel Müller 2025, licensed under the EUPL
+
+use crate::CONFIG;
+use crate::Project;
+
+#[test]
+fn configurations_may_start_with_a_byte_order_mark() {
+    Project::new(&format!("\u{feff}{CONFIG}"))
+        .file("main.rs", "// Copyright Foo\nfn main() {}\n")
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(0);
+}
+
+#[test]
+fn license_text_overrides_the_configuration() {
+    let project = Project::new(CONFIG);
+    project.file("main.rs", "// Copyright Bar\nfn main() {}\n");
+
+    project.lizenz(&["verify", "main.rs"]).assert_code(1);
+    project
+        .lizenz(&["--license-text", "Copyright Bar", "verify", "main.rs"])
+        .assert_code(0);
+}
+
+#[test]
+fn license_text_files_override_named_licenses() {
+    let project = Project::new(&format!(
+        "{CONFIG}\n[licenses]\nmit = \"Licensed under MIT\"\n\n[languages.rust]\nfile_endings = [\"*.rs\"]\ncomments = [{{ tree_sitter_name = \"line_comment\", comment_kind = {{ Single = \"//\" }}, preferred = true }}]\nlicense = \"mit\"\n"
+    ));
+    project
+        .file("license.txt", "Copyright Bar\n")
+        .file("main.rs", "fn main() {}\n")
+        .lizenz(&["--license-text-file", "license.txt", "fix", "main.rs"])
+        .assert_code(0);
+
+    assert_eq!(project.read("main.rs"), "// Copyright Bar\nfn main() {}\n");
+}
+
+#[test]
+fn license_text_and_license_text_file_conflict() {
+    Project::new(CONFIG)
+        .file("license.txt", "Copyright Bar\n")
+        .lizenz(&[
+            "--license-text",
+            "Copyright Bar",
+            "--license-text-file",
+            "license.txt",
+            "verify",
+        ])
+        .assert_code(2);
+}
+
+/// A license written as a multi-line string, ending in a newline
+const TRAILING_NEWLINE_CONFIG: &str =
+    "[license]\ntext = \"\"\"\nCopyright Foo\nAll rights reserved\n\"\"\"\n";
+
+#[test]
+fn trailing_newlines_of_license_texts_are_ignored() {
+    Project::new(TRAILING_NEWLINE_CONFIG)
+        .file("main.rs", "// Copyright Foo\n// All rights reserved")
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(0);
+}
+
+#[test]
+fn fix_does_not_insert_trailing_newlines_of_license_texts() {
+    let project = Project::new(TRAILING_NEWLINE_CONFIG);
+    project
+        .file("main.rs", "fn main() {}\n")
+        .lizenz(&["fix", "main.rs"])
+        .assert_code(0);
+    assert_eq!(
+        project.read("main.rs"),
+        "// Copyright Foo\n// All rights reserved\nfn main() {}\n"
+    );
+}
+
+#[test]
+fn root_resolves_the_configuration_and_files() {
+    let project = Project::new(CONFIG);
+    project
+        .file("src/valid.rs", "// Copyright Foo\nfn main() {}\n")
+        .file("src/invalid.rs", "fn main() {}\n");
+    let scratch = tempfile::tempdir().expect("Could not create a directory");
+    let root = project.path().to_str().unwrap();
+
+    let output = project
+        .command(&["--root", root, "verify", "src/valid.rs"])
+        .current_dir(scratch.path())
+        .output()
+        .expect("Could not run lizenz");
+    assert_eq!(output.status.code(), Some(0), "{output:?}");
+
+    let output = project
+        .command(&["--root", root, "verify", "src/invalid.rs"])
+        .current_dir(scratch.path())
+        .output()
+        .expect("Could not run lizenz");
+    assert_eq!(output.status.code(), Some(1), "{output:?}");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("src/invalid.rs:"));
+}
+
+#[test]
+fn missing_roots_are_errors() {
+    Project::new(CONFIG)
+        .lizenz(&["--root", "missing", "verify"])
+        .assert_code(2)
+        .assert_output("Could not change into the root directory missing");
+}
+
+#[test]
+fn config_path_may_be_a_directory() {
+    let project = Project::new(CONFIG);
+    project
+        .file("other/lizenz.toml", "[license]\ntext = \"Copyright Bar\"\n")
+        .file(
+            "hidden/.lizenz.toml",
+            "[license]\ntext = \"Copyright Baz\"\n",
+        )
+        .file("main.rs", "// Copyright Bar\nfn main() {}\n");
+
+    project
+        .lizenz(&["--config-path", "other", "verify", "main.rs"])
+        .assert_code(0);
+    project
+        .lizenz(&["--config-path", "hidden", "verify", "main.rs"])
+        .assert_code(1);
+    project
+        .lizenz(&["--config-path", ".", "verify", "main.rs"])
+        .assert_code(1);
+}
+
+#[test]
+fn config_path_directories_without_a_configuration_are_errors() {
+    let project = Project::new(CONFIG);
+    project
+        .file("empty/main.rs", "fn main() {}\n")
+        .lizenz(&["--config-path", "empty", "verify", "main.rs"])
+        .assert_code(2)
+        .assert_output("empty/lizenz.toml");
+}
+
+#[test]
+fn syntax_errors_point_at_their_location() {
+    Project::new("[license]\ntext = \"Copyright Foo\n")
+        .lizenz(&["verify"])
+        .assert_code(2)
+        .assert_output("invalid basic string")
+        .assert_output("line 2, column 22");
+}
+
+#[test]
+fn invalid_values_point_at_their_location() {
+    Project::new("[license]\ntext = 5\n")
+        .lizenz(&["verify"])
+        .assert_code(2)
+        .assert_output("invalid type: integer `5`, expected a string")
+        .assert_output("line 2, column 8");
+}
+
+/// An organization wide configuration, overridden by the project's `lizenz.toml`
+const BASE_CONFIG: &str = r#"[license]
+text = "Copyright Org"
+verify_lines = 1
+
+[licenses]
+mit = "Licensed under MIT"
+"#;
+
+#[test]
+fn later_configurations_override_earlier_ones() {
+    let project = Project::new(
+        "[license]\ntext = \"Copyright Project\\nAll rights reserved\"\n\n[licenses]\napache = \"Licensed under Apache\"\n",
+    );
+    project.file("base/lizenz.toml", BASE_CONFIG).file(
+        "main.rs",
+        "// Copyright Project\n// Some rights reserved\nfn main() {}\n",
+    );
+
+    // The text of the override applies, while the base's verify_lines is kept
+    project
+        .lizenz(&["-c", "base", "-c", "lizenz.toml", "verify", "main.rs"])
+        .assert_code(0);
+    // Without the override, the base's text applies
+    project
+        .lizenz(&["-c", "base", "verify", "main.rs"])
+        .assert_code(1);
+    // The order decides which text applies
+    project
+        .lizenz(&["-c", "lizenz.toml", "-c", "base", "verify", "main.rs"])
+        .assert_code(1);
+}
+
+#[test]
+fn named_licenses_of_all_configurations_are_merged() {
+    let project = Project::new(
+        "[licenses]\napache = \"Licensed under Apache\"\n\n[languages.rust]\nfile_endings = [\"*.rs\"]\ncomments = [{ tree_sitter_name = \"line_comment\", comment_kind = { Single = \"//\" }, preferred = true }]\nlicense = \"mit\"\n",
+    );
+    project
+        .file("base/lizenz.toml", BASE_CONFIG)
+        .file("main.rs", "// Licensed under MIT\nfn main() {}\n")
+        .lizenz(&["-c", "base", "-c", "lizenz.toml", "verify", "main.rs"])
+        .assert_code(0);
+}
+
+const PROFILE_CONFIG: &str = r#"[license]
+text = "Copyright Local"
+
+[profiles.ci.license]
+text = "Copyright CI"
+
+[profiles.release]
+license = { text = "Copyright Release" }
+"#;
+
+#[test]
+fn profiles_select_their_license_text() {
+    let project = Project::new(PROFILE_CONFIG);
+    project
+        .file("local.rs", "// Copyright Local\nfn main() {}\n")
+        .file("ci.rs", "// Copyright CI\nfn main() {}\n")
+        .file("release.rs", "// Copyright Release\nfn main() {}\n");
+
+    for (profile, valid) in [
+        (None, "local.rs"),
+        (Some("ci"), "ci.rs"),
+        (Some("release"), "release.rs"),
+    ] {
+        let profile = profile.map(|profile| ["--profile", profile]);
+        for file in ["local.rs", "ci.rs", "release.rs"] {
+            let mut args = profile.iter().flatten().copied().collect::<Vec<_>>();
+            args.extend(["verify", file]);
+            project
+                .lizenz(&args)
+                .assert_code(if file == valid { 0 } else { 1 });
+        }
+    }
+}
+
+#[test]
+fn profiles_may_be_selected_by_the_environment() {
+    let project = Project::new(PROFILE_CONFIG);
+    project.file("ci.rs", "// Copyright CI\nfn main() {}\n");
+
+    let status = |profile: &str| {
+        project
+            .command(&["verify", "ci.rs"])
+            .env("LIZENZ_PROFILE", profile)
+            .output()
+            .expect("Could not run lizenz")
+            .status
+            .code()
+    };
+    assert_eq!(status("ci"), Some(0));
+    assert_eq!(status("release"), Some(1));
+    // The flag takes precedence over the environment
+    assert_eq!(
+        project
+            .command(&["--profile", "ci", "verify", "ci.rs"])
+            .env("LIZENZ_PROFILE", "release")
+            .output()
+            .expect("Could not run lizenz")
+            .status
+            .code(),
+        Some(0)
+    );
+}
+
+#[test]
+fn fix_inserts_the_license_text_of_the_profile() {
+    let project = Project::new(PROFILE_CONFIG);
+    project
+        .file("main.rs", "fn main() {}\n")
+        .lizenz(&["--profile", "ci", "fix", "main.rs"])
+        .assert_code(0);
+    assert_eq!(project.read("main.rs"), "// Copyright CI\nfn main() {}\n");
+}
+
+#[test]
+fn profiles_keep_the_unset_keys_of_the_base() {
+    let project = Project::new(
+        "[license]\ntext = \"Copyright Local\\nAll rights reserved\"\nverify_lines = 1\n\n[profiles.ci.license]\ntext = \"Copyright CI\\nAll rights reserved\"\n",
+    );
+    project
+        .file(
+            "main.rs",
+            "// Copyright CI\n// Some rights reserved\nfn main() {}\n",
+        )
+        .lizenz(&["--profile", "ci", "verify", "main.rs"])
+        .assert_code(0);
+}
+
+#[test]
+fn unknown_profiles_are_errors() {
+    Project::new(PROFILE_CONFIG)
+        .file("main.rs", "// Copyright Local\nfn main() {}\n")
+        .lizenz(&["--profile", "staging", "verify", "main.rs"])
+        .assert_code(2)
+        .assert_output("The profile staging is not configured");
+}
@@ -0,0 +1,53 @@
+// © Marcel Müller 2025, licensed under the EUPL
+
+use crate::CONFIG;
+use crate::Project;
+
+#[test]
+fn doctor_passes_a_working_setup() {
+    Project::new(CONFIG)
+        .lizenz(&["doctor"])
+        .assert_code(0)
+        .assert_output("[ok] The language rust has a grammar with its node kinds")
+        .assert_no_output("[FAIL]");
+}
+
+#[test]
+fn doctor_reports_a_broken_setup() {
+    Project::new(
+        r#"[license]
+text = ""
+
+[languages.rust]
+file_endings = ["*.rs"]
+comments = [{ tree_sitter_name = "line_comment", comment_kind = { Single = "//" }, preferred = true }]
+import_node_kind = "import_statement"
+comment_containers = ["module"]
+
+[languages.notebook]
+file_endings = ["*.ipynb"]
+notebook_language = "julia"
+comments = []
+
+[languages.python]
+file_endings = ["*.py", "*.rs"]
+comments = []
+"#,
+    )
+    .lizenz(&["doctor"])
+    .assert_code(1)
+    .assert_output("[FAIL] The license text is not empty")
+    .assert_output("[FAIL] All notebook languages exist")
+    .assert_output("The grammar has no node kinds named import_statement, module")
+    .assert_output("No grammar named python exists")
+    .assert_output("*.rs is used by python, rust");
+}
+
+#[test]
+fn doctor_skips_checks_of_an_invalid_configuration() {
+    Project::new("[license]\n")
+        .lizenz(&["doctor"])
+        .assert_code(1)
+        .assert_output("[FAIL] Configuration")
+        .assert_output("[skip] All notebook languages exist");
+}
@@ -0,0 +1,173 @@
+// © Marcel Müller 2025, licensed under the EUPL
+
+use crate::CONFIG;
+use crate::Project;
+use crate::config;
+
+#[test]
+fn valid_files_exit_with_zero() {
+    Project::new(CONFIG)
+        .file("main.rs", "// Copyright Foo\nfn main() {}\n")
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(0);
+}
+
+#[test]
+fn files_without_header_exit_with_one() {
+    Project::new(CONFIG)
+        .file("main.rs", "fn main() {}\n")
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(1)
+        .assert_output("main.rs");
+}
+
+#[test]
+fn invalid_configurations_exit_with_two() {
+    Project::new("[license\n")
+        .file("main.rs", "// Copyright Foo\nfn main() {}\n")
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(2);
+}
+
+#[test]
+fn missing_configurations_exit_with_two() {
+    let project = Project::new(CONFIG);
+    project
+        .file("main.rs", "// Copyright Foo\nfn main() {}\n")
+        .lizenz(&["--config-path", "missing.toml", "verify", "main.rs"])
+        .assert_code(2);
+}
+
+#[test]
+fn missing_grammars_exit_with_two() {
+    Project::new(&format!(
+        "{CONFIG}\n[languages.python]\nfile_endings = [\"*.py\"]\ncomments = [{{ tree_sitter_name = \"comment\", comment_kind = {{ Single = \"#\" }}, preferred = true }}]\n"
+    ))
+    .file("main.py", "# Copyright Foo\n")
+    .lizenz(&["verify", "main.py"])
+    .assert_code(2)
+    .assert_output("grammar");
+}
+
+#[test]
+fn the_help_documents_the_exit_codes() {
+    Project::new(CONFIG)
+        .lizenz(&["--help"])
+        .assert_code(0)
+        .assert_output("Exit codes:");
+}
+
+/// The exit codes of verifying a file without header and one with the wrong header
+fn fail_on_codes(fail_on: &str) -> (i32, i32) {
+    let project = Project::new(CONFIG);
+    project
+        .file("missing.rs", "fn main() {}\n")
+        .file("mismatch.rs", "// Copyright Bar\nfn main() {}\n");
+    let code = |file| project.lizenz(&["verify", "--fail-on", fail_on, file]).code;
+    (code("missing.rs"), code("mismatch.rs"))
+}
+
+#[test]
+fn fail_on_any_fails_all_invalid_files() {
+    assert_eq!(fail_on_codes("any"), (1, 1));
+}
+
+#[test]
+fn fail_on_missing_only_fails_files_without_header() {
+    assert_eq!(fail_on_codes("missing"), (1, 0));
+}
+
+#[test]
+fn fail_on_mismatch_only_fails_files_with_the_wrong_header() {
+    assert_eq!(fail_on_codes("mismatch"), (0, 1));
+}
+
+#[test]
+fn fail_on_only_reports_failing_files() {
+    Project::new(CONFIG)
+        .file("missing.rs", "fn main() {}\n")
+        .file("mismatch.rs", "// Copyright Bar\nfn main() {}\n")
+        .lizenz(&[
+            "verify",
+            "--fail-on",
+            "mismatch",
+            "missing.rs",
+            "mismatch.rs",
+        ])
+        .assert_code(1)
+        .assert_output("mismatch.rs: missing or incorrect license header")
+        .assert_no_output("missing.rs:");
+}
+
+#[test]
+fn fail_fast_stops_at_the_first_failing_file() {
+    let run = Project::new(CONFIG)
+        .file("a.rs", "// Copyright Foo\nfn a() {}\n")
+        .file("b.rs", "fn b() {}\n")
+        .file("c.rs", "fn c() {}\n")
+        .lizenz(&[
+            "--jobs",
+            "1",
+            "verify",
+            "--fail-fast",
+            "a.rs",
+            "b.rs",
+            "c.rs",
+        ]);
+    run.assert_code(1);
+    assert_eq!(run.stdout, "b.rs: missing or incorrect license header\n");
+}
+
+#[test]
+fn without_fail_fast_all_failing_files_are_reported() {
+    let run = Project::new(CONFIG)
+        .file("b.rs", "fn b() {}\n")
+        .file("c.rs", "fn c() {}\n")
+        .lizenz(&["--jobs", "1", "verify", "b.rs", "c.rs"]);
+    run.assert_code(1);
+    assert_eq!(
+        run.stdout,
+        "b.rs: missing or incorrect license header\nc.rs: missing or incorrect license header\n"
+    );
+}
+
+/// The exit code verifying a file with the given header, with a `max_edit_distance` of 2
+fn distance_code(header: &str, fail_on: &str) -> i32 {
+    Project::new(&config(
+        "text = \"Copyright Foo\"\nmax_edit_distance = 2",
+        "",
+    ))
+    .file("main.rs", &format!("// {header}\nfn main() {{}}\n"))
+    .lizenz(&["verify", "--fail-on", fail_on, "main.rs"])
+    .code
+}
+
+#[test]
+fn exact_headers_are_no_near_matches() {
+    assert_eq!(distance_code("Copyright Foo", "any"), 0);
+    assert_eq!(distance_code("Copyright Foo", "distant"), 0);
+}
+
+#[test]
+fn near_headers_only_fail_without_fail_on_distant() {
+    assert_eq!(distance_code("Copyrigth Foo", "any"), 1);
+    assert_eq!(distance_code("Copyrigth Foo", "distant"), 0);
+}
+
+#[test]
+fn distant_headers_always_fail() {
+    assert_eq!(distance_code("Licensed under MIT", "any"), 1);
+    assert_eq!(distance_code("Licensed under MIT", "distant"), 1);
+}
+
+#[test]
+fn near_matches_report_their_distance() {
+    Project::new(&config(
+        "text = \"Copyright Foo\"\nmax_edit_distance = 2",
+        "",
+    ))
+    .file("main.rs", "// Copyrigth Foo\nfn main() {}\n")
+    .lizenz(&["verify", "main.rs"])
+    .assert_code(1)
+    .assert_output("nearly matches, 2 edits from the expected one");
+}
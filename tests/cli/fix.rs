@@ -0,0 +1,384 @@
+// © Marcel Müller 2025, licensed under the EUPL
+
+use std::time::Duration;
+use std::time::SystemTime;
+
+use crate::CONFIG;
+use crate::Project;
+use crate::config;
+
+#[test]
+fn verify_fix_fixes_like_fix() {
+    let project = Project::new(CONFIG);
+    project
+        .file("main.rs", "fn main() {}\n")
+        .file("valid.rs", "// Copyright Foo\nfn valid() {}\n")
+        .lizenz(&["verify", "--fix", "main.rs", "valid.rs"])
+        .assert_code(0);
+
+    assert_eq!(project.read("main.rs"), "// Copyright Foo\nfn main() {}\n");
+    assert_eq!(
+        project.read("valid.rs"),
+        "// Copyright Foo\nfn valid() {}\n"
+    );
+    project
+        .lizenz(&["verify", "main.rs", "valid.rs"])
+        .assert_code(0);
+}
+
+#[test]
+fn diff_output_writes_an_applicable_patch() {
+    let project = Project::new(CONFIG);
+    project
+        .file("src/main.rs", "fn main() {}\n")
+        .file("src/valid.rs", "// Copyright Foo\nfn valid() {}\n")
+        .commit_all();
+
+    project
+        .lizenz(&[
+            "fix",
+            "--diff-output",
+            "fix.patch",
+            "src/main.rs",
+            "src/valid.rs",
+        ])
+        .assert_code(0);
+    assert_eq!(project.read("src/main.rs"), "fn main() {}\n");
+    let patch = project.read("fix.patch");
+    assert!(
+        patch.contains("--- a/src/main.rs\n+++ b/src/main.rs\n"),
+        "{patch}"
+    );
+    assert!(!patch.contains("valid.rs"), "{patch}");
+
+    project.git(&["apply", "fix.patch"]);
+    assert_eq!(
+        project.read("src/main.rs"),
+        "// Copyright Foo\nfn main() {}\n"
+    );
+}
+
+#[test]
+fn diff_output_paths_are_relative_to_the_repository() {
+    let project = Project::new(CONFIG);
+    project.file("src/main.rs", "fn main() {}\n").commit_all();
+
+    project
+        .command(&[
+            "--config-path",
+            "../lizenz.toml",
+            "fix",
+            "--diff-output",
+            "../fix.patch",
+            "main.rs",
+        ])
+        .current_dir(project.path().join("src"))
+        .status()
+        .expect("Could not run lizenz");
+
+    let patch = project.read("fix.patch");
+    assert!(
+        patch.contains("--- a/src/main.rs\n+++ b/src/main.rs\n"),
+        "{patch}"
+    );
+    project.git(&["apply", "fix.patch"]);
+    assert_eq!(
+        project.read("src/main.rs"),
+        "// Copyright Foo\nfn main() {}\n"
+    );
+}
+
+#[test]
+fn create_writes_a_new_file_that_verifies() {
+    let project = Project::new(CONFIG);
+    project
+        .lizenz(&["fix", "--create", "new.rs", "--lang", "rust"])
+        .assert_code(0);
+
+    assert_eq!(project.read("new.rs"), "// Copyright Foo\n");
+    project.lizenz(&["verify", "new.rs"]).assert_code(0);
+}
+
+#[test]
+fn create_detects_the_language_from_the_name() {
+    let project = Project::new(CONFIG);
+    project
+        .lizenz(&["fix", "--create", "run.sh"])
+        .assert_code(0);
+
+    assert_eq!(project.read("run.sh"), "# Copyright Foo\n");
+}
+
+#[test]
+fn create_refuses_to_overwrite_without_force() {
+    let project = Project::new(CONFIG);
+    project
+        .file("main.rs", "fn main() {}\n")
+        .lizenz(&["fix", "--create", "main.rs"])
+        .assert_code(2)
+        .assert_output("already exists");
+    assert_eq!(project.read("main.rs"), "fn main() {}\n");
+
+    project
+        .lizenz(&["fix", "--create", "main.rs", "--force"])
+        .assert_code(0);
+    assert_eq!(project.read("main.rs"), "// Copyright Foo\n");
+}
+
+/// A project with `link.rs` linking to `target.rs`, which has no header
+#[cfg(unix)]
+fn project_with_symlink() -> Project {
+    let project = Project::new(CONFIG);
+    project.file("target.rs", "fn main() {}\n");
+    std::os::unix::fs::symlink("target.rs", project.path().join("link.rs"))
+        .expect("Could not create the symlink");
+    project
+}
+
+#[cfg(unix)]
+#[test]
+fn verify_checks_the_target_of_symlinks() {
+    let project = project_with_symlink();
+    project.lizenz(&["verify", "link.rs"]).assert_code(1);
+    project.file("target.rs", "// Copyright Foo\nfn main() {}\n");
+    project.lizenz(&["verify", "link.rs"]).assert_code(0);
+    project
+        .lizenz(&["verify", "--no-follow-symlinks", "link.rs"])
+        .assert_code(0);
+}
+
+#[cfg(unix)]
+#[test]
+fn fix_fixes_the_target_of_symlinks() {
+    let project = project_with_symlink();
+    project
+        .lizenz(&["fix", "link.rs"])
+        .assert_code(0)
+        .assert_output("fixing its target");
+
+    assert_eq!(
+        project.read("target.rs"),
+        "// Copyright Foo\nfn main() {}\n"
+    );
+    assert!(project.path().join("link.rs").is_symlink());
+}
+
+#[cfg(unix)]
+#[test]
+fn fix_does_not_follow_symlinks_if_told_so() {
+    let project = project_with_symlink();
+    project
+        .lizenz(&["fix", "--no-follow-symlinks", "link.rs"])
+        .assert_code(1)
+        .assert_output("Not fixing link.rs, as it is a symbolic link");
+
+    assert_eq!(project.read("target.rs"), "fn main() {}\n");
+}
+
+#[test]
+fn files_of_only_a_shebang_and_header_verify() {
+    Project::new(CONFIG)
+        .file("run.sh", "#!/bin/sh\n# Copyright Foo\n")
+        .lizenz(&["verify", "run.sh"])
+        .assert_code(0);
+}
+
+#[test]
+fn fix_inserts_the_header_below_a_lone_shebang_once() {
+    let project = Project::new(CONFIG);
+    project
+        .file("run.sh", "#!/bin/sh\n")
+        .lizenz(&["fix", "run.sh"])
+        .assert_code(0);
+    assert_eq!(project.read("run.sh"), "#!/bin/sh\n# Copyright Foo\n");
+
+    project.lizenz(&["fix", "run.sh"]).assert_code(0);
+    assert_eq!(project.read("run.sh"), "#!/bin/sh\n# Copyright Foo\n");
+    project.lizenz(&["verify", "run.sh"]).assert_code(0);
+}
+
+#[cfg(unix)]
+#[test]
+fn fix_keeps_the_mode_of_files() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let project = Project::new(CONFIG);
+    project
+        .file("run.sh", "#!/bin/sh\necho hello\n")
+        .file("secret.rs", "fn main() {}\n");
+    for (file, mode) in [("run.sh", 0o755), ("secret.rs", 0o600)] {
+        std::fs::set_permissions(
+            project.path().join(file),
+            std::fs::Permissions::from_mode(mode),
+        )
+        .expect("Could not set the mode");
+    }
+
+    project
+        .lizenz(&["fix", "run.sh", "secret.rs"])
+        .assert_code(0);
+
+    for (file, mode) in [("run.sh", 0o755), ("secret.rs", 0o600)] {
+        let metadata = std::fs::metadata(project.path().join(file)).unwrap();
+        assert_eq!(metadata.permissions().mode() & 0o777, mode, "{file}");
+    }
+    assert_eq!(
+        project.read("run.sh"),
+        "#!/bin/sh\n# Copyright Foo\necho hello\n"
+    );
+}
+
+#[test]
+fn fix_keeps_one_leading_blank_line_if_told_so() {
+    let project = Project::new(&config(
+        "text = \"Copyright Foo\"\nkeep_leading_blank = true",
+        "",
+    ));
+    project
+        .file("one.rs", "\nfn main() {}\n")
+        .file("many.rs", "\n\n  \nfn main() {}\n")
+        .file("none.rs", "fn main() {}\n")
+        .lizenz(&["fix", "one.rs", "many.rs", "none.rs"])
+        .assert_code(0);
+
+    assert_eq!(project.read("one.rs"), "// Copyright Foo\n\nfn main() {}\n");
+    assert_eq!(
+        project.read("many.rs"),
+        "// Copyright Foo\n\nfn main() {}\n"
+    );
+    assert_eq!(project.read("none.rs"), "// Copyright Foo\nfn main() {}\n");
+}
+
+#[test]
+fn fix_leaves_leading_blank_lines_alone_by_default() {
+    let project = Project::new(CONFIG);
+    project
+        .file("main.rs", "\n\nfn main() {}\n")
+        .lizenz(&["fix", "main.rs"])
+        .assert_code(0);
+    assert_eq!(
+        project.read("main.rs"),
+        "// Copyright Foo\n\n\nfn main() {}\n"
+    );
+}
+
+/// A project whose Rust files are formatted by the given shell script after fixing them
+#[cfg(unix)]
+fn formatted_project(script: &str) -> Project {
+    let project = Project::new(&config(
+        "text = \"Copyright Foo\"",
+        r#"format_command = ["sh", "format.sh"]"#,
+    ));
+    project.file("format.sh", script);
+    project
+}
+
+#[cfg(unix)]
+#[test]
+fn fixed_files_are_formatted() {
+    let project = formatted_project("echo '// Formatted' >> \"$1\"\n");
+    project
+        .file("fixed.rs", "fn main() {}\n")
+        .file("valid.rs", "// Copyright Foo\nfn main() {}\n")
+        .lizenz(&["fix", "fixed.rs", "valid.rs"])
+        .assert_code(0);
+
+    assert_eq!(
+        project.read("fixed.rs"),
+        "// Copyright Foo\nfn main() {}\n// Formatted\n"
+    );
+    assert_eq!(project.read("valid.rs"), "// Copyright Foo\nfn main() {}\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn failing_formatters_keep_the_fix() {
+    let project = formatted_project("echo 'cannot format' >&2\nexit 1\n");
+    project
+        .file("main.rs", "fn main() {}\n")
+        .lizenz(&["fix", "main.rs"])
+        .assert_code(0)
+        .assert_output("Formatting main.rs with sh failed with exit status: 1, keeping it unformatted: cannot format");
+    assert_eq!(project.read("main.rs"), "// Copyright Foo\nfn main() {}\n");
+}
+
+#[test]
+fn missing_formatters_keep_the_fix() {
+    let project = Project::new(&config(
+        "text = \"Copyright Foo\"",
+        r#"format_command = ["no-such-formatter"]"#,
+    ));
+    project
+        .file("main.rs", "fn main() {}\n")
+        .lizenz(&["fix", "main.rs"])
+        .assert_code(0)
+        .assert_output("Could not run the formatter no-such-formatter for main.rs");
+    assert_eq!(project.read("main.rs"), "// Copyright Foo\nfn main() {}\n");
+}
+
+/// Sets the modification time of the project's file to a fixed time in the past, returning it
+fn backdate(project: &Project, path: &str) -> SystemTime {
+    let time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+    std::fs::File::options()
+        .write(true)
+        .open(project.path().join(path))
+        .and_then(|file| file.set_modified(time))
+        .expect("Could not set the modification time");
+    time
+}
+
+fn modified(project: &Project, path: &str) -> SystemTime {
+    std::fs::metadata(project.path().join(path))
+        .and_then(|metadata| metadata.modified())
+        .expect("Could not read the modification time")
+}
+
+#[test]
+fn fix_does_not_touch_valid_files() {
+    let project = Project::new(CONFIG);
+    project
+        .file("valid.rs", "// Copyright Foo\nfn main() {}\n")
+        .file("invalid.rs", "fn main() {}\n");
+    let valid = backdate(&project, "valid.rs");
+    let invalid = backdate(&project, "invalid.rs");
+
+    project
+        .lizenz(&["fix", "valid.rs", "invalid.rs"])
+        .assert_code(0);
+
+    assert_eq!(modified(&project, "valid.rs"), valid);
+    assert_ne!(modified(&project, "invalid.rs"), invalid);
+    assert_eq!(project.read("valid.rs"), "// Copyright Foo\nfn main() {}\n");
+}
+
+#[test]
+fn updating_current_years_does_not_touch_the_file() {
+    let project = Project::new("[license]\ntext = \"Copyright {{year}} Foo\"\n");
+    project.file("main.rs", "// Copyright 2020-2025 Foo\nfn main() {}\n");
+    let current = backdate(&project, "main.rs");
+
+    project
+        .lizenz_with_log(&["fix", "--update-years", "main.rs"], "lizenz=debug")
+        .assert_code(0)
+        .assert_log("main.rs is unchanged by fixing it, not writing it")
+        .assert_no_log("WARN");
+
+    assert_eq!(modified(&project, "main.rs"), current);
+    assert_eq!(
+        project.read("main.rs"),
+        "// Copyright 2020-2025 Foo\nfn main() {}\n"
+    );
+}
+
+#[test]
+fn fixing_twice_only_writes_once() {
+    let project = Project::new(CONFIG);
+    project
+        .file("main.rs", "fn main() {}\n")
+        .lizenz(&["fix", "main.rs"])
+        .assert_code(0);
+    let fixed = backdate(&project, "main.rs");
+
+    project.lizenz(&["fix", "main.rs"]).assert_code(0);
+    assert_eq!(modified(&project, "main.rs"), fixed);
+}
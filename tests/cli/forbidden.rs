@@ -0,0 +1,72 @@
+// © Marcel Müller 2025, licensed under the EUPL
+
+use crate::Project;
+
+const FORBIDDEN_CONFIG: &str =
+    "[license]\ntext = \"Copyright Foo\"\nforbidden = [\"Old Corp\", \"GPL-2.0\"]\n";
+
+#[test]
+fn correct_licenses_with_a_forbidden_phrase_fail() {
+    Project::new(FORBIDDEN_CONFIG)
+        .file(
+            "main.rs",
+            "// Copyright Foo\n// Formerly Old Corp\nfn main() {}\n",
+        )
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(1)
+        .assert_output("main.rs: license header contains the forbidden phrase `Old Corp`");
+}
+
+#[test]
+fn forbidden_phrases_are_found_across_lines() {
+    Project::new(FORBIDDEN_CONFIG)
+        .file(
+            "main.rs",
+            "// Copyright Foo\n// Formerly Old\n// Corp\nfn main() {}\n",
+        )
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(1)
+        .assert_output("forbidden phrase `Old Corp`");
+}
+
+#[test]
+fn forbidden_phrases_are_found_in_the_whole_leading_comment_block() {
+    Project::new(FORBIDDEN_CONFIG)
+        .file(
+            "main.rs",
+            "// Copyright Foo\n\n/* Licensed under GPL-2.0 */\nfn main() {}\n",
+        )
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(1)
+        .assert_output("forbidden phrase `GPL-2.0`");
+}
+
+#[test]
+fn comments_after_code_may_contain_forbidden_phrases() {
+    let project = Project::new(FORBIDDEN_CONFIG);
+    project
+        .file(
+            "main.rs",
+            "// Copyright Foo\nfn main() {}\n\n/// Ported from Old Corp's parser\nfn parse() {}\n",
+        )
+        .file(
+            "broken.rs",
+            "// Copyright Foo\nfn main(\n// Ported from Old Corp's parser\n",
+        );
+
+    project.lizenz(&["verify", "main.rs"]).assert_code(0);
+    // Syntax errors wrapping the code do not make its comments leading ones
+    project.lizenz(&["verify", "broken.rs"]).assert_code(0);
+}
+
+#[test]
+fn fix_does_not_remove_forbidden_phrases() {
+    let content = "// Copyright Foo\n// Formerly Old Corp\nfn main() {}\n";
+    let project = Project::new(FORBIDDEN_CONFIG);
+    project
+        .file("main.rs", content)
+        .lizenz(&["fix", "main.rs"])
+        .assert_code(1)
+        .assert_output("Not fixing main.rs, as forbidden phrases cannot be removed");
+    assert_eq!(project.read("main.rs"), content);
+}
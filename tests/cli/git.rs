@@ -0,0 +1,174 @@
+// © Marcel Müller 2025, licensed under the EUPL
+
+use crate::Project;
+
+const GIT_AUTHOR_LICENSE: &str = "[license]\ntext = \"Copyright {{git_author}}\"\n";
+
+#[test]
+fn git_author_is_the_configured_user_name() {
+    let project = Project::new(GIT_AUTHOR_LICENSE);
+    project
+        .git(&["init", "-q"])
+        .git(&["config", "user.name", "Jane Doe"])
+        .file("valid.rs", "// Copyright Jane Doe\nfn main() {}\n")
+        .file("other.rs", "// Copyright John Doe\nfn main() {}\n")
+        .file("missing.rs", "fn main() {}\n");
+
+    project.lizenz(&["verify", "valid.rs"]).assert_code(0);
+    project.lizenz(&["verify", "other.rs"]).assert_code(1);
+    project.lizenz(&["fix", "missing.rs"]).assert_code(0);
+    assert_eq!(
+        project.read("missing.rs"),
+        "// Copyright Jane Doe\nfn main() {}\n"
+    );
+}
+
+#[test]
+fn git_author_is_matched_literally_in_regex_mode() {
+    let project =
+        Project::new("[license]\ntext = \"Copyright {{git_author}}\"\nmatch_mode = \"regex\"\n");
+    project
+        .git(&["init", "-q"])
+        .git(&["config", "user.name", "J. Doe (ACME)"])
+        .file("valid.rs", "// Copyright J. Doe (ACME)\nfn main() {}\n")
+        .file("other.rs", "// Copyright JX Doe ACME\nfn main() {}\n");
+
+    project.lizenz(&["verify", "valid.rs"]).assert_code(0);
+    project.lizenz(&["verify", "other.rs"]).assert_code(1);
+}
+
+#[test]
+fn git_author_without_user_name_is_an_error() {
+    Project::new(GIT_AUTHOR_LICENSE)
+        .git(&["init", "-q"])
+        .file("main.rs", "// Copyright Jane Doe\nfn main() {}\n")
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(2)
+        .assert_output("no user.name configured");
+}
+
+/// A license whose year has to be the year git reports for the file
+fn year_from_git(which: &str) -> String {
+    format!("[license]\ntext = \"Copyright {{{{year}}}} Foo\"\nyear_from_git = \"{which}\"\n")
+}
+
+/// A project whose `main.rs` was added in 2020 and last changed in 2023
+fn project_with_history(which: &str, header: &str) -> Project {
+    let project = Project::new(&year_from_git(which));
+    project
+        .git(&["init", "-q", "-b", "main"])
+        .file("main.rs", &format!("// {header}\nfn main() {{}}\n"))
+        .git(&["add", "main.rs"])
+        .git(&[
+            "commit",
+            "-q",
+            "-m",
+            "Add",
+            "--date",
+            "2020-05-01T12:00:00Z",
+        ])
+        .file(
+            "main.rs",
+            &format!("// {header}\nfn main() {{ loop {{}} }}\n"),
+        )
+        .git(&["commit", "-q", "-am", "Change"]);
+    project
+}
+
+#[test]
+fn year_from_git_last_modified_requires_the_year_of_the_last_commit() {
+    project_with_history("last_modified", "Copyright 2023 Foo")
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(0);
+    project_with_history("last_modified", "Copyright 2020 Foo")
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(1)
+        .assert_output("copyright year 2020 is not the year 2023 from git");
+}
+
+#[test]
+fn year_from_git_created_requires_the_year_of_the_first_commit() {
+    project_with_history("created", "Copyright 2020 Foo")
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(0);
+    project_with_history("created", "Copyright 2023 Foo")
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(1)
+        .assert_output("copyright year 2023 is not the year 2020 from git");
+}
+
+#[test]
+fn year_from_git_checks_the_end_of_ranges() {
+    let project = Project::new(&year_from_git("last_modified"));
+    project
+        .git(&["init", "-q", "-b", "main"])
+        .file("main.rs", "// Copyright 2020-2025 Foo\nfn main() {}\n")
+        .git(&["add", "main.rs"])
+        .git(&[
+            "commit",
+            "-q",
+            "-m",
+            "Add",
+            "--date",
+            "2025-05-01T12:00:00Z",
+        ])
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(0);
+}
+
+#[test]
+fn year_from_git_skips_files_without_commits() {
+    let project = Project::new(&year_from_git("last_modified"));
+    project
+        .git(&["init", "-q", "-b", "main"])
+        .file("new.rs", "// Copyright 2019 Foo\nfn main() {}\n");
+    project.lizenz(&["verify", "new.rs"]).assert_code(0);
+
+    project
+        .file("old.rs", "// Copyright 2023 Foo\nfn main() {}\n")
+        .git(&["add", "old.rs"])
+        .git(&["commit", "-q", "-m", "Add"]);
+    project.lizenz(&["verify", "new.rs"]).assert_code(0);
+}
+
+#[test]
+fn since_commit_checks_added_modified_and_renamed_files() {
+    let project = Project::new("[license]\ntext = \"Copyright Foo\"\n");
+    project
+        .file("modified.rs", "fn modified() {}\n")
+        .file("renamed.rs", "fn renamed() {}\n")
+        .file("deleted.rs", "fn deleted() {}\n")
+        .file("unchanged.rs", "fn unchanged() {}\n")
+        .commit_all()
+        .git(&["tag", "base"])
+        .file("added.rs", "fn added() {}\n")
+        .file("modified.rs", "fn modified() { loop {} }\n")
+        .git(&["mv", "renamed.rs", "moved.rs"])
+        .git(&["rm", "-q", "deleted.rs"])
+        .commit_all();
+
+    let run = project.lizenz(&["verify", "--since-commit", "base"]);
+    run.assert_code(1)
+        .assert_output("added.rs:")
+        .assert_output("modified.rs:")
+        .assert_output("moved.rs:")
+        .assert_no_output("renamed.rs")
+        .assert_no_output("deleted.rs")
+        .assert_no_output("unchanged.rs");
+}
+
+#[test]
+fn since_commit_passes_if_all_changed_files_have_headers() {
+    let project = Project::new("[license]\ntext = \"Copyright Foo\"\n");
+    project
+        .file("old.rs", "fn old() {}\n")
+        .commit_all()
+        .git(&["tag", "base"])
+        .file("new.rs", "// Copyright Foo\nfn new() {}\n")
+        .git(&["rm", "-q", "old.rs"])
+        .commit_all();
+
+    project
+        .lizenz(&["verify", "--since-commit", "base"])
+        .assert_code(0);
+}
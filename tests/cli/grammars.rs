@@ -0,0 +1,301 @@
+// © Marcel Müller 2025, licensed under the EUPL
+
+use std::path::Path;
+use std::process::Command;
+
+use crate::CONFIG;
+use crate::Project;
+
+/// Compiles a fake grammar named `name`, whose language only consists of its ABI version
+fn fake_grammar(directory: &Path, name: &str, abi_version: u32) {
+    std::fs::create_dir_all(directory).expect("Could not create the grammar directory");
+    let source = directory.join(format!("{name}.c"));
+    std::fs::write(
+        &source,
+        format!(
+            "static unsigned int language[256] = {{ {abi_version} }};\nconst void *tree_sitter_{name}(void) {{ return language; }}\n"
+        ),
+    )
+    .expect("Could not write the grammar");
+
+    let status = Command::new("cc")
+        .args(["-shared", "-fPIC", "-o"])
+        .arg(directory.join(format!("{name}.so")))
+        .arg(&source)
+        .status()
+        .expect("Could not run cc");
+    assert!(status.success(), "Could not compile the fake grammar");
+    std::fs::remove_file(source).expect("Could not remove the grammar source");
+}
+
+/// Compiles a fake grammar of ABI version 15 named `name`, which calls itself `internal_name`
+///
+/// The struct mirrors the layout of `TSLanguage` up to its name.
+fn misnamed_grammar(directory: &Path, name: &str, internal_name: &str) {
+    std::fs::create_dir_all(directory).expect("Could not create the grammar directory");
+    let source = directory.join(format!("{name}.c"));
+    std::fs::write(
+        &source,
+        format!(
+            r#"struct language {{
+    unsigned int counts[9];
+    unsigned short max_alias_sequence_length;
+    const void *tables[13];
+    const void *lex_fns[2];
+    unsigned short keyword_capture_token;
+    const void *external_scanner[7];
+    const void *primary_state_ids;
+    const char *name;
+}};
+static struct language language = {{ .counts = {{ 15 }}, .name = "{internal_name}" }};
+const void *tree_sitter_{name}(void) {{ return &language; }}
+"#
+        ),
+    )
+    .expect("Could not write the grammar");
+
+    let status = Command::new("cc")
+        .args(["-shared", "-fPIC", "-o"])
+        .arg(directory.join(format!("{name}.so")))
+        .arg(&source)
+        .status()
+        .expect("Could not run cc");
+    assert!(status.success(), "Could not compile the fake grammar");
+    std::fs::remove_file(source).expect("Could not remove the grammar source");
+}
+
+#[test]
+fn grammars_calling_themselves_differently_are_pointed_out() {
+    let project = Project::new(&format!(
+        "{CONFIG}\n[languages.fancy]\nfile_endings = [\"*.fancy\"]\ncomments = []\n"
+    ));
+    misnamed_grammar(&project.path().join("grammars"), "fancy_lang", "fancy");
+
+    project
+        .file("main.fancy", "fancy\n")
+        .lizenz(&["--tree-sitter-grammars", "grammars", "verify", "main.fancy"])
+        .assert_code(2)
+        .assert_output("calls itself fancy, but is used for the language fancy_lang after its file name, rename it to fancy.so")
+        .assert_output("the grammar fancy_lang calls itself fancy and might have to be renamed");
+}
+
+#[test]
+fn unsupported_abi_versions_are_named() {
+    let project = Project::new(CONFIG);
+    let grammars = project.path().join("grammars");
+    fake_grammar(&grammars, "fake", 99);
+
+    project
+        .file("main.rs", "// Copyright Foo\nfn main() {}\n")
+        .lizenz(&["--tree-sitter-grammars", "grammars", "verify", "main.rs"])
+        .assert_code(2)
+        .assert_output("The grammar fake uses ABI version 99");
+}
+
+#[test]
+fn loaded_abi_versions_are_logged() {
+    let project = Project::new(CONFIG);
+    let grammars = project.path().join("grammars");
+    fake_grammar(&grammars, "fake", 14);
+
+    project
+        .file("main.rs", "// Copyright Foo\nfn main() {}\n")
+        .lizenz_with_log(
+            &["--tree-sitter-grammars", "grammars", "verify", "main.rs"],
+            "lizenz=debug",
+        )
+        .assert_log("Loaded grammar fake with ABI version 14");
+}
+
+/// A project with a grammar directory that lizenz fails to load in each of the given locations,
+/// relative to the project, and empty grammar directories in all others
+fn project_with_broken_grammars(broken: &[&str]) -> Project {
+    let project = Project::new(CONFIG);
+    project.file("main.rs", "// Copyright Foo\nfn main() {}\n");
+    for directory in ["flag", "env", ".data/lizenz/grammars"] {
+        let directory = project.path().join(directory);
+        std::fs::create_dir_all(&directory).expect("Could not create the grammar directory");
+        if broken.iter().any(|broken| directory.ends_with(broken)) {
+            fake_grammar(&directory, "broken", 99);
+        }
+    }
+    project
+}
+
+#[test]
+fn the_data_directory_is_used_by_default() {
+    project_with_broken_grammars(&[".data/lizenz/grammars"])
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(2)
+        .assert_output("The grammar broken");
+}
+
+/// Runs `verify` in the project with `TREE_SITTER_GRAMMARS` set to its `env` directory
+fn verify_with_environment_variable(project: &Project, args: &[&str]) -> Option<i32> {
+    project
+        .command(&[args, &["verify", "main.rs"]].concat())
+        .env("TREE_SITTER_GRAMMARS", project.path().join("env"))
+        .output()
+        .expect("Could not run lizenz")
+        .status
+        .code()
+}
+
+#[test]
+fn the_environment_variable_takes_precedence_over_the_data_directory() {
+    let project = project_with_broken_grammars(&[".data/lizenz/grammars"]);
+    assert_eq!(verify_with_environment_variable(&project, &[]), Some(0));
+
+    let project = project_with_broken_grammars(&["env"]);
+    assert_eq!(verify_with_environment_variable(&project, &[]), Some(2));
+}
+
+#[test]
+fn the_option_takes_precedence_over_the_environment_variable() {
+    let project = project_with_broken_grammars(&["env", ".data/lizenz/grammars"]);
+    assert_eq!(
+        verify_with_environment_variable(&project, &["--tree-sitter-grammars", "flag"]),
+        Some(0)
+    );
+}
+
+#[test]
+fn built_in_grammars_need_no_grammar_directory() {
+    Project::new(CONFIG)
+        .file("main.rs", "// Copyright Foo\nfn main() {}\n")
+        .lizenz_with_log(&["verify", "main.rs"], "lizenz=debug")
+        .assert_code(0)
+        .assert_log("Using the built-in grammar rust")
+        .assert_log("No grammar directory found, only using the built-in grammars");
+}
+
+#[test]
+fn built_in_grammars_shadow_loaded_ones() {
+    let project = Project::new(CONFIG);
+    // A broken grammar, which would fail verification if it was used
+    fake_grammar(&project.path().join("grammars"), "rust", 14);
+
+    project
+        .file("main.rs", "// Copyright Foo\nfn main() {}\n")
+        .lizenz_with_log(
+            &["--tree-sitter-grammars", "grammars", "verify", "main.rs"],
+            "lizenz=debug",
+        )
+        .assert_code(0)
+        .assert_log("Not using the grammar rust from grammars, as it is shadowed");
+}
+
+#[test]
+fn missing_grammar_directories_are_named() {
+    Project::new(CONFIG)
+        .file("main.rs", "// Copyright Foo\nfn main() {}\n")
+        .lizenz(&["--tree-sitter-grammars", "missing", "verify", "main.rs"])
+        .assert_code(2)
+        .assert_output("Could not read the grammar directory missing");
+}
+
+#[test]
+fn earlier_grammar_directories_take_precedence() {
+    let project = Project::new(CONFIG);
+    fake_grammar(&project.path().join("first"), "fake", 14);
+    fake_grammar(&project.path().join("second"), "fake", 14);
+
+    project
+        .file("main.rs", "// Copyright Foo\nfn main() {}\n")
+        .lizenz_with_log(
+            &[
+                "--tree-sitter-grammars",
+                "first",
+                "--tree-sitter-grammars",
+                "second",
+                "verify",
+                "main.rs",
+            ],
+            "lizenz=debug",
+        )
+        .assert_code(0)
+        .assert_log("Not using the grammar fake from second, as it is shadowed")
+        .assert_no_log("from first, as it is shadowed");
+}
+
+#[test]
+fn hyphenated_grammar_names_become_underscores() {
+    let project = Project::new(&format!(
+        "{CONFIG}\n[languages.c_sharp]\nfile_endings = [\"*.cs\"]\ncomments = []\n"
+    ));
+    let grammars = project.path().join("grammars");
+    fake_grammar(&grammars, "c_sharp", 14);
+    std::fs::rename(grammars.join("c_sharp.so"), grammars.join("c-sharp.so"))
+        .expect("Could not rename the grammar");
+
+    let run = project.lizenz_with_log(
+        &["--tree-sitter-grammars", "grammars", "doctor"],
+        "lizenz=debug",
+    );
+    run.assert_code(0)
+        .assert_log("Loaded grammar c_sharp with ABI version 14");
+    assert!(
+        run.stdout
+            .contains("[ok] The language c_sharp has a grammar with its node kinds"),
+        "{run:#?}"
+    );
+}
+
+/// A configuration with a Lisp language, for which no grammar exists
+fn lisp_config() -> String {
+    format!(
+        "{CONFIG}\n[languages.lisp]\nfile_endings = [\"*.lisp\"]\ncomments = [{{ tree_sitter_name = \"comment\", comment_kind = {{ Single = \";\" }}, preferred = true }}]\n"
+    )
+}
+
+#[test]
+fn languages_without_a_grammar_fail_the_run() {
+    Project::new(&lisp_config())
+        .file("main.lisp", "; Copyright Foo\n(main)\n")
+        .file("main.rs", "// Copyright Foo\nfn main() {}\n")
+        .lizenz(&["verify", "main.lisp", "main.rs"])
+        .assert_code(2)
+        .assert_output("Found language lisp but no tree-sitter grammar exists for it");
+}
+
+#[test]
+fn ignore_missing_grammar_skips_languages_without_a_grammar() {
+    let project = Project::new(&lisp_config());
+    project
+        .file("main.lisp", "(main)\n")
+        .file("valid.rs", "// Copyright Foo\nfn main() {}\n")
+        .file("invalid.rs", "fn main() {}\n");
+
+    project
+        .lizenz(&[
+            "verify",
+            "--ignore-missing-grammar",
+            "main.lisp",
+            "valid.rs",
+        ])
+        .assert_code(0)
+        .assert_output("Skipping main.lisp, as no grammar exists for its language lisp");
+    // The other languages are still verified
+    project
+        .lizenz(&[
+            "verify",
+            "--ignore-missing-grammar",
+            "main.lisp",
+            "invalid.rs",
+        ])
+        .assert_code(1)
+        .assert_output("invalid.rs: missing or incorrect license header")
+        .assert_no_output("main.lisp: missing");
+}
+
+#[test]
+fn ignore_missing_grammar_leaves_files_unfixed() {
+    let project = Project::new(&lisp_config());
+    project
+        .file("main.lisp", "(main)\n")
+        .file("main.rs", "fn main() {}\n")
+        .lizenz(&["fix", "--ignore-missing-grammar", "main.lisp", "main.rs"])
+        .assert_code(0);
+    assert_eq!(project.read("main.lisp"), "(main)\n");
+    assert_eq!(project.read("main.rs"), "// Copyright Foo\nfn main() {}\n");
+}
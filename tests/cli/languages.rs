@@ -0,0 +1,673 @@
+// © Marcel Müller 2025, licensed under the EUPL
+
+use crate::CONFIG;
+use crate::Project;
+use crate::config;
+
+/// TOML, whose comments following a table header belong to the table
+fn toml_config(options: &str) -> String {
+    format!(
+        r##"{CONFIG}
+[languages.toml]
+file_endings = ["*.toml"]
+comments = [{{ tree_sitter_name = "comment", comment_kind = {{ Single = "#" }}, preferred = true }}]
+{options}
+"##
+    )
+}
+
+#[test]
+fn comments_in_containers_are_searched() {
+    let file = "[package]\n# Copyright Foo\nname = \"foo\"\n";
+
+    Project::new(&toml_config(r#"comment_containers = ["table"]"#))
+        .file("Cargo.toml", file)
+        .lizenz(&["verify", "Cargo.toml"])
+        .assert_code(0);
+    Project::new(&toml_config(""))
+        .file("Cargo.toml", file)
+        .lizenz(&["verify", "Cargo.toml"])
+        .assert_code(1);
+}
+
+#[test]
+fn top_level_comments_take_precedence_over_containers() {
+    Project::new(&toml_config(r#"comment_containers = ["table"]"#))
+        .file(
+            "Cargo.toml",
+            "# Something else\n[package]\n# Copyright Foo\nname = \"foo\"\n",
+        )
+        .lizenz(&["verify", "Cargo.toml"])
+        .assert_code(1);
+}
+
+#[test]
+fn comments_after_code_in_containers_are_not_searched() {
+    let project = Project::new(&toml_config(r#"comment_containers = ["table"]"#));
+    project
+        .file(
+            "code_first.toml",
+            "[package]\nname = \"foo\"\n# Copyright Foo\n",
+        )
+        .file(
+            "later_table.toml",
+            "[package]\nname = \"foo\"\n\n[dependencies]\n# Copyright Foo\n",
+        )
+        .file(
+            "top_level_code.toml",
+            "name = \"foo\"\n\n[package]\n# Copyright Foo\n",
+        );
+
+    for file in ["code_first.toml", "later_table.toml", "top_level_code.toml"] {
+        project.lizenz(&["verify", file]).assert_code(1);
+    }
+}
+
+#[test]
+fn only_leading_containers_are_searched() {
+    let project = Project::new(&config(
+        "text = \"Copyright Foo\"",
+        "comment_containers = [\"function_item\"]",
+    ));
+    project
+        .file("first.rs", "fn main() // Copyright Foo\n{}\n")
+        .file(
+            "second.rs",
+            "fn main() {}\nfn other() // Copyright Foo\n{}\n",
+        )
+        .file(
+            "code_before.rs",
+            "fn main() {\n    let x = 1;\n    // Copyright Foo\n}\n",
+        );
+
+    project.lizenz(&["verify", "first.rs"]).assert_code(0);
+    project.lizenz(&["verify", "second.rs"]).assert_code(1);
+    project.lizenz(&["verify", "code_before.rs"]).assert_code(1);
+}
+
+const NAMED_LICENSES: &str = r##"
+[license]
+text = "Copyright Default"
+
+[licenses]
+mit = "Licensed under MIT"
+apache = "Licensed under Apache"
+
+[languages.rust]
+file_endings = ["*.rs"]
+comments = [{ tree_sitter_name = "line_comment", comment_kind = { Single = "//" }, preferred = true }]
+license = "mit"
+
+[languages.toml]
+file_endings = ["*.toml"]
+comments = [{ tree_sitter_name = "comment", comment_kind = { Single = "#" }, preferred = true }]
+license = "apache"
+"##;
+
+#[test]
+fn languages_use_their_named_licenses() {
+    let project = Project::new(NAMED_LICENSES);
+    project
+        .file("main.rs", "// Licensed under MIT\nfn main() {}\n")
+        .file("Cargo.toml", "# Licensed under Apache\n[package]\n");
+    project
+        .lizenz(&["verify", "main.rs", "Cargo.toml"])
+        .assert_code(0);
+
+    project
+        .file("main.rs", "// Licensed under Apache\nfn main() {}\n")
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(1);
+}
+
+#[test]
+fn fix_inserts_the_named_license() {
+    let project = Project::new(NAMED_LICENSES);
+    project
+        .file("main.rs", "fn main() {}\n")
+        .file("Cargo.toml", "[package]\n")
+        .lizenz(&["fix", "main.rs", "Cargo.toml"])
+        .assert_code(0);
+
+    assert_eq!(
+        project.read("main.rs"),
+        "// Licensed under MIT\nfn main() {}\n"
+    );
+    assert_eq!(
+        project.read("Cargo.toml"),
+        "# Licensed under Apache\n[package]\n"
+    );
+}
+
+#[test]
+fn unknown_license_names_are_configuration_errors() {
+    Project::new(&NAMED_LICENSES.replace("license = \"mit\"", "license = \"gpl\""))
+        .file("main.rs", "fn main() {}\n")
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(2)
+        .assert_output("gpl");
+}
+
+fn footer_config() -> String {
+    NAMED_LICENSES.replace(
+        "license = \"mit\"",
+        "license = \"mit\"\nfooter = \"apache\"",
+    )
+}
+
+#[test]
+fn files_need_both_a_header_and_a_footer() {
+    let project = Project::new(&footer_config());
+    project
+        .file(
+            "main.rs",
+            "// Licensed under MIT\nfn main() {}\n// Licensed under Apache\n",
+        )
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(0);
+
+    project
+        .file("main.rs", "// Licensed under MIT\nfn main() {}\n")
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(1)
+        .assert_output("missing or incorrect license footer");
+    project
+        .file("main.rs", "fn main() {}\n// Licensed under Apache\n")
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(1);
+}
+
+#[test]
+fn fix_inserts_both_the_header_and_the_footer() {
+    let project = Project::new(&footer_config());
+    project
+        .file("main.rs", "fn main() {}")
+        .file("footer.rs", "// Licensed under MIT\nfn main() {}\n")
+        .lizenz(&["fix", "main.rs", "footer.rs"])
+        .assert_code(0);
+
+    let expected = "// Licensed under MIT\nfn main() {}\n// Licensed under Apache\n";
+    assert_eq!(project.read("main.rs"), expected);
+    assert_eq!(project.read("footer.rs"), expected);
+    project
+        .lizenz(&["verify", "main.rs", "footer.rs"])
+        .assert_code(0);
+}
+
+#[test]
+fn unknown_footer_names_are_configuration_errors() {
+    Project::new(&footer_config().replace("footer = \"apache\"", "footer = \"gpl\""))
+        .lizenz(&["verify"])
+        .assert_code(2)
+        .assert_output("uses the footer gpl");
+}
+
+const BANNER: &str = r#"required_banner = "Code generated by foo. DO NOT EDIT.""#;
+
+#[test]
+fn required_banners_have_to_be_present() {
+    let project = Project::new(&crate::config(r#"text = "Copyright Foo""#, BANNER));
+    project
+        .file(
+            "banner.rs",
+            "// Code generated by foo. DO NOT EDIT.\n// Copyright Foo\nfn main() {}\n",
+        )
+        .file("license.rs", "// Copyright Foo\nfn main() {}\n");
+
+    project.lizenz(&["verify", "banner.rs"]).assert_code(0);
+    project.lizenz(&["verify", "license.rs"]).assert_code(1);
+}
+
+#[test]
+fn fix_inserts_banners_above_the_license() {
+    let project = Project::new(&crate::config(r#"text = "Copyright Foo""#, BANNER));
+    project
+        .file("missing.rs", "fn main() {}\n")
+        .file("license.rs", "// Copyright Foo\nfn main() {}\n")
+        .lizenz(&["fix", "missing.rs", "license.rs"])
+        .assert_code(0);
+
+    assert_eq!(
+        project.read("missing.rs"),
+        "// Code generated by foo. DO NOT EDIT.\n// Copyright Foo\nfn main() {}\n"
+    );
+    assert_eq!(
+        project.read("license.rs"),
+        "// Code generated by foo. DO NOT EDIT.\n// Copyright Foo\nfn main() {}\n"
+    );
+}
+
+#[test]
+fn languages_without_comments_are_warned_about() {
+    Project::new(&format!(
+        "{CONFIG}\n[languages.bash]\nfile_endings = [\"*.sh\"]\ncomments = []\n"
+    ))
+    .file("run.sh", "# Copyright Foo\necho hello\n")
+    .lizenz(&["verify", "run.sh"])
+    .assert_code(1)
+    .assert_output(
+        "The language bash has no comment configuration, so no license header can be found in run.sh",
+    );
+}
+
+#[test]
+fn fix_names_languages_without_comments() {
+    let project = Project::new(&format!(
+        "{CONFIG}\n[languages.bash]\nfile_endings = [\"*.sh\"]\ncomments = []\n"
+    ));
+    project
+        .file("run.sh", "echo hello\n")
+        .lizenz(&["fix", "run.sh"])
+        .assert_code(2)
+        .assert_output(
+            "Cannot insert a license header into run.sh, as no comment style is configured for the language bash",
+        );
+    assert_eq!(project.read("run.sh"), "echo hello\n");
+}
+
+/// A configuration placing the header relative to an inner attribute like `#![no_std]`, the
+/// closest Rust has to a package declaration
+fn declaration_config(header: &str) -> String {
+    config(
+        "text = \"Copyright Foo\"",
+        &format!("declaration = {{ node_kind = \"inner_attribute_item\", header = \"{header}\" }}"),
+    )
+}
+
+#[test]
+fn headers_before_a_declaration_verify() {
+    let project = Project::new(&declaration_config("before"));
+    project
+        .file("lib.rs", "// Copyright Foo\n#![no_std]\nfn main() {}\n")
+        .lizenz(&["verify", "lib.rs"])
+        .assert_code(0);
+    project
+        .file("lib.rs", "#![no_std]\n// Copyright Foo\nfn main() {}\n")
+        .lizenz(&["verify", "lib.rs"])
+        .assert_code(1);
+}
+
+#[test]
+fn headers_after_a_declaration_verify() {
+    let project = Project::new(&declaration_config("after"));
+    project
+        .file("lib.rs", "#![no_std]\n// Copyright Foo\nfn main() {}\n")
+        .lizenz(&["verify", "lib.rs"])
+        .assert_code(0);
+    project
+        .file("lib.rs", "// Copyright Foo\n#![no_std]\nfn main() {}\n")
+        .lizenz(&["verify", "lib.rs"])
+        .assert_code(1);
+}
+
+#[test]
+fn fix_inserts_headers_after_a_declaration() {
+    let project = Project::new(&declaration_config("after"));
+    project
+        .file("lib.rs", "#![no_std]\nfn main() {}\n")
+        .lizenz(&["fix", "lib.rs"])
+        .assert_code(0);
+    assert_eq!(
+        project.read("lib.rs"),
+        "#![no_std]\n// Copyright Foo\nfn main() {}\n"
+    );
+}
+
+/// Rust files beginning with a front matter block, like cargo scripts
+fn front_matter_config() -> String {
+    config("text = \"Copyright Foo\"", "front_matter = true")
+}
+
+#[test]
+fn headers_follow_the_front_matter() {
+    let project = Project::new(&front_matter_config());
+    project
+        .file(
+            "script.rs",
+            "---\n[dependencies]\nfoo = \"1\"\n---\n// Copyright Foo\nfn main() {}\n",
+        )
+        .lizenz(&["verify", "script.rs"])
+        .assert_code(0);
+    project
+        .file("script.rs", "---\n[dependencies]\n---\nfn main() {}\n")
+        .lizenz(&["verify", "script.rs"])
+        .assert_code(1);
+}
+
+#[test]
+fn fix_inserts_headers_after_the_front_matter() {
+    let project = Project::new(&front_matter_config());
+    project
+        .file("script.rs", "---\n[dependencies]\n---\nfn main() {}\n")
+        .lizenz(&["fix", "script.rs"])
+        .assert_code(0);
+    assert_eq!(
+        project.read("script.rs"),
+        "---\n[dependencies]\n---\n// Copyright Foo\nfn main() {}\n"
+    );
+}
+
+/// A two line license between decoration rules
+fn rules_config() -> String {
+    config(
+        "text = \"Copyright Foo\\nAll rights reserved\"\ntop_rule = \"====\"\nbottom_rule = \"----\"",
+        "",
+    )
+}
+
+const RULED_HEADER: &str =
+    "// ====\n// Copyright Foo\n// All rights reserved\n// ----\nfn main() {}\n";
+
+#[test]
+fn fix_surrounds_headers_with_rules() {
+    let project = Project::new(&rules_config());
+    project
+        .file("main.rs", "fn main() {}\n")
+        .lizenz(&["fix", "main.rs"])
+        .assert_code(0);
+    assert_eq!(project.read("main.rs"), RULED_HEADER);
+}
+
+#[test]
+fn headers_have_to_be_surrounded_by_rules() {
+    let project = Project::new(&rules_config());
+    project
+        .file("ruled.rs", RULED_HEADER)
+        .file(
+            "plain.rs",
+            "// Copyright Foo\n// All rights reserved\nfn main() {}\n",
+        )
+        .file(
+            "no_bottom.rs",
+            "// ====\n// Copyright Foo\n// All rights reserved\nfn main() {}\n",
+        );
+
+    project.lizenz(&["verify", "ruled.rs"]).assert_code(0);
+    project.lizenz(&["verify", "plain.rs"]).assert_code(1);
+    project.lizenz(&["verify", "no_bottom.rs"]).assert_code(1);
+}
+
+#[test]
+fn rules_are_literal_in_the_regex_match_mode() {
+    let project = Project::new(&config(
+        "text = \"Copyright F.o\"\ntop_rule = \"+++\"\nmatch_mode = \"regex\"",
+        "",
+    ));
+    project
+        .file("main.rs", "// +++\n// Copyright Foo\nfn main() {}\n")
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(0);
+}
+
+/// A configuration in `config/` whose Rust headers are the comments mentioning a copyright
+fn comment_query_project(query: &str) -> Project {
+    let project = Project::new(CONFIG);
+    project
+        .file(
+            "config/lizenz.toml",
+            &config(
+                "text = \"Copyright Foo\"",
+                r#"comment_query = "queries/comments.scm""#,
+            ),
+        )
+        .file("config/queries/comments.scm", query);
+    project
+}
+
+const COPYRIGHT_QUERY: &str = r#"((line_comment) @comment (#match? @comment "Copyright"))"#;
+
+#[test]
+fn comment_queries_select_the_header_comments() {
+    let project = comment_query_project(COPYRIGHT_QUERY);
+    project
+        .file(
+            "unrelated.rs",
+            "// Unrelated\n// Copyright Foo\nfn main() {}\n",
+        )
+        .file("valid.rs", "// Copyright Foo\nfn main() {}\n")
+        .lizenz(&[
+            "--config-path",
+            "config",
+            "verify",
+            "unrelated.rs",
+            "valid.rs",
+        ])
+        .assert_code(0);
+
+    project
+        .file("invalid.rs", "// Copyright Bar\nfn main() {}\n")
+        .lizenz(&["--config-path", "config", "verify", "invalid.rs"])
+        .assert_code(1);
+}
+
+#[test]
+fn comment_queries_without_comment_captures_are_errors() {
+    comment_query_project("(line_comment) @other")
+        .lizenz(&["--config-path", "config", "verify", "main.rs"])
+        .assert_code(2)
+        .assert_output("has no @comment capture");
+}
+
+#[test]
+fn invalid_comment_queries_are_errors() {
+    comment_query_project("(no_such_node) @comment")
+        .lizenz(&["--config-path", "config", "verify", "main.rs"])
+        .assert_code(2)
+        .assert_output("The comment query of rust at config/queries/comments.scm is invalid");
+}
+
+#[test]
+fn assumed_languages_apply_to_files_without_file_endings() {
+    let project = Project::new(CONFIG);
+    project
+        .file("generated", "// Copyright Foo\nfn main() {}\n")
+        .lizenz(&["--assume-language", "rust", "verify", "generated"])
+        .assert_code(0);
+
+    project
+        .file("snippet", "fn main() {}\n")
+        .lizenz(&["--assume-language", "rust", "fix", "snippet"])
+        .assert_code(0);
+    assert_eq!(project.read("snippet"), "// Copyright Foo\nfn main() {}\n");
+}
+
+#[test]
+fn assumed_languages_override_file_endings() {
+    Project::new(CONFIG)
+        .file("main.toml", "// Copyright Foo\nfn main() {}\n")
+        .lizenz(&["--assume-language", "rust", "verify", "main.toml"])
+        .assert_code(0);
+}
+
+#[test]
+fn unconfigured_assumed_languages_are_errors() {
+    Project::new(CONFIG)
+        .file("main.py", "print()\n")
+        .lizenz(&["--assume-language", "python", "verify", "main.py"])
+        .assert_code(2)
+        .assert_output("Cannot assume the language python, as it is not configured");
+}
+
+#[test]
+fn files_below_min_lines_need_no_header() {
+    let project = Project::new(&config("text = \"Copyright Foo\"", "min_lines = 3"));
+    project
+        .file("under.rs", "mod a;\nmod b;\n")
+        .lizenz(&["verify", "under.rs"])
+        .assert_code(0);
+    project
+        .file("over.rs", "mod a;\nmod b;\nmod c;\n")
+        .lizenz(&["verify", "over.rs"])
+        .assert_code(1);
+}
+
+#[test]
+fn files_below_min_bytes_need_no_header() {
+    let project = Project::new(&config("text = \"Copyright Foo\"", "min_bytes = 20"));
+    project
+        .file("under.rs", "mod abcdefghijklm;\n")
+        .lizenz(&["verify", "under.rs"])
+        .assert_code(0);
+    project
+        .file("over.rs", "mod abcdefghijklmn;\n")
+        .lizenz(&["verify", "over.rs"])
+        .assert_code(1);
+}
+
+#[test]
+fn fix_does_not_insert_headers_into_files_below_the_minimum() {
+    let project = Project::new(&config("text = \"Copyright Foo\"", "min_lines = 3"));
+    project
+        .file("mod.rs", "mod a;\n")
+        .lizenz(&["fix", "mod.rs"])
+        .assert_code(0);
+    assert_eq!(project.read("mod.rs"), "mod a;\n");
+}
+
+#[test]
+fn stats_count_files_below_the_minimum_separately() {
+    let project = Project::new(&config("text = \"Copyright Foo\"", "min_lines = 3"));
+    let run = project
+        .file("src/mod.rs", "mod a;\n")
+        .file("src/main.rs", "// Copyright Foo\nfn main() {\n}\n")
+        .lizenz(&["stats", "src", "--format", "json"]);
+    run.assert_code(0);
+
+    let stats = serde_json::from_str::<serde_json::Value>(&run.stdout).expect("Invalid JSON");
+    assert_eq!(stats["languages"]["rust"]["too_small"], 1);
+    assert_eq!(stats["languages"]["rust"]["valid"], 1);
+    assert_eq!(stats["coverage"], 100.0);
+}
+
+/// A Lisp language whose leading `;;` comments are extracted by the given shell script
+#[cfg(unix)]
+fn extractor_project(script: &str) -> Project {
+    let project = Project::new(&format!(
+        r#"{CONFIG}
+[languages.lisp]
+file_endings = ["*.lisp"]
+comments = [{{ tree_sitter_name = "comment", comment_kind = {{ Single = ";;" }}, preferred = true }}]
+extractor = ["sh", "extract.sh"]
+"#
+    ));
+    project.file("extract.sh", script);
+    project
+}
+
+#[cfg(unix)]
+const LISP_EXTRACTOR: &str = "awk '/^;;/ { sub(/^;; ?/, \"\"); print; next } { exit }'\n";
+
+#[cfg(unix)]
+#[test]
+fn extractors_print_the_leading_comments() {
+    let project = extractor_project(LISP_EXTRACTOR);
+    project
+        .file("good.lisp", ";; Copyright Foo\n(print 1)\n")
+        .file("bad.lisp", ";; Copyright Bar\n(print 1)\n")
+        .lizenz(&["verify", "good.lisp", "bad.lisp"])
+        .assert_code(1)
+        .assert_output("bad.lisp: missing or incorrect license header")
+        .assert_no_output("good.lisp:");
+}
+
+#[cfg(unix)]
+#[test]
+fn fix_inserts_headers_into_extractor_languages() {
+    let project = extractor_project(LISP_EXTRACTOR);
+    project
+        .file("main.lisp", "(print 1)\n")
+        .lizenz(&["fix", "main.lisp"])
+        .assert_code(0);
+    assert_eq!(project.read("main.lisp"), ";; Copyright Foo\n(print 1)\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn failing_extractors_are_errors() {
+    extractor_project("echo 'no such syntax' >&2\nexit 3\n")
+        .file("main.lisp", ";; Copyright Foo\n(print 1)\n")
+        .lizenz(&["verify", "main.lisp"])
+        .assert_code(2)
+        .assert_output("The extractor sh failed for main.lisp with exit status: 3: no such syntax");
+}
+
+#[cfg(unix)]
+#[test]
+fn extractors_get_the_content_on_stdin() {
+    extractor_project("test \"$1\" = main.lisp && head -n 1 | cut -c 4-\n")
+        .file("main.lisp", ";; Copyright Foo\n(print 1)\n")
+        .lizenz(&["verify", "main.lisp"])
+        .assert_code(0);
+}
+
+fn import_config() -> String {
+    config(
+        "text = \"Copyright Foo\"",
+        "import_node_kind = \"use_declaration\"",
+    )
+}
+
+#[test]
+fn headers_after_the_first_import_fail() {
+    let project = Project::new(&import_config());
+    project
+        .file(
+            "after.rs",
+            "use std::fs;\n\n// Copyright Foo\nfn main() {}\n",
+        )
+        .file(
+            "later.rs",
+            "/* Crate docs */\n\nuse std::fs;\n// Copyright Foo\nfn main() {}\n",
+        )
+        .file(
+            "before.rs",
+            "// Copyright Foo\nuse std::fs;\nfn main() {}\n",
+        );
+
+    project
+        .lizenz(&["verify", "after.rs"])
+        .assert_code(1)
+        .assert_output(
+            "after.rs: missing or incorrect license header before the first import on line 1",
+        );
+    project
+        .lizenz(&["verify", "later.rs"])
+        .assert_code(1)
+        .assert_output("before the first import on line 3");
+    project.lizenz(&["verify", "before.rs"]).assert_code(0);
+
+    Project::new(&config(
+        "text = \"Copyright Foo\"",
+        "import_node_kind = \"use_declaration\"\ncomment_containers = [\"function_item\"]",
+    ))
+    .file("main.rs", "use std::io;\nfn main() // Copyright Foo\n{}\n")
+    .lizenz(&["verify", "main.rs"])
+    .assert_code(1)
+    .assert_output("before the first import on line 1");
+}
+
+#[test]
+fn explain_names_the_first_import() {
+    Project::new(&import_config())
+        .file(
+            "main.rs",
+            "use std::fs;\n\n// Copyright Foo\nfn main() {}\n",
+        )
+        .lizenz(&["verify", "--explain", "main.rs"])
+        .assert_code(1)
+        .assert_output("  first import: line 1, only comments before it were searched\n");
+}
+
+#[test]
+fn fix_inserts_the_header_before_the_first_import() {
+    let project = Project::new(&import_config());
+    project
+        .file("main.rs", "use std::fs;\nfn main() {}\n")
+        .lizenz(&["fix", "main.rs"])
+        .assert_code(0);
+    assert_eq!(
+        project.read("main.rs"),
+        "// Copyright Foo\nuse std::fs;\nfn main() {}\n"
+    );
+    project.lizenz(&["verify", "main.rs"]).assert_code(0);
+}
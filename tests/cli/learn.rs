@@ -0,0 +1,52 @@
+// © Marcel Müller 2025, licensed under the EUPL
+
+use crate::CONFIG;
+use crate::Project;
+
+/// A project with two header variants, the first twice as common as the second
+fn project_with_variants() -> Project {
+    let project = Project::new(&format!("# Copyright A\n{CONFIG}"));
+    project
+        .file("src/a.rs", "// Copyright B\n// Second line\nfn a() {}\n")
+        .file("src/b.rs", "// Copyright A\nfn b() {}\n")
+        .file("src/c.rs", "// Copyright A\nfn c() {}\n")
+        .file("src/d.rs", "fn d() {}\n");
+    project
+}
+
+#[test]
+fn learn_lists_variants_by_frequency() {
+    let run = project_with_variants().lizenz(&["learn"]);
+    run.assert_code(0);
+    assert_eq!(
+        run.stdout,
+        "3 files (rust, toml):\n    | Copyright A\n\n1 files (rust):\n    | Copyright B\n    | Second line\n\n1 files without a header\n"
+    );
+}
+
+#[test]
+fn learn_reports_variants_as_json() {
+    let run = project_with_variants().lizenz(&["learn", "--format", "json", "src"]);
+    run.assert_code(0);
+    let report: serde_json::Value = serde_json::from_str(&run.stdout).unwrap();
+    assert_eq!(
+        report,
+        serde_json::json!({
+            "variants": [
+                {
+                    "header": "Copyright A",
+                    "count": 2,
+                    "languages": ["rust"],
+                    "files": ["src/b.rs", "src/c.rs"],
+                },
+                {
+                    "header": "Copyright B\nSecond line",
+                    "count": 1,
+                    "languages": ["rust"],
+                    "files": ["src/a.rs"],
+                },
+            ],
+            "without_header": 1,
+        })
+    );
+}
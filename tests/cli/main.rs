@@ -0,0 +1,232 @@
+// © Marcel Müller 2025, licensed under the EUPL
+
+//! Tests running the lizenz binary on temporary projects, using its built-in grammars
+
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+use std::process::Stdio;
+
+mod archive;
+mod comments;
+mod configuration;
+mod doctor;
+mod exit_codes;
+mod fix;
+mod forbidden;
+mod git;
+mod grammars;
+mod languages;
+mod learn;
+mod match_modes;
+mod normalization;
+mod notebooks;
+mod output;
+mod selection;
+#[cfg(unix)]
+mod serve;
+mod stats;
+mod templates;
+mod timeouts;
+
+/// A configuration requiring the header `// Copyright Foo` in Rust files
+pub const CONFIG: &str = "[license]\ntext = \"Copyright Foo\"\n";
+
+/// The comments of Rust, like in the default configuration
+const RUST_COMMENTS: &str = r#"comments = [
+    { tree_sitter_name = "line_comment", comment_kind = { Single = "//" }, preferred = true },
+    { tree_sitter_name = "block_comment", comment_kind = { Multi = { start = "/*", end = "*/", between = "*" } }, preferred = false },
+]"#;
+
+/// The seconds since the epoch of a day in 2025, which the tests run as if it was today
+const SOURCE_DATE_EPOCH: &str = "1750000000";
+
+/// A configuration with the given body of its `[license]` table, whose Rust language has the
+/// given additional options, like `min_lines = 2`
+pub fn config(license: &str, rust: &str) -> String {
+    format!(
+        "[license]\n{license}\n\n[languages.rust]\nfile_endings = [\"*.rs\"]\n{RUST_COMMENTS}\n{rust}\n"
+    )
+}
+
+/// A temporary directory lizenz is run in
+pub struct Project {
+    dir: tempfile::TempDir,
+}
+
+/// The outcome of running lizenz
+#[derive(Debug)]
+pub struct Run {
+    pub code: i32,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl Project {
+    /// A project containing only a `lizenz.toml` with the given content
+    pub fn new(config: &str) -> Self {
+        let project = Project {
+            dir: tempfile::tempdir().expect("Could not create a temporary directory"),
+        };
+        project.file("lizenz.toml", config);
+        project
+    }
+
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// Writes a file, creating its parent directories
+    pub fn file(&self, path: &str, content: &str) -> &Self {
+        let path = self.path().join(path);
+        std::fs::create_dir_all(path.parent().expect("Files have a parent"))
+            .expect("Could not create the parent directories");
+        std::fs::write(path, content).expect("Could not write the file");
+        self
+    }
+
+    pub fn read(&self, path: &str) -> String {
+        std::fs::read_to_string(self.path().join(path)).expect("Could not read the file")
+    }
+
+    /// A command running lizenz in the project, isolated from the environment of the tests
+    pub fn command(&self, args: &[&str]) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_lizenz"));
+        command
+            .args(args)
+            .current_dir(self.path())
+            .env_remove("TREE_SITTER_GRAMMARS")
+            .env_remove("LIZENZ_PROFILE")
+            .env_remove("RUST_LOG")
+            .env_remove("NO_COLOR")
+            .env("XDG_DATA_HOME", self.path().join(".data"))
+            .env("SOURCE_DATE_EPOCH", SOURCE_DATE_EPOCH);
+        isolate_git(&mut command);
+        command
+    }
+
+    /// Runs lizenz with the given arguments
+    pub fn lizenz(&self, args: &[&str]) -> Run {
+        self.lizenz_with_stdin(args, "")
+    }
+
+    /// Runs lizenz with the given arguments, logging what the `RUST_LOG` filter `log` selects
+    pub fn lizenz_with_log(&self, args: &[&str], log: &str) -> Run {
+        let mut command = self.command(args);
+        command.env("RUST_LOG", log);
+        run(command, "")
+    }
+
+    /// Runs lizenz with the given arguments and content on stdin
+    pub fn lizenz_with_stdin(&self, args: &[&str], stdin: &str) -> Run {
+        run(self.command(args), stdin)
+    }
+
+    /// Runs git in the project, panicking if it fails
+    pub fn git(&self, args: &[&str]) -> &Self {
+        let mut command = Command::new("git");
+        command.args(args).current_dir(self.path());
+        isolate_git(&mut command);
+        let output = command.output().expect("Could not run git");
+        assert!(
+            output.status.success(),
+            "git {args:?} failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        self
+    }
+
+    /// Turns the project into a git repository with all of its files committed
+    pub fn commit_all(&self) -> &Self {
+        if !self.path().join(".git").exists() {
+            self.git(&["init", "-q", "-b", "main"]);
+        }
+        self.git(&["add", "-A"])
+            .git(&["commit", "-q", "--allow-empty", "-m", "Commit"])
+    }
+}
+
+impl Run {
+    /// Asserts that lizenz exited with the given code, printing its output otherwise
+    #[track_caller]
+    pub fn assert_code(&self, code: i32) -> &Self {
+        assert_eq!(self.code, code, "unexpected exit code of {self:#?}");
+        self
+    }
+
+    /// Asserts that stdout or stderr contain the given text
+    #[track_caller]
+    pub fn assert_output(&self, text: &str) -> &Self {
+        assert!(
+            self.stdout.contains(text) || self.stderr.contains(text),
+            "the output does not contain {text:?}: {self:#?}"
+        );
+        self
+    }
+
+    /// Asserts that the logs, which are written to stderr, contain the given text
+    #[track_caller]
+    pub fn assert_log(&self, text: &str) -> &Self {
+        assert!(
+            self.stderr.contains(text),
+            "the logs do not contain {text:?}: {self:#?}"
+        );
+        self
+    }
+
+    /// Asserts that the logs, which are written to stderr, do not contain the given text
+    #[track_caller]
+    pub fn assert_no_log(&self, text: &str) -> &Self {
+        assert!(
+            !self.stderr.contains(text),
+            "the logs contain {text:?}: {self:#?}"
+        );
+        self
+    }
+
+    /// Asserts that neither stdout nor stderr contain the given text
+    #[track_caller]
+    pub fn assert_no_output(&self, text: &str) -> &Self {
+        assert!(
+            !self.stdout.contains(text) && !self.stderr.contains(text),
+            "the output contains {text:?}: {self:#?}"
+        );
+        self
+    }
+}
+
+/// Runs the lizenz command with content on stdin, capturing its output
+fn run(mut command: Command, stdin: &str) -> Run {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Could not run lizenz");
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(stdin.as_bytes())
+        .expect("Could not write to lizenz");
+    let output = child.wait_with_output().expect("Could not run lizenz");
+
+    Run {
+        code: output.status.code().expect("lizenz was killed"),
+        stdout: String::from_utf8(output.stdout).expect("lizenz printed invalid UTF-8"),
+        stderr: String::from_utf8(output.stderr).expect("lizenz printed invalid UTF-8"),
+    }
+}
+
+/// Makes git ignore the configuration of the machine and commit as a fixed author at a fixed date
+fn isolate_git(command: &mut Command) {
+    command
+        .env("GIT_CONFIG_GLOBAL", "/dev/null")
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .env("GIT_AUTHOR_NAME", "Jane Doe")
+        .env("GIT_AUTHOR_EMAIL", "jane@example.com")
+        .env("GIT_COMMITTER_NAME", "Jane Doe")
+        .env("GIT_COMMITTER_EMAIL", "jane@example.com")
+        .env("GIT_AUTHOR_DATE", "2023-03-01T12:00:00Z")
+        .env("GIT_COMMITTER_DATE", "2023-03-01T12:00:00Z");
+}
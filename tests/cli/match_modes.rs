@@ -0,0 +1,94 @@
+// © Marcel Müller 2025, licensed under the EUPL
+
+use crate::Project;
+use crate::config;
+
+/// A header with a blank comment line, which only the normalizing modes ignore
+const SPACED_HEADER: &str = "// Copyright Foo\n//\n// All rights reserved\n\nfn main() {}\n";
+
+fn verify_spaced_header(text: &str, match_mode: &str) -> i32 {
+    Project::new(&config(
+        &format!("text = {text:?}\nmatch_mode = {match_mode:?}"),
+        "",
+    ))
+    .file("main.rs", SPACED_HEADER)
+    .lizenz(&["verify", "main.rs"])
+    .code
+}
+
+#[test]
+fn exact_requires_identical_lines() {
+    assert_eq!(
+        verify_spaced_header("Copyright Foo\nAll rights reserved", "exact"),
+        1
+    );
+    assert_eq!(
+        verify_spaced_header("Copyright Foo\n\nAll rights reserved", "exact"),
+        0
+    );
+}
+
+#[test]
+fn normalized_ignores_blank_lines() {
+    assert_eq!(
+        verify_spaced_header("Copyright Foo\nAll rights reserved", "normalized"),
+        0
+    );
+}
+
+#[test]
+fn normalized_compares_literally() {
+    assert_eq!(
+        verify_spaced_header("Copyright F.o\nAll rights reserved", "normalized"),
+        1
+    );
+}
+
+#[test]
+fn regex_matches_the_normalized_lines() {
+    assert_eq!(
+        verify_spaced_header("Copyright F.o\nAll rights reserved", "regex"),
+        0
+    );
+    assert_eq!(
+        verify_spaced_header("Copyright B.r\nAll rights reserved", "regex"),
+        1
+    );
+}
+
+#[test]
+fn invalid_regex_is_a_configuration_error() {
+    let run = Project::new(&config(
+        "text = \"Copyright (Foo\"\nmatch_mode = \"regex\"",
+        "",
+    ))
+    .file("a.rs", SPACED_HEADER)
+    .file("b.rs", SPACED_HEADER)
+    .lizenz(&["verify", "a.rs", "b.rs"]);
+    run.assert_code(2)
+        .assert_output("unclosed group")
+        .assert_output("lizenz.toml");
+    let output = format!("{}{}", run.stdout, run.stderr);
+    assert_eq!(
+        output
+            .matches("The license text is not a valid regular expression")
+            .count(),
+        1,
+        "{output}"
+    );
+}
+
+#[test]
+fn invalid_footer_regex_is_a_configuration_error() {
+    Project::new(&format!(
+        "{}\n[licenses]\nend = \"End [of\"\n",
+        config(
+            "text = \"Copyright Foo\"\nmatch_mode = \"regex\"",
+            "footer = \"end\""
+        )
+    ))
+    .file("main.rs", SPACED_HEADER)
+    .lizenz(&["verify", "main.rs"])
+    .assert_code(2)
+    .assert_output("The license end is not a valid regular expression");
+}
@@ -0,0 +1,254 @@
+// © Marcel Müller 2025, licensed under the EUPL
+
+use crate::Project;
+use crate::config;
+
+const BLANK_LINE_LICENSE: &str = r#"text = "Copyright Foo\n\nAll rights reserved""#;
+
+#[test]
+fn blank_comment_lines_are_ignored_by_default() {
+    Project::new(&config(BLANK_LINE_LICENSE, ""))
+        .file(
+            "main.rs",
+            "// Copyright Foo\n// All rights reserved\nfn main() {}\n",
+        )
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(0);
+}
+
+#[test]
+fn preserved_blank_comment_lines_are_required() {
+    let project = Project::new(&config(
+        BLANK_LINE_LICENSE,
+        "preserve_blank_comment_lines = true",
+    ));
+    project
+        .file(
+            "blank.rs",
+            "// Copyright Foo\n//\n// All rights reserved\nfn main() {}\n",
+        )
+        .file(
+            "missing.rs",
+            "// Copyright Foo\n// All rights reserved\nfn main() {}\n",
+        );
+
+    project.lizenz(&["verify", "blank.rs"]).assert_code(0);
+    project.lizenz(&["verify", "missing.rs"]).assert_code(1);
+}
+
+/// Rust with block comments whose lines keep their relative indentation
+const UNALIGNED_BLOCK_COMMENTS: &str = r#"
+[languages.rust]
+file_endings = ["*.rs"]
+comments = [
+    { tree_sitter_name = "block_comment", comment_kind = { Multi = { start = "/*", end = "*/" } }, preferred = true },
+]
+"#;
+
+#[test]
+fn tab_indented_block_comments_match_with_expanded_tabs() {
+    let license = "[license]\ntext = \"Copyright Foo\\nPlain\\n    Indented\"\n";
+    let file = "/* Copyright Foo\nPlain\n\tIndented */\nfn main() {}\n";
+
+    Project::new(&format!(
+        "{license}expand_tabs = 4\n{UNALIGNED_BLOCK_COMMENTS}"
+    ))
+    .file("main.rs", file)
+    .lizenz(&["verify", "main.rs"])
+    .assert_code(0);
+    Project::new(&format!("{license}{UNALIGNED_BLOCK_COMMENTS}"))
+        .file("main.rs", file)
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(1);
+}
+
+/// A header with a decomposed `é`, an `e` followed by a combining accent
+const DECOMPOSED_HEADER: &str = "// Copyright Ame\u{301}lie\nfn main() {}\n";
+
+#[test]
+fn normalize_unicode_matches_decomposed_characters() {
+    Project::new(&config(
+        "text = \"Copyright Am\u{e9}lie\"\nnormalize_unicode = true",
+        "",
+    ))
+    .file("main.rs", DECOMPOSED_HEADER)
+    .lizenz(&["verify", "main.rs"])
+    .assert_code(0);
+}
+
+#[test]
+fn decomposed_characters_differ_without_normalize_unicode() {
+    Project::new(&config("text = \"Copyright Am\u{e9}lie\"", ""))
+        .file("main.rs", DECOMPOSED_HEADER)
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(1);
+}
+
+#[test]
+fn blank_lines_between_comments_are_part_of_the_header() {
+    let project = Project::new(&config(
+        BLANK_LINE_LICENSE,
+        "preserve_blank_comment_lines = true",
+    ));
+    project
+        .file(
+            "spaced.rs",
+            "// Copyright Foo\n\n// All rights reserved\nfn main() {}\n",
+        )
+        .file(
+            "blocks.rs",
+            "/* Copyright Foo */\n\n/* All rights reserved */\nfn main() {}\n",
+        )
+        .file(
+            "joined.rs",
+            "/* Copyright Foo */\n/* All rights reserved */\nfn main() {}\n",
+        );
+
+    project.lizenz(&["verify", "spaced.rs"]).assert_code(0);
+    project.lizenz(&["verify", "blocks.rs"]).assert_code(0);
+    project.lizenz(&["verify", "joined.rs"]).assert_code(1);
+}
+
+/// A license line too long for 80 columns
+const LONG_LICENSE: &str = "Licensed under the European Union Public License 1.2 or later, see the LICENSE file at the root of this repository for details";
+
+#[test]
+fn fix_wraps_long_license_lines() {
+    let project = Project::new(&config(&format!("text = {LONG_LICENSE:?}\nwrap = 80"), ""));
+    project
+        .file("main.rs", "fn main() {}\n")
+        .lizenz(&["fix", "main.rs"])
+        .assert_code(0);
+
+    let content = project.read("main.rs");
+    assert_eq!(
+        content,
+        "// Licensed under the European Union Public License 1.2 or later, see the\n// LICENSE file at the root of this repository for details\nfn main() {}\n"
+    );
+    assert!(content.lines().all(|line| line.chars().count() <= 80));
+    project.lizenz(&["verify", "main.rs"]).assert_code(0);
+}
+
+#[test]
+fn wrapped_headers_match_word_by_word() {
+    let project = Project::new(&config(&format!("text = {LONG_LICENSE:?}\nwrap = 80"), ""));
+    project
+        .file(
+            "unwrapped.rs",
+            &format!("// {LONG_LICENSE}\nfn main() {{}}\n"),
+        )
+        .file(
+            "narrow.rs",
+            "// Licensed under the European Union\n// Public License 1.2 or later, see the LICENSE file\n// at the root of this repository for details\nfn main() {}\n",
+        )
+        .file(
+            "different.rs",
+            "// Licensed under the European Union\n// Public License 1.1 or later, see the LICENSE file\n// at the root of this repository for details\nfn main() {}\n",
+        );
+
+    project
+        .lizenz(&["verify", "unwrapped.rs", "narrow.rs"])
+        .assert_code(0);
+    project.lizenz(&["verify", "different.rs"]).assert_code(1);
+}
+
+/// A block comment header indented by four and six spaces
+const INDENTED_HEADER: &str =
+    "/*\n    Copyright Foo\n      All rights reserved\n*/\nfn main() {}\n";
+const INDENTED_LICENSE: &str = "[license]\ntext = \"Copyright Foo\\nAll rights reserved\"\n";
+
+#[test]
+fn ignore_indentation_strips_all_leading_whitespace() {
+    Project::new(&format!(
+        "{INDENTED_LICENSE}ignore_indentation = true\n{UNALIGNED_BLOCK_COMMENTS}"
+    ))
+    .file("main.rs", INDENTED_HEADER)
+    .lizenz(&["verify", "main.rs"])
+    .assert_code(0);
+}
+
+#[test]
+fn indentation_matters_without_ignore_indentation() {
+    Project::new(&format!("{INDENTED_LICENSE}{UNALIGNED_BLOCK_COMMENTS}"))
+        .file("main.rs", INDENTED_HEADER)
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(1);
+}
+
+/// A license of which only the copyright line is verified
+const COPYRIGHT_LINE_LICENSE: &str =
+    "text = \"Copyright Foo\\nAll rights reserved\"\nverify_lines = 1";
+
+#[test]
+fn verify_lines_only_requires_the_leading_lines() {
+    let project = Project::new(&config(COPYRIGHT_LINE_LICENSE, ""));
+    project
+        .file("short.rs", "// Copyright Foo\nfn main() {}\n")
+        .file(
+            "other.rs",
+            "// Copyright Foo\n// Some rights reserved\nfn main() {}\n",
+        )
+        .file(
+            "wrong.rs",
+            "// Copyright Bar\n// All rights reserved\nfn main() {}\n",
+        );
+
+    project
+        .lizenz(&["verify", "short.rs", "other.rs"])
+        .assert_code(0);
+    project.lizenz(&["verify", "wrong.rs"]).assert_code(1);
+}
+
+#[test]
+fn fix_inserts_all_lines_despite_verify_lines() {
+    let project = Project::new(&config(COPYRIGHT_LINE_LICENSE, ""));
+    project
+        .file("main.rs", "fn main() {}\n")
+        .lizenz(&["fix", "main.rs"])
+        .assert_code(0);
+    assert_eq!(
+        project.read("main.rs"),
+        "// Copyright Foo\n// All rights reserved\nfn main() {}\n"
+    );
+}
+
+#[test]
+fn crlf_headers_verify_against_lf_licenses() {
+    let project = Project::new(&config(
+        "text = \"Copyright Foo\\nAll rights reserved\"",
+        "",
+    ));
+    project
+        .file(
+            "line.rs",
+            "// Copyright Foo\r\n// All rights reserved\r\nfn main() {}\r\n",
+        )
+        .file(
+            "block.rs",
+            "/*\r\n * Copyright Foo\r\n * All rights reserved\r\n */\r\nfn main() {}\r\n",
+        )
+        .lizenz(&["verify", "line.rs", "block.rs"])
+        .assert_code(0);
+
+    project
+        .file(
+            "other.rs",
+            "// Copyright Foo\r\n// Some rights reserved\r\nfn main() {}\r\n",
+        )
+        .lizenz(&["verify", "other.rs"])
+        .assert_code(1);
+}
+
+#[test]
+fn crlf_licenses_verify_lf_headers() {
+    Project::new(&config(
+        "text = \"Copyright Foo\\r\\nAll rights reserved\"",
+        "",
+    ))
+    .file(
+        "main.rs",
+        "// Copyright Foo\n// All rights reserved\nfn main() {}\n",
+    )
+    .lizenz(&["verify", "main.rs"])
+    .assert_code(0);
+}
@@ -0,0 +1,106 @@
+// © Marcel Müller 2025, licensed under the EUPL
+
+use crate::CONFIG;
+use crate::Project;
+
+/// Notebooks whose code cells are bash, as the grammar is built in
+fn notebook_config() -> String {
+    format!(
+        "{CONFIG}\n[languages.notebook]\nfile_endings = [\"*.ipynb\"]\nnotebook_language = \"bash\"\ncomments = []\n"
+    )
+}
+
+/// A minimal notebook of a markdown cell followed by code cells with the given sources
+fn notebook(code_cells: &[&str]) -> String {
+    let mut cells = vec![
+        r##"{"cell_type": "markdown", "metadata": {}, "source": ["# Analysis\n"]}"##.to_string(),
+    ];
+    cells.extend(code_cells.iter().map(|source| {
+        format!(
+            r#"{{"cell_type": "code", "execution_count": null, "metadata": {{}}, "outputs": [], "source": {}}}"#,
+            serde_json::to_string(source).expect("Could not encode the source")
+        )
+    }));
+    format!(
+        r#"{{"cells": [{}], "metadata": {{}}, "nbformat": 4, "nbformat_minor": 5}}"#,
+        cells.join(", ")
+    )
+}
+
+#[test]
+fn the_first_code_cell_of_notebooks_is_verified() {
+    let project = Project::new(&notebook_config());
+    project
+        .file(
+            "valid.ipynb",
+            &notebook(&["# Copyright Foo\necho hi\n", "echo second\n"]),
+        )
+        .file(
+            "invalid.ipynb",
+            &notebook(&["echo hi\n", "# Copyright Foo\necho second\n"]),
+        );
+
+    project.lizenz(&["verify", "valid.ipynb"]).assert_code(0);
+    project
+        .lizenz(&["verify", "invalid.ipynb"])
+        .assert_code(1)
+        .assert_output("invalid.ipynb: missing or incorrect license header");
+}
+
+#[test]
+fn notebooks_without_code_cells_need_no_header() {
+    Project::new(&notebook_config())
+        .file("notes.ipynb", &notebook(&[]))
+        .lizenz(&["verify", "notes.ipynb"])
+        .assert_code(0);
+}
+
+#[test]
+fn fix_inserts_the_header_into_the_first_code_cell() {
+    let project = Project::new(&notebook_config());
+    project
+        .file("analysis.ipynb", &notebook(&["echo hi\n", "echo second\n"]))
+        .lizenz(&["fix", "analysis.ipynb"])
+        .assert_code(0);
+
+    let fixed: serde_json::Value =
+        serde_json::from_str(&project.read("analysis.ipynb")).expect("The notebook is not JSON");
+    let cells = fixed["cells"]
+        .as_array()
+        .expect("The notebook has no cells");
+    assert_eq!(cells[0]["source"], serde_json::json!(["# Analysis\n"]));
+    assert_eq!(
+        cells[1]["source"],
+        serde_json::json!(["# Copyright Foo\n", "echo hi\n"])
+    );
+    assert_eq!(cells[2]["source"], "echo second\n");
+    project.lizenz(&["verify", "analysis.ipynb"]).assert_code(0);
+}
+
+#[test]
+fn notebooks_without_code_cells_are_left_unfixed() {
+    let project = Project::new(&notebook_config());
+    let content = notebook(&[]);
+    project
+        .file("notes.ipynb", &content)
+        .lizenz(&["fix", "notes.ipynb"])
+        .assert_code(0);
+    assert_eq!(project.read("notes.ipynb"), content);
+}
+
+#[test]
+fn invalid_notebooks_are_errors() {
+    Project::new(&notebook_config())
+        .file("broken.ipynb", "{\"cells\": [")
+        .lizenz(&["verify", "broken.ipynb"])
+        .assert_code(2)
+        .assert_output("Could not read the notebook broken.ipynb");
+}
+
+#[test]
+fn notebooks_cannot_be_created() {
+    Project::new(&notebook_config())
+        .lizenz(&["fix", "--create", "new.ipynb"])
+        .assert_code(2)
+        .assert_output("Cannot create the notebook new.ipynb");
+}
@@ -0,0 +1,467 @@
+// © Marcel Müller 2025, licensed under the EUPL
+
+use crate::CONFIG;
+use crate::Project;
+
+const ANSI_ESCAPE: &str = "\u{1b}[";
+
+#[test]
+fn never_colors_nothing() {
+    Project::new(CONFIG)
+        .file("main.rs", "fn main() {}\n")
+        .lizenz(&["--color", "never", "verify", "--explain", "main.rs"])
+        .assert_code(1)
+        .assert_no_output(ANSI_ESCAPE);
+}
+
+#[test]
+fn always_colors_even_without_a_terminal() {
+    Project::new(CONFIG)
+        .file("main.rs", "fn main() {}\n")
+        .lizenz(&["--color", "always", "verify", "main.rs"])
+        .assert_code(1)
+        .assert_output(ANSI_ESCAPE);
+}
+
+#[test]
+fn auto_does_not_color_pipes() {
+    Project::new(CONFIG)
+        .file("main.rs", "fn main() {}\n")
+        .lizenz(&["verify", "main.rs"])
+        .assert_code(1)
+        .assert_no_output(ANSI_ESCAPE);
+}
+
+#[test]
+fn the_config_schema_is_json_describing_the_configuration() {
+    let run = Project::new(CONFIG).lizenz(&["config-schema"]);
+    run.assert_code(0);
+
+    let schema = serde_json::from_str::<serde_json::Value>(&run.stdout)
+        .expect("The schema is not valid JSON");
+    assert!(schema["properties"]["license"].is_object(), "{schema:#}");
+    assert!(schema["properties"]["languages"].is_object(), "{schema:#}");
+    for definition in [
+        "LicenseConfig",
+        "LanguageConfig",
+        "CommentConfig",
+        "CommentKind",
+    ] {
+        assert!(
+            schema["$defs"][definition].is_object(),
+            "{definition} is missing from {schema:#}"
+        );
+    }
+}
+
+/// A project with many files without header, to be checked in parallel
+fn many_invalid_files() -> (Project, Vec<String>) {
+    let project = Project::new(CONFIG);
+    let files = (0..200)
+        .map(|index| format!("f{index}.rs"))
+        .collect::<Vec<_>>();
+    for file in &files {
+        project.file(file, "fn main() {}\n");
+    }
+    (project, files)
+}
+
+#[test]
+fn parallel_human_output_does_not_interleave_lines() {
+    let (project, files) = many_invalid_files();
+    let mut args = vec!["--jobs", "8", "verify"];
+    args.extend(files.iter().map(String::as_str));
+    let run = project.lizenz(&args);
+    run.assert_code(1);
+
+    let mut reported = run
+        .stdout
+        .lines()
+        .map(|line| {
+            line.strip_suffix(": missing or incorrect license header")
+                .unwrap_or_else(|| panic!("garbled line {line:?} in {run:#?}"))
+        })
+        .collect::<Vec<_>>();
+    reported.sort_unstable();
+    let mut expected = files.iter().map(String::as_str).collect::<Vec<_>>();
+    expected.sort_unstable();
+    assert_eq!(reported, expected);
+}
+
+#[test]
+fn parallel_json_output_is_deterministic() {
+    let (project, files) = many_invalid_files();
+    let mut args = vec!["--jobs", "8", "verify", "--format", "json"];
+    args.extend(files.iter().map(String::as_str));
+    let first = project.lizenz(&args);
+    first.assert_code(1);
+    let report: serde_json::Value = serde_json::from_str(&first.stdout).unwrap();
+    assert_eq!(report["findings"].as_array().unwrap().len(), files.len());
+
+    for _ in 0..3 {
+        assert_eq!(project.lizenz(&args).stdout, first.stdout);
+    }
+}
+
+#[test]
+fn count_prints_the_number_of_failing_files() {
+    let project = Project::new(CONFIG);
+    project
+        .file("a.rs", "fn a() {}\n")
+        .file("b.rs", "fn b() {}\n")
+        .file("valid.rs", "// Copyright Foo\nfn valid() {}\n");
+
+    let run = project.lizenz(&["verify", "--count", "a.rs", "b.rs", "valid.rs"]);
+    run.assert_code(1);
+    assert_eq!(run.stdout, "2\n");
+
+    let run = project.lizenz(&["verify", "--count", "valid.rs"]);
+    run.assert_code(0);
+    assert_eq!(run.stdout, "0\n");
+}
+
+#[test]
+fn count_covers_searched_directories() {
+    let project = Project::new(&format!("# Copyright Foo\n{CONFIG}"));
+    project
+        .file("src/a.rs", "fn a() {}\n")
+        .file("src/nested/b.rs", "fn b() {}\n")
+        .file("src/valid.rs", "// Copyright Foo\nfn valid() {}\n");
+
+    let run = project.lizenz(&["verify", "--count"]);
+    run.assert_code(1);
+    assert_eq!(run.stdout, "2\n");
+}
+
+#[test]
+fn explain_points_at_the_first_differing_line() {
+    let run = Project::new("[license]\ntext = \"Copyright Foo\\nAll rights reserved\\nThird\"\n")
+        .file(
+            "main.rs",
+            "// Copyright Foo\n// All rights reservd\n// Third\nfn main() {}\n",
+        )
+        .lizenz(&["verify", "--explain", "main.rs"]);
+    run.assert_code(1)
+        .assert_output("  language: rust\n")
+        .assert_output("  first difference in line 2 of the header:\n")
+        .assert_output("    found:    All rights reservd\n")
+        .assert_output("    expected: All rights reserved\n");
+    let caret = run
+        .stdout
+        .lines()
+        .find(|line| line.trim() == "^")
+        .expect("The explanation has no caret");
+    assert_eq!(
+        caret.find('^'),
+        Some("    expected: All rights reserv".len())
+    );
+}
+
+#[test]
+fn explain_is_shown_regardless_of_the_log_level() {
+    let project = Project::new(CONFIG);
+    project.file("main.rs", "fn main() {}\n");
+    let run = project.lizenz_with_log(&["verify", "--explain", "main.rs"], "error");
+    run.assert_code(1);
+    assert!(
+        run.stdout.contains("  expected:\n    | Copyright Foo"),
+        "{run:#?}"
+    );
+}
+
+#[test]
+fn output_writes_the_report_to_a_file() {
+    let project = Project::new(CONFIG);
+    project
+        .file("main.rs", "fn main() {}\n")
+        .file("valid.rs", "// Copyright Foo\nfn valid() {}\n");
+
+    let run = project.lizenz(&[
+        "verify",
+        "--format",
+        "sarif",
+        "--output",
+        "report.sarif",
+        "main.rs",
+        "valid.rs",
+    ]);
+    // The human readable output is still printed, as the report goes to the file
+    run.assert_code(1)
+        .assert_output("main.rs: missing or incorrect license header");
+
+    let report: serde_json::Value = serde_json::from_str(&project.read("report.sarif")).unwrap();
+    assert_eq!(report["version"], "2.1.0");
+    let results = report["runs"][0]["results"].as_array().unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(
+        results[0]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+        "main.rs"
+    );
+}
+
+#[test]
+fn output_of_json_reports_is_valid_json() {
+    let project = Project::new(CONFIG);
+    project
+        .file("main.rs", "fn main() {}\n")
+        .lizenz(&[
+            "verify",
+            "--format",
+            "json",
+            "--output",
+            "report.json",
+            "main.rs",
+        ])
+        .assert_code(1);
+
+    let report: serde_json::Value = serde_json::from_str(&project.read("report.json")).unwrap();
+    assert_eq!(report["findings"][0]["path"], "main.rs");
+    assert_eq!(report["findings"][0]["outcome"], "missing");
+}
+
+#[test]
+fn output_requires_a_machine_readable_format() {
+    Project::new(CONFIG)
+        .file("main.rs", "fn main() {}\n")
+        .lizenz(&["verify", "--output", "report.txt", "main.rs"])
+        .assert_code(2)
+        .assert_output("--output requires a machine readable --format");
+}
+
+#[test]
+fn timings_list_the_slowest_files() {
+    let project = Project::new(CONFIG);
+    project
+        .file("a.rs", "// Copyright Foo\nfn main() {}\n")
+        .file("b.rs", "fn main() {}\n");
+
+    let run = project.lizenz(&["verify", "--timings", "a.rs", "b.rs"]);
+    run.assert_code(1);
+    assert!(
+        run.stderr.contains("Verifying 2 files took"),
+        "{}",
+        run.stderr
+    );
+    assert!(run.stderr.contains("  a.rs\n"), "{}", run.stderr);
+    assert!(run.stderr.contains("  b.rs\n"), "{}", run.stderr);
+    assert!(!run.stdout.contains("Verifying 2 files"), "{}", run.stdout);
+}
+
+#[test]
+fn per_file_durations_are_traced() {
+    let project = Project::new(CONFIG);
+    project
+        .file("main.rs", "// Copyright Foo\nfn main() {}\n")
+        .lizenz_with_log(&["verify", "main.rs"], "lizenz=trace")
+        .assert_log("Read main.rs in")
+        .assert_log("Parsed main.rs in")
+        .assert_log("Compared the header of main.rs in");
+}
+
+#[test]
+fn reported_paths_are_relative_to_the_current_directory() {
+    let project = Project::new(CONFIG);
+    let run = project
+        .file("src/main.rs", "fn main() {}\n")
+        .lizenz(&["verify", "./src/../src/main.rs"]);
+    run.assert_code(1);
+    assert_eq!(
+        run.stdout,
+        "src/main.rs: missing or incorrect license header\n"
+    );
+}
+
+#[test]
+fn reported_paths_are_relative_to_relative_to() {
+    let project = Project::new(CONFIG);
+    let run = project.file("src/main.rs", "fn main() {}\n").lizenz(&[
+        "verify",
+        "--relative-to",
+        "src",
+        "--format",
+        "json",
+        "src/main.rs",
+    ]);
+    run.assert_code(1);
+    let report = serde_json::from_str::<serde_json::Value>(&run.stdout).expect("Invalid JSON");
+    assert_eq!(report["findings"][0]["path"], "main.rs");
+}
+
+#[test]
+fn paths_outside_of_relative_to_are_absolute() {
+    let project = Project::new(CONFIG);
+    let run = project
+        .file("src/main.rs", "fn main() {}\n")
+        .file("other/main.rs", "fn main() {}\n")
+        .lizenz(&["verify", "--relative-to", "src", "other/main.rs"]);
+    run.assert_code(1);
+
+    let absolute = project
+        .path()
+        .canonicalize()
+        .expect("The project has a path")
+        .join("other")
+        .join("main.rs");
+    run.assert_output(&format!("{}: missing", absolute.display()));
+}
+
+#[test]
+fn try_explains_content_from_stdin() {
+    Project::new(CONFIG)
+        .lizenz_with_stdin(
+            &["try", "--lang", "rust"],
+            "// Copyright Fo\nfn main() {}\n",
+        )
+        .assert_code(1)
+        .assert_output("<stdin>: missing or incorrect license header\n")
+        .assert_output("  language: rust\n")
+        .assert_output("  comment style: line_comment (//, preferred)\n")
+        .assert_output("  first difference in line 1 of the header:\n")
+        .assert_output("    found:    Copyright Fo\n")
+        .assert_output("    expected: Copyright Foo\n");
+}
+
+#[test]
+fn try_reports_valid_content() {
+    Project::new(CONFIG)
+        .lizenz_with_stdin(&["try", "--lang", "bash"], "# Copyright Foo\necho hi\n")
+        .assert_code(0)
+        .assert_output("<stdin>: valid license header\n")
+        .assert_output("  language: bash\n")
+        .assert_output("  found:\n    | Copyright Foo\n");
+}
+
+#[test]
+fn try_uses_the_given_language_regardless_of_file_endings() {
+    let project = Project::new(CONFIG);
+    // Without a file name, only the language decides how comments look
+    project
+        .lizenz_with_stdin(&["try", "--lang", "rust"], "# Copyright Foo\n")
+        .assert_code(1);
+    project
+        .lizenz_with_stdin(&["try", "--lang", "toml"], "# Copyright Foo\n")
+        .assert_code(0);
+}
+
+#[test]
+fn try_rejects_unconfigured_languages() {
+    Project::new(CONFIG)
+        .lizenz(&["try", "--lang", "python"])
+        .assert_code(2)
+        .assert_output("Cannot try the language python, as it is not configured");
+}
+
+#[test]
+fn github_format_prints_workflow_commands() {
+    let project = Project::new(CONFIG);
+    project
+        .file("src/a.rs", "fn a() {}\n")
+        .file("src/b,c.rs", "fn b() {}\n")
+        .file("valid.rs", "// Copyright Foo\nfn valid() {}\n");
+
+    let run = project.lizenz(&[
+        "verify",
+        "--format",
+        "github",
+        "src/a.rs",
+        "valid.rs",
+        "src/b,c.rs",
+    ]);
+    run.assert_code(1);
+    assert_eq!(
+        run.stdout,
+        "::error file=src/a.rs,line=1::missing or incorrect license header\n::error file=src/b%2Cc.rs,line=1::missing or incorrect license header\n"
+    );
+
+    let run = project.lizenz(&["verify", "--format", "github", "valid.rs"]);
+    run.assert_code(0);
+    assert_eq!(run.stdout, "");
+}
+
+#[test]
+fn warnings_are_written_to_stderr() {
+    let run = Project::new(CONFIG)
+        .file("main.rs", "// Copyright Foo\nfn main() {}\n")
+        .file("large.rs", &"fn large() {}\n".repeat(10))
+        .lizenz(&["verify", "--max-filesize", "50", "main.rs", "large.rs"]);
+    run.assert_code(0);
+    assert_eq!(run.stdout, "");
+    assert!(
+        run.stderr
+            .contains("Skipping large.rs, as it is larger than 50 bytes"),
+        "{run:#?}"
+    );
+}
+
+/// A project with an invalid file and one above a `--max-filesize` of 50, whose skipping is
+/// warned about
+fn project_with_warning() -> Project {
+    let project = Project::new(CONFIG);
+    project
+        .file("main.rs", "fn main() {}\n")
+        .file("large.rs", &"fn large() {}\n".repeat(10));
+    project
+}
+
+#[test]
+fn machine_readable_reports_are_not_mixed_with_warnings() {
+    let project = project_with_warning();
+    for format in ["json", "sarif"] {
+        let run = project.lizenz(&[
+            "verify",
+            "--format",
+            format,
+            "--max-filesize",
+            "50",
+            "main.rs",
+            "large.rs",
+        ]);
+        run.assert_code(1)
+            .assert_log("Skipping large.rs, as it is larger than 50 bytes");
+        let report: serde_json::Value =
+            serde_json::from_str(&run.stdout).expect("stdout is not only the report");
+        let findings = match format {
+            "json" => &report["findings"],
+            _ => &report["runs"][0]["results"],
+        };
+        assert_eq!(findings.as_array().map(Vec::len), Some(1), "{report}");
+    }
+}
+
+#[test]
+fn count_prints_only_the_number_while_warning() {
+    let run = project_with_warning().lizenz(&[
+        "verify",
+        "--count",
+        "--max-filesize",
+        "50",
+        "main.rs",
+        "large.rs",
+    ]);
+    run.assert_code(1)
+        .assert_log("Skipping large.rs, as it is larger than 50 bytes");
+    assert_eq!(run.stdout, "1\n");
+}
+
+#[test]
+fn github_format_prints_only_workflow_commands_while_warning() {
+    let run = project_with_warning().lizenz(&[
+        "verify",
+        "--format",
+        "github",
+        "--max-filesize",
+        "50",
+        "main.rs",
+        "large.rs",
+    ]);
+    run.assert_code(1)
+        .assert_log("Skipping large.rs, as it is larger than 50 bytes");
+    assert_eq!(
+        run.stdout,
+        "::error file=main.rs,line=1::missing or incorrect license header\n"
+    );
+    assert!(
+        run.stdout.lines().all(|line| line.starts_with("::error ")),
+        "{run:#?}"
+    );
+}
@@ -0,0 +1,238 @@
+// © Marcel Müller 2025, licensed under the EUPL
+
+use crate::CONFIG;
+use crate::Project;
+
+const VALID: &str = "// Copyright Foo\nfn main() {}\n";
+const INVALID: &str = "fn main() {}\n";
+
+#[test]
+fn include_restricts_the_given_files() {
+    Project::new(CONFIG)
+        .file("src/main.rs", VALID)
+        .file("other/main.rs", INVALID)
+        .lizenz(&[
+            "verify",
+            "--include",
+            "src/**",
+            "src/main.rs",
+            "other/main.rs",
+        ])
+        .assert_code(0);
+}
+
+#[test]
+fn include_restricts_searched_directories() {
+    let project = Project::new(CONFIG);
+    project
+        .file("src/main.rs", VALID)
+        .file("other/main.rs", INVALID);
+
+    project
+        .lizenz(&["verify", "--include", "src/**"])
+        .assert_code(0);
+    project
+        .lizenz(&["verify", "--include", "src/**", "--include", "other/**"])
+        .assert_code(1)
+        .assert_output("other/main.rs");
+}
+
+#[test]
+fn include_without_slash_matches_file_names() {
+    Project::new(CONFIG)
+        .file("src/lib.rs", VALID)
+        .file("src/main.rs", INVALID)
+        .lizenz(&["verify", "--include", "lib.rs"])
+        .assert_code(0);
+}
+
+#[test]
+fn include_combines_with_ignore_files() {
+    Project::new(CONFIG)
+        .file(".ignore", "src/generated/\n")
+        .file("src/main.rs", VALID)
+        .file("src/generated/bindings.rs", INVALID)
+        .lizenz(&["verify", "--include", "src/**"])
+        .assert_code(0);
+}
+
+/// A Rust file without header of exactly `size` bytes
+fn invalid_file_of_size(size: usize) -> String {
+    let mut content = String::from("fn main() {}\n");
+    content.push_str(&"/".repeat(size - content.len() - 1));
+    content.push('\n');
+    content
+}
+
+#[test]
+fn files_above_the_max_filesize_are_skipped() {
+    Project::new(CONFIG)
+        .file("main.rs", &invalid_file_of_size(101))
+        .lizenz(&["verify", "--max-filesize", "100", "main.rs"])
+        .assert_code(0)
+        .assert_output("Skipping main.rs, as it is larger than 100 bytes");
+}
+
+#[test]
+fn files_at_the_max_filesize_are_checked() {
+    Project::new(CONFIG)
+        .file("main.rs", &invalid_file_of_size(100))
+        .lizenz(&["verify", "--max-filesize", "100", "main.rs"])
+        .assert_code(1)
+        .assert_no_output("Skipping");
+}
+
+/// The configuration with a header of its own, as lizenz.toml is found in the directory as well
+fn licensed_config() -> String {
+    format!("# Copyright Foo\n{CONFIG}")
+}
+
+#[test]
+fn verify_without_files_checks_the_current_directory() {
+    let project = Project::new(&licensed_config());
+    project
+        .file("src/main.rs", VALID)
+        .file("src/nested/deep/lib.rs", VALID);
+    project.lizenz(&["verify"]).assert_code(0);
+
+    project.file("src/nested/invalid.rs", INVALID);
+    project
+        .lizenz(&["verify"])
+        .assert_code(1)
+        .assert_output("invalid.rs");
+}
+
+#[test]
+fn verify_without_files_respects_ignore_files() {
+    Project::new(&licensed_config())
+        .file(".ignore", "target/\n")
+        .file("src/main.rs", VALID)
+        .file("target/generated.rs", INVALID)
+        .lizenz(&["verify"])
+        .assert_code(0);
+}
+
+#[test]
+fn given_files_replace_the_current_directory() {
+    Project::new(&licensed_config())
+        .file("src/main.rs", VALID)
+        .file("src/invalid.rs", INVALID)
+        .lizenz(&["verify", "src/main.rs"])
+        .assert_code(0);
+}
+
+#[test]
+fn files_from_reads_one_file_per_line() {
+    let project = Project::new(CONFIG);
+    project
+        .file("a.rs", VALID)
+        .file("b.rs", INVALID)
+        .file("list.txt", "a.rs\r\n\nb.rs\n");
+    project
+        .lizenz(&["verify", "--files-from", "list.txt"])
+        .assert_code(1)
+        .assert_output("b.rs:");
+    project
+        .lizenz_with_stdin(&["verify", "--files-from", "-"], "a.rs\n")
+        .assert_code(0);
+}
+
+#[cfg(unix)]
+#[test]
+fn null_separates_files_containing_newlines() {
+    let project = Project::new(CONFIG);
+    project
+        .file("new\nline.rs", INVALID)
+        .file("valid.rs", VALID)
+        .file("list", "valid.rs\0new\nline.rs\0");
+
+    project
+        .lizenz(&["verify", "--files-from", "list", "-0"])
+        .assert_code(1)
+        .assert_output("new\nline.rs");
+    project
+        .lizenz_with_stdin(
+            &["verify", "--files-from", "-", "--null"],
+            "valid.rs\0new\nline.rs\0",
+        )
+        .assert_code(1)
+        .assert_output("new\nline.rs");
+    project
+        .lizenz_with_stdin(&["verify", "--files-from", "-", "--null"], "valid.rs\0")
+        .assert_code(0);
+}
+
+#[test]
+fn list_files_prints_the_selected_files_and_their_languages() {
+    let run = Project::new(&licensed_config())
+        .file("src/main.rs", INVALID)
+        .file("scripts/run.sh", "echo hello\n")
+        .file("README.md", "# Foo\n")
+        .file(".ignore", "target/\n")
+        .file("target/generated.rs", INVALID)
+        .lizenz(&["verify", "--list-files"]);
+    run.assert_code(0);
+
+    let mut listed = run.stdout.lines().collect::<Vec<_>>();
+    listed.sort_unstable();
+    assert_eq!(
+        listed,
+        [
+            "lizenz.toml: toml",
+            "scripts/run.sh: bash",
+            "src/main.rs: rust"
+        ]
+    );
+}
+
+#[test]
+fn list_files_applies_includes() {
+    let run = Project::new(CONFIG)
+        .file("src/main.rs", INVALID)
+        .file("other/main.rs", INVALID)
+        .lizenz(&["verify", "--list-files", "--include", "src/**"]);
+    run.assert_code(0);
+    assert_eq!(run.stdout, "src/main.rs: rust\n");
+}
+
+/// A directory of interleaved Rust, shell and TOML files, every third of which is invalid,
+/// returning the invalid ones
+fn mixed_fixture(project: &Project) -> Vec<String> {
+    let languages = [
+        ("rs", "// Copyright Foo\n", "fn main() {}\n"),
+        ("sh", "# Copyright Foo\n", "echo hello\n"),
+        ("toml", "# Copyright Foo\n", "[package]\n"),
+    ];
+    let mut invalid = vec![];
+    for index in 0..60 {
+        let (ending, header, content) = languages[index % languages.len()];
+        let file = format!("src/{index}.{ending}");
+        if index % 3 == index / 3 % 3 {
+            project.file(&file, content);
+            invalid.push(file);
+        } else {
+            project.file(&file, &format!("{header}{content}"));
+        }
+    }
+    invalid.sort();
+    invalid
+}
+
+#[test]
+fn mixed_directories_are_verified_with_reused_parsers() {
+    let project = Project::new(&licensed_config());
+    let expected = mixed_fixture(&project);
+
+    for jobs in ["1", "4"] {
+        let run = project.lizenz(&["--jobs", jobs, "verify"]);
+        run.assert_code(1);
+        let mut reported = run
+            .stdout
+            .lines()
+            .filter_map(|line| line.strip_suffix(": missing or incorrect license header"))
+            .map(|file| file.trim_start_matches("./").to_string())
+            .collect::<Vec<_>>();
+        reported.sort();
+        assert_eq!(reported, expected, "with {jobs} jobs");
+    }
+}
@@ -0,0 +1,131 @@
+// © Marcel Müller 2025, licensed under the EUPL
+
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::os::unix::net::UnixStream;
+use std::process::Child;
+use std::process::Stdio;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::CONFIG;
+use crate::Project;
+
+/// A running `lizenz serve`, which is stopped when dropped
+struct Server {
+    child: Child,
+    stream: BufReader<UnixStream>,
+}
+
+impl Server {
+    fn start(project: &Project) -> Self {
+        let child = project
+            .command(&["serve", "lizenz.sock"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("Could not start lizenz");
+
+        let socket = project.path().join("lizenz.sock");
+        let started = Instant::now();
+        let stream = loop {
+            match UnixStream::connect(&socket) {
+                Ok(stream) => break stream,
+                Err(_) if started.elapsed() < Duration::from_secs(10) => {
+                    std::thread::sleep(Duration::from_millis(10));
+                }
+                Err(error) => panic!("Could not connect to lizenz: {error}"),
+            }
+        };
+        Self {
+            child,
+            stream: BufReader::new(stream),
+        }
+    }
+
+    /// Sends a request and returns the response to it
+    fn request(&mut self, request: serde_json::Value) -> serde_json::Value {
+        let stream = self.stream.get_mut();
+        writeln!(stream, "{request}").expect("Could not send the request");
+        let mut response = String::new();
+        self.stream
+            .read_line(&mut response)
+            .expect("Could not read the response");
+        serde_json::from_str(&response).expect("The response is not JSON")
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[test]
+fn serve_verifies_files() {
+    let project = Project::new(CONFIG);
+    project
+        .file("valid.rs", "// Copyright Foo\nfn main() {}\n")
+        .file("missing.rs", "fn main() {}\n");
+    let mut server = Server::start(&project);
+
+    assert_eq!(
+        server.request(serde_json::json!({"command": "verify", "path": "valid.rs"})),
+        serde_json::json!({"outcome": "valid"})
+    );
+    assert_eq!(
+        server.request(serde_json::json!({"command": "verify", "path": "missing.rs"})),
+        serde_json::json!({"outcome": "missing"})
+    );
+}
+
+#[test]
+fn serve_returns_fixes_of_given_content() {
+    let project = Project::new(CONFIG);
+    let mut server = Server::start(&project);
+
+    assert_eq!(
+        server.request(serde_json::json!({
+            "command": "fix",
+            "path": "new.rs",
+            "content": "fn main() {}\n",
+        })),
+        serde_json::json!({
+            "outcome": "missing",
+            "content": "// Copyright Foo\nfn main() {}\n",
+        })
+    );
+    assert!(!project.path().join("new.rs").exists());
+}
+
+#[test]
+fn serve_fixes_files() {
+    let project = Project::new(CONFIG);
+    project.file("main.rs", "fn main() {}\n");
+    let mut server = Server::start(&project);
+
+    assert_eq!(
+        server.request(serde_json::json!({"command": "fix", "path": "main.rs"})),
+        serde_json::json!({"outcome": "missing"})
+    );
+    assert_eq!(project.read("main.rs"), "// Copyright Foo\nfn main() {}\n");
+}
+
+#[test]
+fn serve_answers_invalid_requests_with_errors() {
+    let project = Project::new(CONFIG);
+    let mut server = Server::start(&project);
+
+    let response = server.request(serde_json::json!({"command": "frobnicate"}));
+    assert!(
+        response["error"]
+            .as_str()
+            .is_some_and(|error| error.contains("unknown variant `frobnicate`")),
+        "{response}"
+    );
+    // The connection stays usable
+    let response = server.request(serde_json::json!({"command": "verify", "path": "gone.rs"}));
+    assert!(response["error"].is_string(), "{response}");
+}
@@ -0,0 +1,56 @@
+// © Marcel Müller 2025, licensed under the EUPL
+
+use serde_json::json;
+
+use crate::CONFIG;
+use crate::Project;
+
+/// A tree with a valid, a missing and a mismatched Rust file and a valid shell script
+fn fixture() -> Project {
+    let project = Project::new(CONFIG);
+    project
+        .file("src/valid.rs", "// Copyright Foo\nfn valid() {}\n")
+        .file("src/missing.rs", "fn missing() {}\n")
+        .file(
+            "src/nested/mismatched.rs",
+            "// Copyright Bar\nfn mismatched() {}\n",
+        )
+        .file("src/run.sh", "# Copyright Foo\necho hello\n")
+        .file("README.md", "Not a configured language\n");
+    project
+}
+
+#[test]
+fn stats_count_the_outcomes_per_language() {
+    let run = fixture().lizenz(&["stats", "src", "--format", "json"]);
+    run.assert_code(0);
+
+    let stats = serde_json::from_str::<serde_json::Value>(&run.stdout).expect("Invalid JSON");
+    assert_eq!(
+        stats["languages"]["rust"],
+        json!({ "valid": 1, "missing": 1, "mismatched": 1, "too_small": 0, "timed_out": 0 })
+    );
+    assert_eq!(
+        stats["languages"]["bash"],
+        json!({ "valid": 1, "missing": 0, "mismatched": 0, "too_small": 0, "timed_out": 0 })
+    );
+    assert_eq!(stats["total"]["valid"], 2);
+    assert_eq!(stats["coverage"], 50.0);
+}
+
+#[test]
+fn stats_print_a_table_with_the_coverage() {
+    let run = fixture().lizenz(&["stats", "src"]);
+    run.assert_code(0);
+
+    let rust = run
+        .stdout
+        .lines()
+        .find(|line| line.starts_with("rust"))
+        .expect("No line for Rust");
+    assert_eq!(
+        rust.split_whitespace().collect::<Vec<_>>(),
+        ["rust", "1", "1", "1", "0", "0"]
+    );
+    run.assert_output("Coverage: 50.0% (2 of 4 files)");
+}
@@ -0,0 +1,182 @@
+// © Marcel Müller 2025, licensed under the EUPL
+
+use crate::Project;
+
+/// A license whose second part is free text, which differs between files
+const END_CONFIG: &str =
+    "[license]\ntext = \"Copyright Foo\\n{{end}}\\nDescribe this file here\"\n";
+
+#[test]
+fn text_after_the_end_line_is_not_verified() {
+    let project = Project::new(END_CONFIG);
+    project
+        .file("a.rs", "// Copyright Foo\n// Parses things\nfn a() {}\n")
+        .file("b.rs", "// Copyright Foo\n// Prints things\nfn b() {}\n")
+        .file("c.rs", "// Copyright Foo\nfn c() {}\n")
+        .file(
+            "wrong.rs",
+            "// Copyright Bar\n// Describe this file here\nfn d() {}\n",
+        );
+
+    project
+        .lizenz(&["verify", "a.rs", "b.rs", "c.rs"])
+        .assert_code(0);
+    project.lizenz(&["verify", "wrong.rs"]).assert_code(1);
+}
+
+#[test]
+fn fix_inserts_the_text_after_the_end_line() {
+    let project = Project::new(END_CONFIG);
+    project
+        .file("main.rs", "fn main() {}\n")
+        .lizenz(&["fix", "main.rs"])
+        .assert_code(0);
+    assert_eq!(
+        project.read("main.rs"),
+        "// Copyright Foo\n// Describe this file here\nfn main() {}\n"
+    );
+}
+
+/// A configuration requiring a `{{year}}`, which is 2025 through `SOURCE_DATE_EPOCH`
+const YEAR_CONFIG: &str = "[license]\ntext = \"Copyright {{year}} Foo\"\n";
+
+#[test]
+fn years_match_single_years_and_current_ranges() {
+    let project = Project::new(YEAR_CONFIG);
+    project
+        .file("single.rs", "// Copyright 2020 Foo\nfn main() {}\n")
+        .file("range.rs", "// Copyright 2020-2025 Foo\nfn main() {}\n")
+        .lizenz(&["verify", "single.rs", "range.rs"])
+        .assert_code(0);
+
+    project
+        .file("outdated.rs", "// Copyright 2020-2024 Foo\nfn main() {}\n")
+        .lizenz(&["verify", "outdated.rs"])
+        .assert_code(1);
+}
+
+#[test]
+fn fix_inserts_the_current_year() {
+    let project = Project::new(YEAR_CONFIG);
+    project
+        .file("main.rs", "fn main() {}\n")
+        .lizenz(&["fix", "main.rs"])
+        .assert_code(0);
+    assert_eq!(
+        project.read("main.rs"),
+        "// Copyright 2025 Foo\nfn main() {}\n"
+    );
+}
+
+#[test]
+fn update_years_extends_years_to_ranges() {
+    let project = Project::new(YEAR_CONFIG);
+    project
+        .file("single.rs", "// Copyright 2020 Foo\nfn main() {}\n")
+        .file("outdated.rs", "// Copyright 2020-2024 Foo\nfn main() {}\n")
+        .file("current.rs", "// Copyright 2025 Foo\nfn main() {}\n")
+        .lizenz(&[
+            "fix",
+            "--update-years",
+            "single.rs",
+            "outdated.rs",
+            "current.rs",
+        ])
+        .assert_code(0);
+
+    let updated = "// Copyright 2020-2025 Foo\nfn main() {}\n";
+    assert_eq!(project.read("single.rs"), updated);
+    assert_eq!(project.read("outdated.rs"), updated);
+    assert_eq!(
+        project.read("current.rs"),
+        "// Copyright 2025 Foo\nfn main() {}\n"
+    );
+}
+
+#[test]
+fn fix_without_update_years_keeps_single_years() {
+    let project = Project::new(YEAR_CONFIG);
+    project
+        .file("main.rs", "// Copyright 2020 Foo\nfn main() {}\n")
+        .lizenz(&["fix", "main.rs"])
+        .assert_code(0);
+    assert_eq!(
+        project.read("main.rs"),
+        "// Copyright 2020 Foo\nfn main() {}\n"
+    );
+}
+
+/// A configuration whose headers name the path of their file
+const FILE_CONFIG: &str = "[license]\ntext = \"Copyright Foo\\nFile: {{file}}\"\n";
+
+#[test]
+fn file_paths_are_relative_to_the_current_directory_outside_of_git() {
+    let project = Project::new(FILE_CONFIG);
+    project
+        .file("a.rs", "// Copyright Foo\n// File: a.rs\nfn a() {}\n")
+        .file(
+            "src/b.rs",
+            "// Copyright Foo\n// File: src/b.rs\nfn b() {}\n",
+        )
+        .lizenz(&["verify", "a.rs", "./src/b.rs", "src/../src/b.rs"])
+        .assert_code(0);
+}
+
+#[test]
+fn file_paths_are_relative_to_the_repository() {
+    let project = Project::new(FILE_CONFIG);
+    project
+        .file(
+            "src/main.rs",
+            "// Copyright Foo\n// File: src/main.rs\nfn main() {}\n",
+        )
+        .commit_all();
+
+    let run = project
+        .command(&["--config-path", "../lizenz.toml", "verify", "main.rs"])
+        .current_dir(project.path().join("src"))
+        .output()
+        .expect("Could not run lizenz");
+    assert_eq!(run.status.code(), Some(0), "{run:?}");
+}
+
+#[test]
+fn file_paths_are_relative_to_the_root() {
+    let project = Project::new(FILE_CONFIG);
+    project.file("src/main.rs", "fn main() {}\n");
+    let scratch = tempfile::tempdir().expect("Could not create a directory");
+    let root = project.path().to_str().unwrap();
+
+    let run = project
+        .command(&["--root", root, "fix", "./src/main.rs"])
+        .current_dir(scratch.path())
+        .output()
+        .expect("Could not run lizenz");
+    assert_eq!(run.status.code(), Some(0), "{run:?}");
+    assert_eq!(
+        project.read("src/main.rs"),
+        "// Copyright Foo\n// File: src/main.rs\nfn main() {}\n"
+    );
+}
+
+#[test]
+fn fix_inserts_the_path_of_each_file() {
+    let project = Project::new(FILE_CONFIG);
+    project
+        .file("a.rs", "fn a() {}\n")
+        .file("src/b.rs", "fn b() {}\n")
+        .lizenz(&["fix", "./a.rs", "src/b.rs"])
+        .assert_code(0);
+
+    assert_eq!(
+        project.read("a.rs"),
+        "// Copyright Foo\n// File: a.rs\nfn a() {}\n"
+    );
+    assert_eq!(
+        project.read("src/b.rs"),
+        "// Copyright Foo\n// File: src/b.rs\nfn b() {}\n"
+    );
+    project
+        .lizenz(&["verify", "a.rs", "src/b.rs"])
+        .assert_code(0);
+}
@@ -0,0 +1,82 @@
+// © Marcel Müller 2025, licensed under the EUPL
+
+use crate::CONFIG;
+use crate::Project;
+
+/// A project with a valid file that takes far longer than a millisecond to parse, and a small
+/// valid one
+fn project() -> Project {
+    let project = Project::new(CONFIG);
+    project
+        .file(
+            "large.rs",
+            &format!(
+                "// Copyright Foo\n{}",
+                "fn a() { let x = 1; }\n".repeat(100_000)
+            ),
+        )
+        .file("small.rs", "// Copyright Foo\nfn main() {}\n");
+    project
+}
+
+#[test]
+fn files_taking_longer_than_the_timeout_are_reported() {
+    project()
+        .lizenz(&["--timeout-ms", "1", "verify", "large.rs", "small.rs"])
+        .assert_code(1)
+        .assert_output("large.rs: parsing took longer than the timeout of 1 ms")
+        .assert_no_output("small.rs");
+}
+
+#[test]
+fn files_parsed_within_the_timeout_are_verified() {
+    project()
+        .lizenz(&["--timeout-ms", "600000", "verify", "large.rs", "small.rs"])
+        .assert_code(0);
+}
+
+#[test]
+fn timed_out_files_fail_regardless_of_fail_on() {
+    project()
+        .lizenz(&[
+            "--timeout-ms",
+            "1",
+            "verify",
+            "--fail-on",
+            "missing",
+            "large.rs",
+        ])
+        .assert_code(1)
+        .assert_output("large.rs: parsing took longer than the timeout of 1 ms");
+}
+
+#[test]
+fn timed_out_files_are_not_fixed() {
+    let project = project();
+    let content = project.read("large.rs");
+    project
+        .lizenz(&["--timeout-ms", "1", "fix", "large.rs"])
+        .assert_code(1)
+        .assert_output("Not fixing large.rs, as parsing it took longer than the timeout");
+    assert_eq!(project.read("large.rs"), content);
+}
+
+#[test]
+fn timeouts_are_explained_and_reported_as_json() {
+    let project = project();
+    project
+        .lizenz(&["--timeout-ms", "1", "verify", "--explain", "large.rs"])
+        .assert_code(1)
+        .assert_output("  header not searched: parsing took longer than 1 ms\n");
+    project
+        .lizenz(&[
+            "--timeout-ms",
+            "1",
+            "verify",
+            "--format",
+            "json",
+            "large.rs",
+        ])
+        .assert_code(1)
+        .assert_output("\"outcome\": \"timed_out\"");
+}